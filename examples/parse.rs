@@ -5,7 +5,7 @@ extern crate trackable;
 
 use clap::{App, Arg};
 use mpeg2ts::pes::{PesPacketReader, ReadPesPacket};
-use mpeg2ts::ts::{ReadTsPacket, TsHeader, TsPacketReader, TsPacketWriter, WriteTsPacket};
+use mpeg2ts::ts::{ReadTsPacket, TsPacketReader, TsPacketWriter, WriteTsPacket};
 use std::io::Write;
 use trackable::error::Failure;
 
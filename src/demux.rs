@@ -0,0 +1,327 @@
+//! Push-style demultiplexer.
+use std::mem;
+
+use pes::{PesPacket, PesPacketDecoder};
+use ts::{
+    ContinuityEvent, PacketFormat, PidFilter, ProgramEvent, ProgramMap, TableEvent, TsPacket,
+    TsPacketReader,
+};
+use {ErrorKind, ErrorPolicy, PacketError, Result};
+
+/// Event raised by `Demuxer`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum DemuxEvent {
+    /// A TS packet selected by the PID filter.
+    TsPacket(TsPacket),
+
+    /// A completed PSI table.
+    Table(TableEvent),
+
+    /// A completed PES packet.
+    PesPacket(PesPacket<Vec<u8>>),
+}
+
+/// Sans-IO transport stream demultiplexer.
+///
+/// The callers feed byte chunks of any size by `push()`,
+/// and take the parsed TS packets, PSI tables and PES packets by `take_events()`.
+/// The bytes of incomplete packets are buffered inside the demuxer.
+///
+/// # Examples
+///
+/// ```
+/// use mpeg2ts::demux::{DemuxEvent, Demuxer};
+///
+/// let mut demuxer = Demuxer::new();
+/// demuxer.push(&[0x47, 0x1F, 0xFF, 0x10]).unwrap();
+/// demuxer.push(&[0xFF; 184]).unwrap();
+/// assert!(demuxer.take_events().is_empty()); // Waiting for the next sync byte
+///
+/// demuxer.finish().unwrap();
+/// let events = demuxer.take_events();
+/// assert_eq!(events.len(), 1);
+/// assert!(matches!(events[0], DemuxEvent::TsPacket(_)));
+/// ```
+#[derive(Debug)]
+pub struct Demuxer {
+    reader: TsPacketReader<()>,
+    pes_decoder: PesPacketDecoder,
+    events: Vec<DemuxEvent>,
+    finished: bool,
+}
+impl Demuxer {
+    /// Makes a new `Demuxer` instance.
+    pub fn new() -> Self {
+        Self::with_format(PacketFormat::Ts)
+    }
+
+    /// Makes a new `Demuxer` instance that parses packets of the given format.
+    pub fn with_format(format: PacketFormat) -> Self {
+        let mut reader = TsPacketReader::with_format((), format);
+        reader.enable_table_events();
+        Demuxer {
+            reader,
+            pes_decoder: PesPacketDecoder::new(),
+            events: Vec::new(),
+            finished: false,
+        }
+    }
+
+    /// Returns the packet format of the stream.
+    pub fn format(&self) -> PacketFormat {
+        self.reader.format()
+    }
+
+    /// Enables or disables the Reed-Solomon decoding of `PacketFormat::Dvb` packets.
+    ///
+    /// See `TsPacketReader::set_reed_solomon_decoding()` for details.
+    pub fn set_reed_solomon_decoding(&mut self, enabled: bool) {
+        self.reader.set_reed_solomon_decoding(enabled);
+    }
+
+    /// Sets the policy for the packets that cannot be decoded.
+    ///
+    /// The default policy is `ErrorPolicy::Lenient`.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.reader.set_error_policy(policy);
+    }
+
+    /// Returns the number of the packets dropped due to decoding errors.
+    pub fn error_count(&self) -> u64 {
+        self.reader.error_count()
+    }
+
+    /// Sets the filter that selects the TS packets to be demultiplexed.
+    ///
+    /// Only the selected packets raise `DemuxEvent::TsPacket` and are assembled into PES packets.
+    /// The default filter is `PidFilter::All`.
+    pub fn set_pid_filter(&mut self, filter: PidFilter) {
+        self.reader.set_pid_filter(filter);
+    }
+
+    /// Returns the total number of bytes skipped to (re)gain packet synchronization.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.reader.skipped_bytes()
+    }
+
+    /// Returns the programs of the stream built from the PAT and PMTs parsed so far.
+    pub fn program_map(&self) -> &ProgramMap {
+        self.reader.program_map()
+    }
+
+    /// Takes the program change events raised since the last call.
+    pub fn take_program_events(&mut self) -> Vec<ProgramEvent> {
+        self.reader.take_program_events()
+    }
+
+    /// Takes the continuity errors detected since the last call.
+    pub fn take_continuity_events(&mut self) -> Vec<ContinuityEvent> {
+        self.reader.take_continuity_events()
+    }
+
+    /// Feeds a chunk of the stream.
+    ///
+    /// A packet is parsed once the packet synchronizer has confirmed its boundaries.
+    /// Locking onto the stream (initially or after losing the synchronization)
+    /// needs the sync bytes of three consecutive packets,
+    /// and a locked packet is withheld until the sync byte of the next packet arrives.
+    /// So the events of the last packet in the buffered bytes are raised by a later `push()`,
+    /// or by `finish()` at the end of the stream.
+    /// An empty chunk is ignored and does not flush the withheld packet.
+    ///
+    /// If an error is returned (e.g., `ErrorPolicy::Strict` is set),
+    /// the events raised before the error can still be taken by `take_events()`.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        track_assert!(!self.finished, ErrorKind::Other, "Already finished");
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.reader.push_bytes(bytes);
+        track!(self.parse_packets())
+    }
+
+    /// Notifies the end of the stream.
+    ///
+    /// The remaining buffered bytes are parsed and the PES packets being assembled are flushed.
+    /// No more chunks can be fed after calling this method.
+    pub fn finish(&mut self) -> Result<()> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        self.reader.push_bytes(&[]);
+        track!(self.parse_packets())?;
+        while let Some(packet) = track!(self.pes_decoder.flush())? {
+            self.events.push(DemuxEvent::PesPacket(packet));
+        }
+        Ok(())
+    }
+
+    /// Takes the events raised since the last call.
+    ///
+    /// The table and PES packet events are raised before the event of the TS packet
+    /// that completes them.
+    pub fn take_events(&mut self) -> Vec<DemuxEvent> {
+        mem::take(&mut self.events)
+    }
+
+    fn parse_packets(&mut self) -> Result<()> {
+        loop {
+            let result = self.reader.decode_next_packet();
+            let tables = self.reader.take_table_events();
+            self.events.extend(tables.into_iter().map(DemuxEvent::Table));
+            let packet = match track!(result)? {
+                None => return Ok(()),
+                Some(packet) => packet,
+            };
+            let continuity_error = self.reader.decoded_continuity_error();
+            let result = self
+                .pes_decoder
+                .process_ts_packet_with_continuity_error(&packet, continuity_error);
+            match result {
+                Ok(None) => {}
+                Ok(Some(pes_packet)) => self.events.push(DemuxEvent::PesPacket(pes_packet)),
+                Err(e) => {
+                    let error = PacketError {
+                        error: e,
+                        pid: packet.header.pid,
+                        offset: packet.position.map(|p| p.offset),
+                    };
+                    track!(self.reader.handle_error(error))?;
+                }
+            }
+            self.events.push(DemuxEvent::TsPacket(packet));
+        }
+    }
+}
+impl Default for Demuxer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use pes::{PesPacketReader, ReadPesPacket};
+    use test_util::{packet, pes, stream_bytes_with, ES_PID, PMT_PID};
+    use ts::payload::Bytes;
+    use ts::TsPayload;
+
+    use super::*;
+
+    fn stream_bytes() -> Vec<u8> {
+        stream_bytes_with(&[
+            packet(ES_PID, 0, pes(&[1; 100])),
+            packet(ES_PID, 1, TsPayload::Raw(Bytes::new(&[2; 184]).unwrap())),
+            packet(ES_PID, 2, pes(&[3; 10])),
+        ])
+    }
+
+    #[test]
+    fn demuxer() {
+        let bytes = stream_bytes();
+        let mut demuxer = Demuxer::new();
+        let mut events = Vec::new();
+        for chunk in bytes.chunks(7) {
+            track_try_unwrap!(demuxer.push(chunk));
+            events.extend(demuxer.take_events());
+        }
+        track_try_unwrap!(demuxer.finish());
+        events.extend(demuxer.take_events());
+        assert!(demuxer.push(&[0x47]).is_err());
+
+        assert_eq!(events.len(), 9);
+        assert!(matches!(events[0], DemuxEvent::Table(TableEvent::Pat(_))));
+        assert!(matches!(events[1], DemuxEvent::TsPacket(_)));
+        assert!(matches!(
+            events[2],
+            DemuxEvent::Table(TableEvent::Pmt { ref pid, .. }) if pid.as_u16() == PMT_PID
+        ));
+        for event in &events[3..6] {
+            assert!(matches!(*event, DemuxEvent::TsPacket(_)));
+        }
+        match events[6] {
+            DemuxEvent::PesPacket(ref p) => {
+                assert_eq!(p.data.len(), 100 + 184);
+                assert_eq!(p.data[100..], [2; 184][..]);
+                let first = p.first_ts_packet_position.unwrap();
+                let last = p.last_ts_packet_position.unwrap();
+                assert_eq!((first.offset, first.index), (376, 2));
+                assert_eq!((last.offset, last.index), (564, 3));
+            }
+            ref e => panic!("Unexpected event: {:?}", e),
+        }
+        assert!(matches!(events[7], DemuxEvent::TsPacket(_)));
+        match events[8] {
+            DemuxEvent::PesPacket(ref p) => assert_eq!(p.data, [3; 10]),
+            ref e => panic!("Unexpected event: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn pes_continuity_errors() {
+        let raw = |counter, data| packet(ES_PID, counter, TsPayload::Raw(Bytes::new(data).unwrap()));
+        let bytes = stream_bytes_with(&[
+            packet(ES_PID, 0, pes(&[1; 100])),
+            raw(1, &[2; 184]),
+            raw(1, &[2; 184]), // Duplicate
+            raw(3, &[4; 184]), // Lost the packet of the counter 2
+            packet(ES_PID, 4, pes(&[5; 10])),
+            raw(5, &[6; 184]),
+            raw(5, &[6; 184]), // Duplicate
+        ]);
+        let mut expected = vec![5; 10];
+        expected.extend_from_slice(&[6; 184]);
+
+        let mut demuxer = Demuxer::new();
+        track_try_unwrap!(demuxer.push(&bytes));
+        track_try_unwrap!(demuxer.finish());
+        let pes_packets = demuxer
+            .take_events()
+            .into_iter()
+            .filter_map(|e| match e {
+                DemuxEvent::PesPacket(p) => Some(p.data),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(pes_packets, [expected.clone()]);
+        assert_eq!(demuxer.take_continuity_events().len(), 3);
+
+        let mut reader = PesPacketReader::new(TsPacketReader::new(&bytes[..]));
+        let packet = track_try_unwrap!(reader.read_pes_packet()).unwrap();
+        assert_eq!(packet.data, expected);
+        assert!(track_try_unwrap!(reader.read_pes_packet()).is_none());
+    }
+
+    #[test]
+    fn pes_error_offset() {
+        let mut invalid = pes(&[1; 10]);
+        if let TsPayload::Pes(ref mut pes) = invalid {
+            pes.pes_packet_len = 1; // Shorter than the optional PES header
+        }
+        let bytes = stream_bytes_with(&[packet(ES_PID, 0, invalid)]);
+        let expected = [(ES_PID, Some(TsPacket::SIZE as u64 * 2))];
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        let mut demuxer = Demuxer::new();
+        demuxer.set_error_policy(ErrorPolicy::Callback(Box::new(move |e| {
+            errors_clone.lock().unwrap().push((e.pid.as_u16(), e.offset));
+        })));
+        track_try_unwrap!(demuxer.push(&bytes));
+        track_try_unwrap!(demuxer.finish());
+        assert_eq!(*errors.lock().unwrap(), expected);
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let errors_clone = errors.clone();
+        let mut reader = PesPacketReader::new(TsPacketReader::new(&bytes[..]));
+        reader.set_error_policy(ErrorPolicy::Callback(Box::new(move |e| {
+            errors_clone.lock().unwrap().push((e.pid.as_u16(), e.offset));
+        })));
+        while track_try_unwrap!(reader.read_pes_packet()).is_some() {}
+        assert_eq!(*errors.lock().unwrap(), expected);
+    }
+}
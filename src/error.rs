@@ -1,9 +1,12 @@
+use std::fmt;
 use trackable::error::{ErrorKind as TrackableErrorKind, TrackableError};
 
+use ts::Pid;
+use Result;
+
 /// This crate specific `Error` type.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, TrackableError)]
 pub struct Error(TrackableError<ErrorKind>);
-derive_traits_for_trackable_error_newtype!(Error, ErrorKind);
 
 /// Possible error kinds.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -14,3 +17,79 @@ pub enum ErrorKind {
     Other,
 }
 impl TrackableErrorKind for ErrorKind {}
+
+/// Policy for the packets that cannot be decoded.
+///
+/// It is used by `TsPacketReader` and `PesPacketReader`.
+#[derive(Default)]
+pub enum ErrorPolicy {
+    /// Fails on the first error.
+    Strict,
+
+    /// Skips the erroneous packets and counts them.
+    #[default]
+    Lenient,
+
+    /// Skips the erroneous packets, counts them and hands each error to the callback.
+    Callback(Box<dyn FnMut(&PacketError) + Send>),
+}
+impl fmt::Debug for ErrorPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ErrorPolicy::Strict => write!(f, "Strict"),
+            ErrorPolicy::Lenient => write!(f, "Lenient"),
+            ErrorPolicy::Callback(_) => write!(f, "Callback(_)"),
+        }
+    }
+}
+
+/// Error that occurred while decoding a packet.
+#[derive(Debug, Clone)]
+pub struct PacketError {
+    /// The cause of the error.
+    pub error: Error,
+
+    /// The PID of the TS packet.
+    pub pid: Pid,
+
+    /// The byte offset of the TS packet in the stream (if known).
+    pub offset: Option<u64>,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct ErrorHandler {
+    policy: ErrorPolicy,
+    count: u64,
+}
+impl ErrorHandler {
+    pub fn set_policy(&mut self, policy: ErrorPolicy) {
+        self.policy = policy;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_strict(&self) -> bool {
+        matches!(self.policy, ErrorPolicy::Strict)
+    }
+
+    /// Handles the error according to the policy.
+    ///
+    /// It returns `Err` only if the policy is `ErrorPolicy::Strict`.
+    pub fn handle(&mut self, error: PacketError) -> Result<()> {
+        self.count += 1;
+        match self.policy {
+            ErrorPolicy::Strict => return Err(track!(error.error)),
+            ErrorPolicy::Lenient => {}
+            ErrorPolicy::Callback(ref mut f) => f(&error),
+        }
+        log::trace!(
+            "Dropped packet: pid={}, offset={:?}, error={}",
+            error.pid.as_u16(),
+            error.offset,
+            error.error
+        );
+        Ok(())
+    }
+}
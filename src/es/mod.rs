@@ -1,6 +1,6 @@
 //! Elementary stream.
 pub use self::stream_id::StreamId;
-pub use self::stream_type::StreamType;
+pub use self::stream_type::{StreamType, UnknownStreamType};
 
 mod stream_id;
 mod stream_type;
@@ -16,10 +16,10 @@ impl StreamId {
     /// Maximum value of the identifiers for video streams.
     pub const VIDEO_MAX: u8 = 0xEF;
 
-    //Synchronous KLV identifier
+    /// Identifier for synchronous KLV streams.
     pub const KLV_SYNC: u8 = 0xBD;
 
-    //Synchronous KLV identifier
+    /// Identifier for asynchronous KLV streams.
     pub const KLV_ASYNC: u8 = 0xFC;
 
 
@@ -35,7 +35,7 @@ impl StreamId {
     /// If `id` is not between `AUDIO_MIN` and `AUDIO_MAX`, it will return an `ErrorKind::InvalidInput` error.
     pub fn new_audio(id: u8) -> Result<Self> {
         track_assert!(
-            Self::AUDIO_MIN <= id && id <= Self::AUDIO_MAX,
+            (Self::AUDIO_MIN..=Self::AUDIO_MAX).contains(&id),
             ErrorKind::InvalidInput,
             "Not an audio ID: {}",
             id
@@ -50,7 +50,7 @@ impl StreamId {
     /// If `id` is not between `VIDEO_MIN` and `VIDEO_MAX`, it will return an `ErrorKind::InvalidInput` error.
     pub fn new_video(id: u8) -> Result<Self> {
         track_assert!(
-            Self::VIDEO_MIN <= id && id <= Self::VIDEO_MAX,
+            (Self::VIDEO_MIN..=Self::VIDEO_MAX).contains(&id),
             ErrorKind::InvalidInput,
             "Not a video ID: {}",
             id
@@ -75,15 +75,15 @@ impl StreamId {
 
     /// Returns `true` if this contains a klv identifier, otherwise `false`.
     pub fn is_klv(&self) -> bool {
-        return Self::is_async_klv(&self) || Self::is_sync_klv(&self)
+        self.is_async_klv() || self.is_sync_klv()
     }
 
-    //Returns true if this contains a synchronous klv identifier, otherwise `false`.
+    /// Returns `true` if this contains a synchronous klv identifier, otherwise `false`.
     pub fn is_sync_klv(&self) -> bool {
         self.0 == Self::KLV_SYNC
     }
 
-    //Returns true if this contains an asynchronous klv identifier, otherwise `false`.
+    /// Returns `true` if this contains an asynchronous klv identifier, otherwise `false`.
     pub fn is_async_klv(&self) -> bool {
         self.0 == Self::KLV_ASYNC
     }
@@ -1,59 +1,106 @@
-use {ErrorKind, Result};
+use Result;
 
 /// Elementary stream type.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum StreamType {
-    Other = 0x0,
-    Mpeg1Video = 0x01,
-    Mpeg2Video = 0x02,
-    Mpeg1Audio = 0x03,
-    Mpeg2HalvedSampleRateAudio = 0x04,
-    Mpeg2TabledData = 0x05,
-    Mpeg2PacketizedData = 0x06,
-    Mheg = 0x07,
-    DsmCc = 0x08,
-    AuxiliaryData09 = 0x09,
-    DsmCcMultiprotocolEncapsulation = 0x0A,
-    DsmCcUnMessages = 0x0B,
-    DsmCcStreamDescriptors = 0x0C,
-    DsmCcTabledData = 0x0D,
-    AuxiliaryData0e = 0x0E,
-    AdtsAac = 0x0F,
-    Mpeg4H263BasedVideo = 0x10,
-    Mpeg4LoasMultiFormatFramedAudio = 0x11,
-    Mpeg4FlexMux = 0x12,
-    Mpeg4FlexMuxInTable = 0x13,
-    DsmCcSynchronizedDownloadProtocol = 0x14,
-    PacketizedMetadata = 0x15,
-    SectionedMetadata = 0x16,
-    DsmCcDataCarouselMetadata = 0x17,
-    DsmCcObjectCarouselMetadata = 0x18,
-    SynchronizedDownloadProtocolMetadata = 0x19,
-    Ipmp = 0x1A,
-    H264 = 0x1B,
-    H265 = 0x24,
-    ChineseVideoStandard = 0x42,
-    PcmAudio = 0x80,
-    DolbyDigitalUpToSixChannelAudio = 0x81,
-    Dts6ChannelAudio = 0x82,
-    DolbyTrueHdLosslessAudio = 0x83,
-    DolbyDigitalPlusUpTo16ChannelAudio = 0x84,
-    Dts8ChannelAudio = 0x85,
-    Dts8ChannelLosslessAudio = 0x86,
-    DolbyDigitalPlusUpTo16ChannelAudioForAtsc = 0x87,
-    PresentationGraphicStream = 0x90,
-    AtscDsmCcNetworkResourcesTable = 0x91,
-    DigiCipher2Text = 0xC0,
-    DolbyDigitalUpToSixChannelAudioWithAes128Cbc = 0xC1,
-    DolbyDigitalPlusUpToSixChannelAudioWithAes128Cbc = 0xC2,
-    AdtsAacWithAes128Cbc = 0xCF,
-    UltraHdVideo = 0xD1,
-    H264WithAes128Cbc = 0xDB,
-    MicrosoftWindowsMediaVideo9 = 0xEA,
+    Mpeg1Video,
+    Mpeg2Video,
+    Mpeg1Audio,
+    Mpeg2HalvedSampleRateAudio,
+    Mpeg2TabledData,
+    Mpeg2PacketizedData,
+    Mheg,
+    DsmCc,
+    AuxiliaryData09,
+    DsmCcMultiprotocolEncapsulation,
+    DsmCcUnMessages,
+    DsmCcStreamDescriptors,
+    DsmCcTabledData,
+    AuxiliaryData0e,
+    AdtsAac,
+    Mpeg4H263BasedVideo,
+    Mpeg4LoasMultiFormatFramedAudio,
+    Mpeg4FlexMux,
+    Mpeg4FlexMuxInTable,
+    DsmCcSynchronizedDownloadProtocol,
+    PacketizedMetadata,
+    SectionedMetadata,
+    DsmCcDataCarouselMetadata,
+    DsmCcObjectCarouselMetadata,
+    SynchronizedDownloadProtocolMetadata,
+    /// ISO/IEC 13818-11 (MPEG-2 IPMP) stream.
+    Ipmp,
+    H264,
+    Mpeg4RawAudio,
+    Mpeg4Text,
+    Mpeg4AuxiliaryVideo,
+    /// H.264 SVC (Annex G) sub-bitstream.
+    SvcVideo,
+    /// H.264 MVC (Annex H) sub-bitstream.
+    MvcVideo,
+    Jpeg2000Video,
+    Mpeg2StereoscopicAdditionalView,
+    H264StereoscopicAdditionalView,
+    H265,
+    /// HEVC temporal video subset (a sub-stream of `H265`).
+    H265TemporalVideoSubset,
+    MvcdVideo,
+    TimelineAndExternalMediaInformation,
+    /// SHVC (HEVC Annex F/G) enhancement sub-partition.
+    H265EnhancementSubPartition,
+    /// SHVC (HEVC Annex F/G) temporal enhancement sub-partition.
+    H265TemporalEnhancementSubPartition,
+    /// MV-HEVC (HEVC Annex G/H) enhancement sub-partition.
+    MvHevcEnhancementSubPartition,
+    /// MV-HEVC (HEVC Annex G/H) temporal enhancement sub-partition.
+    MvHevcTemporalEnhancementSubPartition,
+    GreenAccessUnits,
+    MpegH3dAudioMain,
+    MpegH3dAudioAuxiliary,
+    QualityAccessUnits,
+    MediaOrchestrationAccessUnits,
+    H265TileSubstream,
+    JpegXsVideo,
+    /// VVC video.
+    H266,
+    /// VVC temporal video subset (a sub-stream of `H266`).
+    H266TemporalVideoSubset,
+    EvcVideo,
+    ChineseVideoStandard,
+    /// ISO/IEC 14496 (MPEG-4 IPMP) stream.
+    IpmpStream,
+    PcmAudio,
+    DolbyDigitalUpToSixChannelAudio,
+    Dts6ChannelAudio,
+    DolbyTrueHdLosslessAudio,
+    DolbyDigitalPlusUpTo16ChannelAudio,
+    Dts8ChannelAudio,
+    Dts8ChannelLosslessAudio,
+    DolbyDigitalPlusUpTo16ChannelAudioForAtsc,
+    PresentationGraphicStream,
+    AtscDsmCcNetworkResourcesTable,
+    DigiCipher2Text,
+    DolbyDigitalUpToSixChannelAudioWithAes128Cbc,
+    DolbyDigitalPlusUpToSixChannelAudioWithAes128Cbc,
+    AdtsAacWithAes128Cbc,
+    UltraHdVideo,
+    H264WithAes128Cbc,
+    MicrosoftWindowsMediaVideo9,
+
+    /// Stream type that is not listed above (e.g., reserved or user private values).
+    Unknown(UnknownStreamType),
 }
 impl StreamType {
+    /// SCTE-35 splice information.
+    ///
+    /// The value `0x86` is shared with `Dts8ChannelLosslessAudio` of the Blu-ray streams.
+    pub const SCTE_35: StreamType = StreamType::Dts8ChannelLosslessAudio;
+
     /// Makes a `StreamType` instance that associated with the given number.
+    ///
+    /// The unknown values are kept as `StreamType::Unknown`, so this never fails.
+    /// The values listed in `StreamType` are never mapped to `StreamType::Unknown`.
     pub fn from_u8(n: u8) -> Result<Self> {
         Ok(match n {
             0x01 => StreamType::Mpeg1Video,
@@ -83,8 +130,34 @@ impl StreamType {
             0x19 => StreamType::SynchronizedDownloadProtocolMetadata,
             0x1A => StreamType::Ipmp,
             0x1B => StreamType::H264,
+            0x1C => StreamType::Mpeg4RawAudio,
+            0x1D => StreamType::Mpeg4Text,
+            0x1E => StreamType::Mpeg4AuxiliaryVideo,
+            0x1F => StreamType::SvcVideo,
+            0x20 => StreamType::MvcVideo,
+            0x21 => StreamType::Jpeg2000Video,
+            0x22 => StreamType::Mpeg2StereoscopicAdditionalView,
+            0x23 => StreamType::H264StereoscopicAdditionalView,
             0x24 => StreamType::H265,
+            0x25 => StreamType::H265TemporalVideoSubset,
+            0x26 => StreamType::MvcdVideo,
+            0x27 => StreamType::TimelineAndExternalMediaInformation,
+            0x28 => StreamType::H265EnhancementSubPartition,
+            0x29 => StreamType::H265TemporalEnhancementSubPartition,
+            0x2A => StreamType::MvHevcEnhancementSubPartition,
+            0x2B => StreamType::MvHevcTemporalEnhancementSubPartition,
+            0x2C => StreamType::GreenAccessUnits,
+            0x2D => StreamType::MpegH3dAudioMain,
+            0x2E => StreamType::MpegH3dAudioAuxiliary,
+            0x2F => StreamType::QualityAccessUnits,
+            0x30 => StreamType::MediaOrchestrationAccessUnits,
+            0x31 => StreamType::H265TileSubstream,
+            0x32 => StreamType::JpegXsVideo,
+            0x33 => StreamType::H266,
+            0x34 => StreamType::H266TemporalVideoSubset,
+            0x35 => StreamType::EvcVideo,
             0x42 => StreamType::ChineseVideoStandard,
+            0x7F => StreamType::IpmpStream,
             0x80 => StreamType::PcmAudio,
             0x81 => StreamType::DolbyDigitalUpToSixChannelAudio,
             0x82 => StreamType::Dts6ChannelAudio,
@@ -102,8 +175,125 @@ impl StreamType {
             0xD1 => StreamType::UltraHdVideo,
             0xDB => StreamType::H264WithAes128Cbc,
             0xEA => StreamType::MicrosoftWindowsMediaVideo9,
-            _ => StreamType::Other 
-            //_ => track_panic!(ErrorKind::InvalidInput, "Unknown stream type: {}", n),
+            _ => StreamType::Unknown(UnknownStreamType(n)),
         })
     }
+
+    /// Returns the number associated with the stream type.
+    pub fn as_u8(&self) -> u8 {
+        match *self {
+            StreamType::Mpeg1Video => 0x01,
+            StreamType::Mpeg2Video => 0x02,
+            StreamType::Mpeg1Audio => 0x03,
+            StreamType::Mpeg2HalvedSampleRateAudio => 0x04,
+            StreamType::Mpeg2TabledData => 0x05,
+            StreamType::Mpeg2PacketizedData => 0x06,
+            StreamType::Mheg => 0x07,
+            StreamType::DsmCc => 0x08,
+            StreamType::AuxiliaryData09 => 0x09,
+            StreamType::DsmCcMultiprotocolEncapsulation => 0x0A,
+            StreamType::DsmCcUnMessages => 0x0B,
+            StreamType::DsmCcStreamDescriptors => 0x0C,
+            StreamType::DsmCcTabledData => 0x0D,
+            StreamType::AuxiliaryData0e => 0x0E,
+            StreamType::AdtsAac => 0x0F,
+            StreamType::Mpeg4H263BasedVideo => 0x10,
+            StreamType::Mpeg4LoasMultiFormatFramedAudio => 0x11,
+            StreamType::Mpeg4FlexMux => 0x12,
+            StreamType::Mpeg4FlexMuxInTable => 0x13,
+            StreamType::DsmCcSynchronizedDownloadProtocol => 0x14,
+            StreamType::PacketizedMetadata => 0x15,
+            StreamType::SectionedMetadata => 0x16,
+            StreamType::DsmCcDataCarouselMetadata => 0x17,
+            StreamType::DsmCcObjectCarouselMetadata => 0x18,
+            StreamType::SynchronizedDownloadProtocolMetadata => 0x19,
+            StreamType::Ipmp => 0x1A,
+            StreamType::H264 => 0x1B,
+            StreamType::Mpeg4RawAudio => 0x1C,
+            StreamType::Mpeg4Text => 0x1D,
+            StreamType::Mpeg4AuxiliaryVideo => 0x1E,
+            StreamType::SvcVideo => 0x1F,
+            StreamType::MvcVideo => 0x20,
+            StreamType::Jpeg2000Video => 0x21,
+            StreamType::Mpeg2StereoscopicAdditionalView => 0x22,
+            StreamType::H264StereoscopicAdditionalView => 0x23,
+            StreamType::H265 => 0x24,
+            StreamType::H265TemporalVideoSubset => 0x25,
+            StreamType::MvcdVideo => 0x26,
+            StreamType::TimelineAndExternalMediaInformation => 0x27,
+            StreamType::H265EnhancementSubPartition => 0x28,
+            StreamType::H265TemporalEnhancementSubPartition => 0x29,
+            StreamType::MvHevcEnhancementSubPartition => 0x2A,
+            StreamType::MvHevcTemporalEnhancementSubPartition => 0x2B,
+            StreamType::GreenAccessUnits => 0x2C,
+            StreamType::MpegH3dAudioMain => 0x2D,
+            StreamType::MpegH3dAudioAuxiliary => 0x2E,
+            StreamType::QualityAccessUnits => 0x2F,
+            StreamType::MediaOrchestrationAccessUnits => 0x30,
+            StreamType::H265TileSubstream => 0x31,
+            StreamType::JpegXsVideo => 0x32,
+            StreamType::H266 => 0x33,
+            StreamType::H266TemporalVideoSubset => 0x34,
+            StreamType::EvcVideo => 0x35,
+            StreamType::ChineseVideoStandard => 0x42,
+            StreamType::IpmpStream => 0x7F,
+            StreamType::PcmAudio => 0x80,
+            StreamType::DolbyDigitalUpToSixChannelAudio => 0x81,
+            StreamType::Dts6ChannelAudio => 0x82,
+            StreamType::DolbyTrueHdLosslessAudio => 0x83,
+            StreamType::DolbyDigitalPlusUpTo16ChannelAudio => 0x84,
+            StreamType::Dts8ChannelAudio => 0x85,
+            StreamType::Dts8ChannelLosslessAudio => 0x86,
+            StreamType::DolbyDigitalPlusUpTo16ChannelAudioForAtsc => 0x87,
+            StreamType::PresentationGraphicStream => 0x90,
+            StreamType::AtscDsmCcNetworkResourcesTable => 0x91,
+            StreamType::DigiCipher2Text => 0xC0,
+            StreamType::DolbyDigitalUpToSixChannelAudioWithAes128Cbc => 0xC1,
+            StreamType::DolbyDigitalPlusUpToSixChannelAudioWithAes128Cbc => 0xC2,
+            StreamType::AdtsAacWithAes128Cbc => 0xCF,
+            StreamType::UltraHdVideo => 0xD1,
+            StreamType::H264WithAes128Cbc => 0xDB,
+            StreamType::MicrosoftWindowsMediaVideo9 => 0xEA,
+            StreamType::Unknown(t) => t.as_u8(),
+        }
+    }
+}
+
+/// Stream type value that has no variant of its own in `StreamType`.
+///
+/// It can be made only by `StreamType::from_u8()`,
+/// so a `StreamType::Unknown` never holds the value of a listed stream type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UnknownStreamType(u8);
+impl UnknownStreamType {
+    /// Returns the value of the stream type.
+    pub fn as_u8(self) -> u8 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for n in 0..=0xFF {
+            let stream_type = StreamType::from_u8(n).unwrap();
+            assert_eq!(stream_type.as_u8(), n);
+        }
+        assert_eq!(
+            StreamType::from_u8(0x21).unwrap(),
+            StreamType::Jpeg2000Video
+        );
+        assert_eq!(StreamType::from_u8(0x33).unwrap(), StreamType::H266);
+        assert!(matches!(
+            StreamType::from_u8(0x00).unwrap(),
+            StreamType::Unknown(t) if t.as_u8() == 0x00
+        ));
+        assert!(matches!(
+            StreamType::from_u8(0xFE).unwrap(),
+            StreamType::Unknown(t) if t.as_u8() == 0xFE
+        ));
+    }
 }
@@ -12,12 +12,22 @@
 //! - [MPEG transport stream](https://en.wikipedia.org/wiki/MPEG_transport_stream)
 //! - [Program-specific information](https://en.wikipedia.org/wiki/Program-specific_information)
 //! - [Packetized elementary stream](https://en.wikipedia.org/wiki/Packetized_elementary_stream)
+//!
+//! # Features
+//!
+//! - `tokio`: Enables the asynchronous readers and writer
+//!   (`AsyncReadTsPacket`, `AsyncReadPesPacket` and `AsyncWriteTsPacket`).
+//! - `rayon`: Enables the `parallel` module that parses large streams on a thread pool.
 #![warn(missing_docs)]
 extern crate byteorder;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "tokio")]
+extern crate tokio;
 #[macro_use]
 extern crate trackable;
 
-pub use error::{Error, ErrorKind};
+pub use error::{Error, ErrorKind, ErrorPolicy, PacketError};
 
 macro_rules! track_io {
     ($expr:expr) => {
@@ -28,13 +38,18 @@ macro_rules! track_io {
     };
 }
 
+pub mod demux;
 pub mod es;
+#[cfg(feature = "rayon")]
+pub mod parallel;
 pub mod pes;
 pub mod time;
 pub mod ts;
 
 mod crc;
 mod error;
+#[cfg(test)]
+mod test_util;
 mod util;
 
 /// This crate specific `Result` type.
@@ -0,0 +1,486 @@
+//! Parallel parsing of transport streams.
+//!
+//! This module is available only if the `rayon` feature is enabled.
+use rayon::prelude::*;
+use std::cmp;
+use std::mem;
+use std::ops::Range;
+use std::sync::mpsc;
+
+use error::ErrorHandler;
+use pes::{PesPacket, PesPacketDecoder};
+use ts::{
+    next_packet_boundary, ContinuityChecker, ContinuityEvent, PacketFormat, PacketPosition,
+    ProgramEvent, ProgramMap, PsiState, TsPacket, TsPacketReader,
+};
+use {ErrorPolicy, PacketError, Result};
+
+/// Number of bytes fed to the scanner at once.
+const FEED_SIZE: usize = 1024 * 1024;
+
+/// Packets and PES packets parsed from a chunk of a stream.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Default)]
+pub struct ParsedChunk {
+    pub ts_packets: Vec<TsPacket>,
+
+    /// The PES packets completed by the TS packets of the chunk.
+    ///
+    /// A PES packet that crosses chunk boundaries belongs to the chunk where it completes.
+    pub pes_packets: Vec<PesPacket<Vec<u8>>>,
+}
+
+/// Parser that parses a transport stream in parallel.
+///
+/// The stream is split into chunks at the packet boundaries,
+/// and the TS packets in the chunks are parsed on the thread pool of `rayon`.
+/// Then the results are stitched back together in order:
+/// the PES packets are assembled, and the continuity counters are checked across the chunks.
+///
+/// To decode the payloads of each chunk, the parser tracks the PAT and PMTs of the stream
+/// by quickly scanning the stream ahead of the parallel parsing.
+#[derive(Debug)]
+pub struct ParallelParser {
+    format: PacketFormat,
+    reed_solomon: bool,
+    chunk_packets: u64,
+    errors: ErrorHandler,
+    programs: ProgramMap,
+    program_events: Vec<ProgramEvent>,
+    continuity_events: Vec<ContinuityEvent>,
+}
+impl ParallelParser {
+    /// Default number of packets in a chunk.
+    pub const DEFAULT_CHUNK_PACKETS: u64 = 16 * 1024;
+
+    /// Makes a new `ParallelParser` instance.
+    pub fn new() -> Self {
+        Self::with_format(PacketFormat::Ts)
+    }
+
+    /// Makes a new `ParallelParser` instance that parses packets of the given format.
+    pub fn with_format(format: PacketFormat) -> Self {
+        ParallelParser {
+            format,
+            reed_solomon: false,
+            chunk_packets: Self::DEFAULT_CHUNK_PACKETS,
+            errors: ErrorHandler::default(),
+            programs: ProgramMap::default(),
+            program_events: Vec::new(),
+            continuity_events: Vec::new(),
+        }
+    }
+
+    /// Returns the packet format of the stream.
+    pub fn format(&self) -> PacketFormat {
+        self.format
+    }
+
+    /// Enables or disables the Reed-Solomon decoding of `PacketFormat::Dvb` packets.
+    ///
+    /// See `TsPacketReader::set_reed_solomon_decoding()` for details.
+    pub fn set_reed_solomon_decoding(&mut self, enabled: bool) {
+        self.reed_solomon = enabled;
+    }
+
+    /// Sets the number of packets in a chunk.
+    ///
+    /// The default value is `DEFAULT_CHUNK_PACKETS`.
+    pub fn set_chunk_packets(&mut self, packets: u64) {
+        self.chunk_packets = cmp::max(packets, 1);
+    }
+
+    /// Sets the policy for the packets that cannot be decoded.
+    ///
+    /// The default policy is `ErrorPolicy::Lenient`.
+    /// The errors are handled in the order of the stream,
+    /// but the errors of a chunk are handled before its packets are passed to the callback.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.errors.set_policy(policy);
+    }
+
+    /// Returns the number of the packets dropped due to decoding errors.
+    pub fn error_count(&self) -> u64 {
+        self.errors.count()
+    }
+
+    /// Returns the programs of the last parsed stream.
+    pub fn program_map(&self) -> &ProgramMap {
+        &self.programs
+    }
+
+    /// Takes the program change events raised since the last call.
+    pub fn take_program_events(&mut self) -> Vec<ProgramEvent> {
+        mem::take(&mut self.program_events)
+    }
+
+    /// Takes the continuity errors detected since the last call.
+    pub fn take_continuity_events(&mut self) -> Vec<ContinuityEvent> {
+        mem::take(&mut self.continuity_events)
+    }
+
+    /// Parses the whole stream in `bytes` (e.g., a memory-mapped file).
+    ///
+    /// `f` is called with each chunk in the order of the stream.
+    /// At the end of the stream, the PES packets being assembled are flushed
+    /// and passed to `f` as an extra chunk that has no TS packets.
+    ///
+    /// Note that the chunk boundaries are located by searching for the sync bytes
+    /// from the offsets of every `chunk_packets` packets.
+    /// The packets of a clean stream are read as `TsPacketReader` does,
+    /// but the packets around corrupted or missing bytes at a chunk boundary
+    /// may be resynchronized differently.
+    pub fn parse<F>(&mut self, bytes: &[u8], mut f: F) -> Result<()>
+    where
+        F: FnMut(ParsedChunk),
+    {
+        let mut scanner = Scanner::new(self.format, self.reed_solomon, bytes);
+        let mut pes_decoder = PesPacketDecoder::new();
+        let mut continuity = ContinuityChecker::default();
+        let batch_size = rayon::current_num_threads() * 2;
+        loop {
+            let mut chunks = Vec::with_capacity(batch_size);
+            while chunks.len() < batch_size {
+                match scanner.next_chunk(self.chunk_packets) {
+                    None => break,
+                    Some(chunk) => chunks.push(chunk),
+                }
+            }
+            self.programs = scanner.reader.program_map().clone();
+            self.program_events
+                .extend(scanner.reader.take_program_events());
+            if chunks.is_empty() {
+                break;
+            }
+
+            let format = self.format;
+            let reed_solomon = self.reed_solomon;
+            let results = chunks
+                .into_par_iter()
+                .map(|chunk| {
+                    let bytes = &bytes[chunk.range.start as usize..chunk.range.end as usize];
+                    parse_chunk(format, reed_solomon, chunk, bytes)
+                })
+                .collect::<Vec<_>>();
+
+            for result in results {
+                let (ts_packets, errors) = track!(result)?;
+                for error in errors {
+                    track!(self.errors.handle(error))?;
+                }
+
+                let mut pes_packets = Vec::new();
+                for packet in &ts_packets {
+                    let offset = packet.position.map_or(0, |p| p.offset);
+                    let continuity_error = continuity.check(packet);
+                    if let Some((kind, expected)) = continuity_error {
+                        self.continuity_events.push(ContinuityEvent {
+                            kind,
+                            pid: packet.header.pid,
+                            expected,
+                            actual: packet.header.continuity_counter,
+                            offset,
+                        });
+                    }
+                    let kind = continuity_error.map(|(kind, _)| kind);
+                    match pes_decoder.process_ts_packet_with_continuity_error(packet, kind) {
+                        Ok(None) => {}
+                        Ok(Some(pes_packet)) => pes_packets.push(pes_packet),
+                        Err(e) => {
+                            let error = PacketError {
+                                error: e,
+                                pid: packet.header.pid,
+                                offset: Some(offset),
+                            };
+                            track!(self.errors.handle(error))?;
+                        }
+                    }
+                }
+                f(ParsedChunk {
+                    ts_packets,
+                    pes_packets,
+                });
+            }
+        }
+
+        let mut pes_packets = Vec::new();
+        while let Some(pes_packet) = track!(pes_decoder.flush())? {
+            pes_packets.push(pes_packet);
+        }
+        if !pes_packets.is_empty() {
+            f(ParsedChunk {
+                ts_packets: Vec::new(),
+                pes_packets,
+            });
+        }
+        Ok(())
+    }
+}
+impl Default for ParallelParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Chunk to be parsed by a worker thread.
+#[derive(Debug)]
+struct Chunk {
+    start: PacketPosition,
+    range: Range<u64>,
+
+    /// The program map and the PSI sections being assembled at the start of the chunk.
+    psi: PsiState,
+}
+
+/// Sequential scanner that splits a stream into chunks while tracking its PAT and PMTs.
+///
+/// The chunk boundaries are placed every `packets` packets by arithmetic,
+/// then moved forward to the next packet boundary found by the sync bytes.
+/// The packets in each chunk are scanned in the same way as `parse_chunk()` reads them,
+/// but only the PSI packets are decoded.
+#[derive(Debug)]
+struct Scanner<'a> {
+    reader: TsPacketReader<()>,
+    bytes: &'a [u8],
+    next: PacketPosition,
+}
+impl<'a> Scanner<'a> {
+    fn new(format: PacketFormat, reed_solomon: bool, bytes: &'a [u8]) -> Self {
+        let mut reader = TsPacketReader::with_format((), format);
+        reader.set_reed_solomon_decoding(reed_solomon);
+        Scanner {
+            reader,
+            bytes,
+            next: PacketPosition {
+                offset: 0,
+                index: 0,
+            },
+        }
+    }
+
+    fn next_chunk(&mut self, packets: u64) -> Option<Chunk> {
+        let format = self.reader.format();
+        let len = self.bytes.len() as u64;
+        while self.next.offset < len {
+            let start = self.next;
+            let end = packets
+                .saturating_mul(format.packet_size() as u64)
+                .saturating_add(start.offset);
+            let end = next_packet_boundary(self.bytes, format, end);
+
+            let psi = self.reader.psi_state();
+            self.reader.reset_position(start);
+            self.reader.set_psi_state(psi.clone());
+            let mut count = 0;
+            for bytes in self.bytes[start.offset as usize..end as usize].chunks(FEED_SIZE) {
+                self.reader.push_bytes(bytes);
+                count += self.reader.skip_packets();
+            }
+            self.reader.push_bytes(&[]);
+            count += self.reader.skip_packets();
+
+            self.next = self.reader.next_packet_position();
+            debug_assert_eq!(self.next.offset, end);
+            if count > 0 {
+                return Some(Chunk {
+                    start,
+                    range: start.offset..end,
+                    psi,
+                });
+            }
+        }
+        None
+    }
+}
+
+fn parse_chunk(
+    format: PacketFormat,
+    reed_solomon: bool,
+    chunk: Chunk,
+    bytes: &[u8],
+) -> Result<(Vec<TsPacket>, Vec<PacketError>)> {
+    let (tx, rx) = mpsc::channel();
+    let mut reader = chunk_reader(format, reed_solomon, chunk, bytes);
+    reader.set_error_policy(ErrorPolicy::Callback(Box::new(move |e| {
+        let _ = tx.send(e.clone());
+    })));
+
+    let mut packets = Vec::new();
+    while let Some(packet) = track!(reader.decode_next_packet())? {
+        packets.push(packet);
+    }
+    mem::drop(reader);
+    Ok((packets, rx.try_iter().collect()))
+}
+
+/// Makes a reader that reads the packets of `chunk` from `bytes`.
+fn chunk_reader(
+    format: PacketFormat,
+    reed_solomon: bool,
+    chunk: Chunk,
+    bytes: &[u8],
+) -> TsPacketReader<()> {
+    let mut reader = TsPacketReader::with_format((), format);
+    reader.set_reed_solomon_decoding(reed_solomon);
+    reader.reset_position(chunk.start);
+    reader.set_psi_state(chunk.psi);
+    reader.push_bytes(bytes);
+    reader.push_bytes(&[]);
+    reader
+}
+
+#[cfg(test)]
+mod test {
+    use es::StreamType;
+    use pes::{PesPacketReader, ReadPesPacket};
+    use test_util::{packet, pes, stream_bytes_with, stream_bytes_with_table, ES_PID, PMT_PID};
+    use ts::payload::{Bytes, Pmt};
+    use ts::scte35::{AvailDescriptor, SpliceCommand, SpliceDescriptor, SpliceInfoSection};
+    use ts::{EsInfo, Pid, ReadTsPacket, TableEvent, TsPacketWriter, TsPayload, VersionNumber,
+             WriteTsPacket};
+
+    use super::*;
+
+    fn stream_bytes() -> Vec<u8> {
+        // PES packets spanning 1, 2, 3, ... TS packets
+        let mut es_packets = Vec::new();
+        let mut counter = 0;
+        for i in 0..20 {
+            es_packets.push(packet(ES_PID, counter, pes(&[i; 100])));
+            for _ in 0..i {
+                counter = (counter + 1) % 16;
+                let payload = TsPayload::Raw(Bytes::new(&[i; 184]).unwrap());
+                es_packets.push(packet(ES_PID, counter, payload));
+            }
+            counter = (counter + 1) % 16;
+        }
+        stream_bytes_with(&es_packets)
+    }
+
+    #[test]
+    fn parallel_parser() {
+        let mut bytes = vec![0; 10]; // Garbage
+        bytes.extend(stream_bytes());
+
+        let mut expected_ts_packets = Vec::new();
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            expected_ts_packets.push(packet);
+        }
+        let mut expected_pes_packets = Vec::new();
+        let mut reader = PesPacketReader::new(TsPacketReader::new(&bytes[..]));
+        while let Some(packet) = track_try_unwrap!(reader.read_pes_packet()) {
+            expected_pes_packets.push(packet);
+        }
+        assert_eq!(expected_pes_packets.len(), 20);
+
+        let mut parser = ParallelParser::new();
+        parser.set_chunk_packets(7);
+        let mut ts_packets = Vec::new();
+        let mut pes_packets = Vec::new();
+        track_try_unwrap!(parser.parse(&bytes, |chunk| {
+            ts_packets.extend(chunk.ts_packets);
+            pes_packets.extend(chunk.pes_packets);
+        }));
+        assert_eq!(ts_packets, expected_ts_packets);
+        assert_eq!(pes_packets.len(), expected_pes_packets.len());
+        for (a, b) in pes_packets.iter().zip(expected_pes_packets.iter()) {
+            assert_eq!(a.data, b.data);
+            assert_eq!(a.first_ts_packet_position, b.first_ts_packet_position);
+            assert_eq!(a.last_ts_packet_position, b.last_ts_packet_position);
+        }
+        assert!(parser.take_continuity_events().is_empty());
+        assert_eq!(parser.take_program_events().len(), 2);
+        assert_eq!(parser.program_map().program(1).unwrap().es_list().len(), 1);
+    }
+
+    #[test]
+    fn pmt_update_across_chunks() {
+        // `stream_bytes()` consists of 212 packets
+        let mut writer = TsPacketWriter::new(stream_bytes());
+        let pmt = Pmt {
+            program_num: 1,
+            pcr_pid: None,
+            version_number: VersionNumber::from_u8(1).unwrap(),
+            program_descriptors: Vec::new(),
+            table: (0..80)
+                .map(|i| EsInfo {
+                    stream_type: StreamType::H264,
+                    elementary_pid: Pid::new(ES_PID + i).unwrap(),
+                    descriptors: Vec::new(),
+                })
+                .collect(),
+        };
+        let pmt_packet = packet(PMT_PID, 1, TsPayload::Pmt(pmt));
+        track_try_unwrap!(writer.write_ts_packet(&pmt_packet)); // 3 packets
+        let pes_packet = packet(ES_PID + 1, 0, pes(&[0xAA; 100]));
+        track_try_unwrap!(writer.write_ts_packet(&pes_packet));
+        let bytes = writer.into_stream();
+
+        let mut expected_ts_packets = Vec::new();
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            expected_ts_packets.push(packet);
+        }
+        assert_eq!(expected_ts_packets.len(), 216);
+        assert!(matches!(
+            expected_ts_packets[215].payload,
+            Some(TsPayload::Pes(_))
+        ));
+
+        // The chunk boundary lies between the first and second packets of the updated PMT
+        let mut parser = ParallelParser::new();
+        parser.set_chunk_packets(71);
+        let mut ts_packets = Vec::new();
+        let mut pes_packets = Vec::new();
+        track_try_unwrap!(parser.parse(&bytes, |chunk| {
+            ts_packets.extend(chunk.ts_packets);
+            pes_packets.extend(chunk.pes_packets);
+        }));
+        assert_eq!(ts_packets, expected_ts_packets);
+        assert_eq!(pes_packets.len(), 21);
+        assert!(pes_packets.iter().any(|p| p.data == [0xAA; 100]));
+        assert!(parser.take_continuity_events().is_empty());
+        assert_eq!(parser.program_map().program(1).unwrap().es_list().len(), 80);
+    }
+
+    #[test]
+    fn splice_section_across_chunks() {
+        let splice_pid = 0x1F0;
+        let table = vec![
+            EsInfo {
+                stream_type: StreamType::H264,
+                elementary_pid: Pid::new(ES_PID).unwrap(),
+                descriptors: Vec::new(),
+            },
+            EsInfo {
+                stream_type: StreamType::SCTE_35,
+                elementary_pid: Pid::new(splice_pid).unwrap(),
+                descriptors: Vec::new(),
+            },
+        ];
+        let mut section = SpliceInfoSection::new(SpliceCommand::SpliceNull);
+        section.descriptors = (0..30)
+            .map(|i| SpliceDescriptor::Avail(AvailDescriptor { provider_avail_id: i }))
+            .collect();
+        let splice_packet = packet(splice_pid, 0, TsPayload::Scte35(section.clone()));
+        let bytes = stream_bytes_with_table(table, &[splice_packet]);
+        assert_eq!(bytes.len(), TsPacket::SIZE * 4);
+
+        // The chunk boundary lies between the two packets of the section
+        let mut scanner = Scanner::new(PacketFormat::Ts, false, &bytes);
+        let mut sections = Vec::new();
+        while let Some(chunk) = scanner.next_chunk(3) {
+            let range = chunk.range.start as usize..chunk.range.end as usize;
+            let mut reader = chunk_reader(PacketFormat::Ts, false, chunk, &bytes[range]);
+            reader.enable_table_events();
+            while track_try_unwrap!(reader.decode_next_packet()).is_some() {}
+            for event in reader.take_table_events() {
+                if let TableEvent::SpliceInfo { section, .. } = event {
+                    sections.push(section);
+                }
+            }
+        }
+        assert_eq!(sections, [section]);
+    }
+}
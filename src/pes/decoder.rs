@@ -2,7 +2,7 @@ use std::{collections::HashMap, env};
 
 use crate::ts::{
     payload::{Bytes, Pes},
-    Pid, TsPacket, TsPayload,
+    ContinuityEventKind, PacketPosition, Pid, TsPacket, TsPayload,
 };
 use {ErrorKind, Result};
 
@@ -11,6 +11,11 @@ use super::{PartialPesPacket, PesPacket};
 const TS_IGNORE_HEADER_LENGTH: &str = "TS_IGNORE_HEADER_LENGTH";
 
 /// PES packet decoder.
+///
+/// The decoder does not check the continuity counters of the TS packets by itself.
+/// Instead, the continuity errors detected by the TS packet reader are given to
+/// `process_ts_packet_with_continuity_error()`: duplicate packets are ignored,
+/// and the PES packet being assembled is dropped if any of its TS packets are lost.
 #[derive(Debug, Default)]
 pub struct PesPacketDecoder {
     pes_packets: HashMap<Pid, PartialPesPacket>,
@@ -48,7 +53,12 @@ impl PesPacketDecoder {
     }
 
     /// Handles PES payload data.
-    fn handle_pes_payload(&mut self, pid: Pid, pes: Pes) -> Result<Option<PesPacket<Vec<u8>>>> {
+    fn handle_pes_payload(
+        &mut self,
+        pid: Pid,
+        pes: Pes,
+        position: Option<PacketPosition>,
+    ) -> Result<Option<PesPacket<Vec<u8>>>> {
         let data_len = if self.ignore_packet_header_length || pes.pes_packet_len == 0 {
             None
         } else {
@@ -69,6 +79,8 @@ impl PesPacketDecoder {
         let packet = PesPacket {
             header: pes.header,
             data,
+            first_ts_packet_position: position,
+            last_ts_packet_position: position,
         };
         let partial = PartialPesPacket { packet, data_len };
         if let Some(pred) = self.pes_packets.insert(pid, partial) {
@@ -79,13 +91,19 @@ impl PesPacketDecoder {
     }
 
     /// Handles raw payload data.
-    fn handle_raw_payload(&mut self, pid: Pid, data: &Bytes) -> Result<Option<PesPacket<Vec<u8>>>> {
+    fn handle_raw_payload(
+        &mut self,
+        pid: Pid,
+        data: &Bytes,
+        position: Option<PacketPosition>,
+    ) -> Result<Option<PesPacket<Vec<u8>>>> {
         let mut partial = match self.pes_packets.remove(&pid) {
             Some(partial) => partial,
             None => return Ok(None),
         };
 
         partial.packet.data.extend_from_slice(data);
+        partial.packet.last_ts_packet_position = position;
         if Some(partial.packet.data.len()) == partial.data_len {
             Ok(Some(partial.packet))
         } else {
@@ -108,20 +126,64 @@ impl PesPacketDecoder {
     pub fn process_ts_packet(
         &mut self,
         ts_packet: &TsPacket,
+    ) -> Result<Option<PesPacket<Vec<u8>>>> {
+        track!(self.process_ts_packet_with_continuity_error(ts_packet, None))
+    }
+
+    /// Processes a TS packet with the continuity error detected for it,
+    /// and returns a PES packet if available.
+    ///
+    /// A duplicate packet is ignored, and a packet loss drops the PES packet being assembled
+    /// for the PID (see `handle_packet_loss()`).
+    pub fn process_ts_packet_with_continuity_error(
+        &mut self,
+        ts_packet: &TsPacket,
+        continuity_error: Option<ContinuityEventKind>,
     ) -> Result<Option<PesPacket<Vec<u8>>>> {
         if self.eos {
             return track!(self.handle_eos());
         }
 
         let pid = ts_packet.header.pid;
+        match continuity_error {
+            Some(ContinuityEventKind::DuplicatePacket) => return Ok(None),
+            Some(ContinuityEventKind::PacketLoss) => self.handle_packet_loss(pid),
+            None => {}
+        }
+
         let result = match &ts_packet.payload {
-            Some(TsPayload::Pes(payload)) => track!(self.handle_pes_payload(pid, payload.clone()))?,
-            Some(TsPayload::Raw(payload)) => track!(self.handle_raw_payload(pid, payload))?,
+            Some(TsPayload::Pes(payload)) => {
+                track!(self.handle_pes_payload(pid, payload.clone(), ts_packet.position))?
+            }
+            Some(TsPayload::Raw(payload)) => {
+                track!(self.handle_raw_payload(pid, payload, ts_packet.position))?
+            }
             _ => None,
         };
         Ok(result)
     }
 
+    /// Notifies the decoder that TS packets of the given PID have been lost.
+    ///
+    /// The PES packet being assembled for the PID is dropped.
+    pub fn handle_packet_loss(&mut self, pid: Pid) {
+        if let Some(partial) = self.pes_packets.remove(&pid) {
+            log::debug!(
+                "Dropped an incomplete PES packet: pid={}, data_len={}",
+                pid.as_u16(),
+                partial.packet.data.len()
+            );
+        }
+    }
+
+    /// Discards the PES packets being assembled.
+    ///
+    /// This is used when the input jumps to another position of the stream.
+    pub fn reset(&mut self) {
+        self.pes_packets.clear();
+        self.eos = false;
+    }
+
     /// Flush the decoder.
     pub fn flush(&mut self) -> Result<Option<PesPacket<Vec<u8>>>> {
         if self.eos {
@@ -139,7 +201,7 @@ mod tests {
     use crate::{
         es::StreamId,
         pes::PesHeader,
-        ts::{ContinuityCounter, TransportScramblingControl, TsHeader},
+        ts::{AdaptationFieldControl, ContinuityCounter, TransportScramblingControl, TsHeader},
     };
 
     #[test]
@@ -148,13 +210,17 @@ mod tests {
         let packet = TsPacket {
             header: TsHeader {
                 transport_error_indicator: false,
+                payload_unit_start_indicator: false,
                 transport_priority: false,
                 pid: Pid::new(0x100).unwrap(),
                 transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: AdaptationFieldControl::PayloadOnly,
                 continuity_counter: ContinuityCounter::new(),
             },
             payload: Some(TsPayload::Raw(Bytes::new(&[0x00; 184]).unwrap())),
             adaptation_field: None,
+            tp_extra_header: None,
+            position: None,
         };
         assert!(decoder.process_ts_packet(&packet).is_ok());
     }
@@ -176,16 +242,20 @@ mod tests {
             pes_packet_len: 35,
             data: Bytes::new(&[0x00; 32]).unwrap(),
         };
-        let packet = TsPacket {
+        let mut packet = TsPacket {
             header: TsHeader {
                 transport_error_indicator: false,
+                payload_unit_start_indicator: true,
                 transport_priority: false,
                 pid: Pid::new(0x100).unwrap(),
                 transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: AdaptationFieldControl::AdaptationFieldAndPayload,
                 continuity_counter: ContinuityCounter::new(),
             },
             payload: Some(TsPayload::Pes(pes_packet)),
             adaptation_field: None,
+            tp_extra_header: None,
+            position: None,
         };
 
         // first packet returned will be None
@@ -194,6 +264,7 @@ mod tests {
         let pes_packet = result.unwrap();
         assert!(pes_packet.is_none());
 
+        packet.header.continuity_counter.increment();
         let result = decoder.process_ts_packet(&packet);
         assert!(result.is_ok());
         let pes_packet = result.unwrap();
@@ -220,13 +291,17 @@ mod tests {
         let packet = TsPacket {
             header: TsHeader {
                 transport_error_indicator: false,
+                payload_unit_start_indicator: true,
                 transport_priority: false,
                 pid: Pid::new(0x100).unwrap(),
                 transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: AdaptationFieldControl::AdaptationFieldAndPayload,
                 continuity_counter: ContinuityCounter::new(),
             },
             payload: Some(TsPayload::Pes(pes_packet)),
             adaptation_field: None,
+            tp_extra_header: None,
+            position: None,
         };
 
         let result = decoder.process_ts_packet(&packet);
@@ -237,4 +312,62 @@ mod tests {
         let p = result.unwrap();
         assert!(p.is_some());
     }
+
+    #[test]
+    fn test_pes_packet_decoder_packet_loss() {
+        let pes_packet = Pes {
+            header: PesHeader {
+                stream_id: StreamId::new(0x1),
+                priority: false,
+                data_alignment_indicator: false,
+                copyright: false,
+                original_or_copy: true,
+                pts: None,
+                dts: None,
+                escr: None,
+            },
+            pes_packet_len: 3 + 32 + 184 * 2,
+            data: Bytes::new(&[0x00; 32]).unwrap(),
+        };
+        let first = TsPacket {
+            header: TsHeader {
+                transport_error_indicator: false,
+                payload_unit_start_indicator: true,
+                transport_priority: false,
+                pid: Pid::new(0x100).unwrap(),
+                transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: AdaptationFieldControl::AdaptationFieldAndPayload,
+                continuity_counter: ContinuityCounter::new(),
+            },
+            payload: Some(TsPayload::Pes(pes_packet)),
+            adaptation_field: None,
+            tp_extra_header: None,
+            position: None,
+        };
+        let mut second = first.clone();
+        second.header.payload_unit_start_indicator = false;
+        second.header.adaptation_field_control = AdaptationFieldControl::PayloadOnly;
+        second.payload = Some(TsPayload::Raw(Bytes::new(&[0x00; 184]).unwrap()));
+        second.header.continuity_counter.increment();
+        let mut third = second.clone();
+        third.header.continuity_counter.increment();
+
+        let duplicate = Some(ContinuityEventKind::DuplicatePacket);
+        let loss = Some(ContinuityEventKind::PacketLoss);
+
+        // Duplicate packets are ignored.
+        let mut decoder = PesPacketDecoder::new();
+        assert!(decoder.process_ts_packet(&first).unwrap().is_none());
+        assert!(decoder.process_ts_packet(&second).unwrap().is_none());
+        let result = decoder.process_ts_packet_with_continuity_error(&second, duplicate);
+        assert!(result.unwrap().is_none());
+        assert!(decoder.process_ts_packet(&third).unwrap().is_some());
+
+        // The PES packet is dropped because a packet is lost.
+        let mut decoder = PesPacketDecoder::new();
+        assert!(decoder.process_ts_packet(&first).unwrap().is_none());
+        let result = decoder.process_ts_packet_with_continuity_error(&third, loss);
+        assert!(result.unwrap().is_none());
+        assert!(decoder.flush().unwrap().is_none());
+    }
 }
@@ -6,6 +6,8 @@
 pub use self::decoder::PesPacketDecoder;
 pub use self::packet::{PesHeader, PesPacket};
 pub use self::reader::{PesPacketReader, ReadPesPacket};
+#[cfg(feature = "tokio")]
+pub use self::reader::{AsyncReadPesPacket, ReadPesPacketFuture};
 
 mod decoder;
 mod packet;
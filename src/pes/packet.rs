@@ -3,17 +3,29 @@ use std::io::{Read, Write};
 
 use es::StreamId;
 use time::{ClockReference, Timestamp};
+use ts::PacketPosition;
 use util;
 use {ErrorKind, Result};
 
 const PACKET_START_CODE_PREFIX: u64 = 0x00_0001;
 
 /// PES packet.
+///
+/// More fields may be added in the future, so it cannot be constructed outside this crate.
 #[allow(missing_docs)]
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub struct PesPacket<B> {
     pub header: PesHeader,
     pub data: B,
+
+    /// The position of the first TS packet that carries the PES packet.
+    ///
+    /// This is `None` if the positions of the TS packets are unknown.
+    pub first_ts_packet_position: Option<PacketPosition>,
+
+    /// The position of the last TS packet that carries the PES packet.
+    pub last_ts_packet_position: Option<PacketPosition>,
 }
 
 /// PES packet header.
@@ -1,11 +1,22 @@
 use std::collections::VecDeque;
+use std::io::{Read, Seek};
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
 
+use error::ErrorHandler;
 use pes::PesPacket;
-use ts::ReadTsPacket;
+#[cfg(feature = "tokio")]
+use ts::AsyncReadTsPacket;
+use time::Timestamp;
+use ts::{ContinuityEventKind, Pid, ReadTsPacket, SeekClock, TsPacket, TsPacketReader};
 
 use super::PesPacketDecoder;
 
-use {ErrorKind, Result};
+use {ErrorKind, ErrorPolicy, PacketError, Result};
 
 /// The `ReadPesPacket` trait allows for reading PES packets from a source.
 pub trait ReadPesPacket {
@@ -17,6 +28,15 @@ pub trait ReadPesPacket {
     ///Peeks a PES Packet
     fn peek_pes_packet(&mut self) -> Option<&PesPacket<Vec<u8>>>;
 
+    /// Peeks a PES packet.
+    ///
+    /// If the end of the stream is reached, it will return `Ok(None)`.
+    /// Unlike `peek_pes_packet()`, the errors are returned instead of being turned into `None`.
+    /// The default implementation delegates to `peek_pes_packet()`.
+    fn try_peek_pes_packet(&mut self) -> Result<Option<&PesPacket<Vec<u8>>>> {
+        Ok(self.peek_pes_packet())
+    }
+
     ///Marks the reader to a spot that can be returned to at a later time
     fn mark(&mut self) -> Result<()>;
 
@@ -27,7 +47,45 @@ pub trait ReadPesPacket {
     fn has_back_buffer(&self) -> bool;
 }
 
+/// The `AsyncReadPesPacket` trait allows for reading PES packets from an asynchronous source.
+#[cfg(feature = "tokio")]
+pub trait AsyncReadPesPacket {
+    /// Attempts to read a PES packet.
+    ///
+    /// If the end of the stream is reached, it will return `Poll::Ready(Ok(None))`.
+    fn poll_read_pes_packet(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<Option<PesPacket<Vec<u8>>>>>;
+
+    /// Returns a future that reads a PES packet.
+    fn read_pes_packet_async(&mut self) -> ReadPesPacketFuture<'_, Self>
+    where
+        Self: Sized,
+    {
+        ReadPesPacketFuture { reader: self }
+    }
+}
+
+/// Future returned by `AsyncReadPesPacket::read_pes_packet_async()`.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct ReadPesPacketFuture<'a, R: 'a> {
+    reader: &'a mut R,
+}
+#[cfg(feature = "tokio")]
+impl<'a, R: AsyncReadPesPacket> Future for ReadPesPacketFuture<'a, R> {
+    type Output = Result<Option<PesPacket<Vec<u8>>>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.get_mut().reader.poll_read_pes_packet(cx)
+    }
+}
+
 /// PES packet reader.
+///
+/// If the `tokio` feature is enabled, it also implements `AsyncReadPesPacket`
+/// for the TS packet readers that implement `AsyncReadTsPacket`.
 #[derive(Debug)]
 pub struct PesPacketReader<R> {
     peeked_packet: Option<PesPacket<Vec<u8>>>,
@@ -36,8 +94,9 @@ pub struct PesPacketReader<R> {
     eos: bool,
     is_marked: bool,
     back_buffer: VecDeque<PesPacket<Vec<u8>>>,
+    errors: ErrorHandler,
 }
-impl<R: ReadTsPacket> PesPacketReader<R> {
+impl<R> PesPacketReader<R> {
     /// Makes a new `PesPacketReader` instance.
     pub fn new(ts_packet_reader: R) -> Self {
         PesPacketReader {
@@ -47,9 +106,23 @@ impl<R: ReadTsPacket> PesPacketReader<R> {
             eos: false,
             is_marked: false,
             back_buffer: VecDeque::<PesPacket<Vec<u8>>>::with_capacity(200),
+            errors: ErrorHandler::default(),
         }
     }
 
+    /// Sets the policy for the TS packets that cannot be decoded into PES packets.
+    ///
+    /// The default policy is `ErrorPolicy::Lenient`.
+    /// Note that the errors of the underlaying TS packet reader are handled by the reader itself.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.errors.set_policy(policy);
+    }
+
+    /// Returns the number of the TS packets dropped due to decoding errors.
+    pub fn error_count(&self) -> u64 {
+        self.errors.count()
+    }
+
     /// Returns a reference to the underlaying TS packet reader.
     pub fn ts_packet_reader(&self) -> &R {
         &self.ts_packet_reader
@@ -60,14 +133,42 @@ impl<R: ReadTsPacket> PesPacketReader<R> {
         self.ts_packet_reader
     }
 
+    /// Feeds a TS packet to the PES decoder and returns the completed PES packet if any.
+    ///
+    /// `continuity_error` is the continuity error detected by the TS packet reader.
+    /// The packets that cannot be decoded are handled according to the error policy.
+    fn process_ts_packet(
+        &mut self,
+        ts_packet: &TsPacket,
+        continuity_error: Option<ContinuityEventKind>,
+    ) -> Result<Option<PesPacket<Vec<u8>>>> {
+        let result = self
+            .pes_decoder
+            .process_ts_packet_with_continuity_error(ts_packet, continuity_error);
+        match result {
+            Ok(result) => Ok(result),
+            Err(e) => {
+                let error = PacketError {
+                    error: e,
+                    pid: ts_packet.header.pid,
+                    offset: ts_packet.position.map(|p| p.offset),
+                };
+                track!(self.errors.handle(error))?;
+                Ok(None)
+            }
+        }
+    }
+}
+impl<R: ReadTsPacket> PesPacketReader<R> {
     fn read_next_pes_packet(&mut self) -> Result<Option<PesPacket<Vec<u8>>>> {
         if self.eos {
             return track!(self.pes_decoder.flush());
         }
 
         while let Some(ts_packet) = track!(self.ts_packet_reader.read_ts_packet())? {
-            if let Ok(result) = self.pes_decoder.process_ts_packet(&ts_packet) {
-                return Ok(result);
+            let continuity_error = self.ts_packet_reader.last_continuity_error();
+            if let Some(packet) = track!(self.process_ts_packet(&ts_packet, continuity_error))? {
+                return Ok(Some(packet));
             }
         }
 
@@ -75,20 +176,62 @@ impl<R: ReadTsPacket> PesPacketReader<R> {
         track!(self.pes_decoder.flush())
     }
 }
+impl<S: Read + Seek> PesPacketReader<TsPacketReader<S>> {
+    /// Seeks to the TS packet at the given byte offset of the stream.
+    ///
+    /// The PES packets being assembled, the peeked packet and the mark are discarded.
+    /// See `TsPacketReader::seek_to_offset()` for details.
+    pub fn seek_to_offset(&mut self, offset: u64) -> Result<u64> {
+        let offset = track!(self.ts_packet_reader.seek_to_offset(offset))?;
+        self.reset_state();
+        Ok(offset)
+    }
+
+    /// Seeks to the random access point preceding the given presentation time.
+    ///
+    /// The PES packets being assembled, the peeked packet and the mark are discarded.
+    /// See `TsPacketReader::seek_to_timestamp()` for details.
+    pub fn seek_to_timestamp(
+        &mut self,
+        pid: Pid,
+        timestamp: Timestamp,
+        clock: SeekClock,
+    ) -> Result<u64> {
+        let offset = track!(self.ts_packet_reader.seek_to_timestamp(pid, timestamp, clock))?;
+        self.reset_state();
+        Ok(offset)
+    }
+
+    fn reset_state(&mut self) {
+        self.peeked_packet = None;
+        self.pes_decoder.reset();
+        self.eos = false;
+        self.is_marked = false;
+        self.back_buffer.clear();
+    }
+}
 impl<R: ReadTsPacket> ReadPesPacket for PesPacketReader<R> {
     fn peek_pes_packet(&mut self) -> Option<&PesPacket<Vec<u8>>> {
+        match self.try_peek_pes_packet() {
+            Ok(packet) => packet,
+            Err(e) => {
+                log::debug!("Failed to peek a PES packet: {}", e);
+                None
+            }
+        }
+    }
+
+    fn try_peek_pes_packet(&mut self) -> Result<Option<&PesPacket<Vec<u8>>>> {
         if self.peeked_packet.is_none() {
-            self.peeked_packet = self.read_pes_packet().unwrap_or(None);
+            self.peeked_packet = track!(self.read_pes_packet())?;
         }
 
-        self.peeked_packet.as_ref()
+        Ok(self.peeked_packet.as_ref())
     }
 
     fn read_pes_packet(&mut self) -> Result<Option<PesPacket<Vec<u8>>>> {
         let packet = if self.peeked_packet.is_some() {
-            let packet = self.peek_pes_packet().unwrap().to_owned();
-            self.peeked_packet = None;
-            Some(packet)
+            self.peeked_packet.take()
         } else if !self.back_buffer.is_empty() && !self.is_marked {
             self.back_buffer.pop_front()
         } else {
@@ -127,3 +270,34 @@ impl<R: ReadTsPacket> ReadPesPacket for PesPacketReader<R> {
         !self.back_buffer.is_empty()
     }
 }
+#[cfg(feature = "tokio")]
+impl<R: AsyncReadTsPacket> AsyncReadPesPacket for PesPacketReader<R> {
+    fn poll_read_pes_packet(
+        &mut self,
+        cx: &mut Context,
+    ) -> Poll<Result<Option<PesPacket<Vec<u8>>>>> {
+        if let Some(packet) = self.peeked_packet.take() {
+            return Poll::Ready(Ok(Some(packet)));
+        }
+        if self.eos {
+            return Poll::Ready(track!(self.pes_decoder.flush()));
+        }
+
+        loop {
+            let ts_packet = match self.ts_packet_reader.poll_read_ts_packet(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => track!(result)?,
+            };
+            if let Some(ts_packet) = ts_packet {
+                let continuity_error = self.ts_packet_reader.last_continuity_error();
+                let packet = track!(self.process_ts_packet(&ts_packet, continuity_error))?;
+                if let Some(packet) = packet {
+                    return Poll::Ready(Ok(Some(packet)));
+                }
+            } else {
+                self.eos = true;
+                return Poll::Ready(track!(self.pes_decoder.flush()));
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! Fixtures shared by the unit tests.
+use es::{StreamId, StreamType};
+use pes::PesHeader;
+use ts::payload::{Bytes, Pat, Pes, Pmt};
+use ts::{AdaptationFieldControl, ContinuityCounter, EsInfo, Pid, ProgramAssociation,
+         TransportScramblingControl, TsHeader, TsPacket, TsPacketWriter, TsPayload,
+         VersionNumber, WriteTsPacket};
+
+/// PID of the PMT of the program made by `stream_bytes_with()`.
+pub const PMT_PID: u16 = 0x1000;
+
+/// PID of the H.264 stream of the program made by `stream_bytes_with()`.
+pub const ES_PID: u16 = 0x100;
+
+/// Makes a packet of `pid` that carries `payload`.
+///
+/// The `payload_unit_start_indicator` is set unless the payload is `TsPayload::Raw`.
+pub fn packet(pid: u16, counter: u8, payload: TsPayload) -> TsPacket {
+    let is_raw = matches!(payload, TsPayload::Raw(_));
+    TsPacket {
+        header: TsHeader {
+            transport_error_indicator: false,
+            payload_unit_start_indicator: !is_raw,
+            transport_priority: false,
+            pid: Pid::new(pid).unwrap(),
+            transport_scrambling_control: TransportScramblingControl::NotScrambled,
+            adaptation_field_control: if is_raw {
+                AdaptationFieldControl::PayloadOnly
+            } else {
+                AdaptationFieldControl::AdaptationFieldAndPayload
+            },
+            continuity_counter: ContinuityCounter::from_u8(counter).unwrap(),
+        },
+        adaptation_field: None,
+        payload: Some(payload),
+        tp_extra_header: None,
+        position: None,
+    }
+}
+
+/// Makes the first payload of a video PES packet that carries `data`.
+pub fn pes(data: &[u8]) -> TsPayload {
+    TsPayload::Pes(Pes {
+        header: PesHeader {
+            stream_id: StreamId::new(StreamId::VIDEO_MIN),
+            priority: false,
+            data_alignment_indicator: false,
+            copyright: false,
+            original_or_copy: false,
+            pts: None,
+            dts: None,
+            escr: None,
+        },
+        pes_packet_len: 0,
+        data: Bytes::new(data).unwrap(),
+    })
+}
+
+/// Makes a stream of the PAT and PMT of a program that has an H.264 stream on `ES_PID`,
+/// followed by `es_packets`.
+pub fn stream_bytes_with(es_packets: &[TsPacket]) -> Vec<u8> {
+    let table = vec![EsInfo {
+        stream_type: StreamType::H264,
+        elementary_pid: Pid::new(ES_PID).unwrap(),
+        descriptors: Vec::new(),
+    }];
+    stream_bytes_with_table(table, es_packets)
+}
+
+/// Same as `stream_bytes_with()`, but the program has the elementary streams of `table`.
+pub fn stream_bytes_with_table(table: Vec<EsInfo>, es_packets: &[TsPacket]) -> Vec<u8> {
+    let pat = Pat {
+        transport_stream_id: 1,
+        version_number: VersionNumber::new(),
+        table: vec![ProgramAssociation {
+            program_num: 1,
+            program_map_pid: Pid::new(PMT_PID).unwrap(),
+        }],
+    };
+    let pmt = Pmt {
+        program_num: 1,
+        pcr_pid: None,
+        version_number: VersionNumber::new(),
+        program_descriptors: Vec::new(),
+        table,
+    };
+    let packets = [
+        packet(0, 0, TsPayload::Pat(pat)),
+        packet(PMT_PID, 0, TsPayload::Pmt(pmt)),
+    ];
+
+    let mut writer = TsPacketWriter::new(Vec::new());
+    for p in packets.iter().chain(es_packets) {
+        track_try_unwrap!(writer.write_ts_packet(p));
+    }
+    writer.into_stream()
+}
@@ -229,6 +229,8 @@ impl AdaptationExtensionField {
     }
 }
 
+/// Adaptation field control.
+#[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum AdaptationFieldControl {
     PayloadOnly = 0b01,
@@ -236,15 +238,17 @@ pub enum AdaptationFieldControl {
     AdaptationFieldAndPayload = 0b11,
 }
 impl AdaptationFieldControl {
+    /// Returns `true` if the packet has an adaptation field.
     pub fn has_adaptation_field(&self) -> bool {
         *self != AdaptationFieldControl::PayloadOnly
     }
 
+    /// Returns `true` if the packet has a payload.
     pub fn has_payload(&self) -> bool {
         *self != AdaptationFieldControl::AdaptationFieldOnly
     }
 
-    pub fn from_u8(n: u8) -> Result<Self> {
+    pub(super) fn from_u8(n: u8) -> Result<Self> {
         Ok(match n {
             0b01 => AdaptationFieldControl::PayloadOnly,
             0b10 => AdaptationFieldControl::AdaptationFieldOnly,
@@ -0,0 +1,131 @@
+use std::io::{Read, Write};
+
+use ts::descriptor::{self, CaDescriptor};
+use ts::psi::{Psi, PsiTable, PsiTableHeader};
+use ts::{Descriptor, VersionNumber};
+use {ErrorKind, Result};
+
+/// Payload for CAT(Conditional Access Table) packets.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Cat {
+    pub version_number: VersionNumber,
+    pub descriptors: Vec<Descriptor>,
+}
+impl Cat {
+    pub(super) const TABLE_ID: u8 = 1;
+
+    /// Returns the CA descriptors of the table.
+    ///
+    /// The `ca_pid` of each descriptor is the PID of the packets that carry the EMMs
+    /// (Entitlement Management Messages) of the CA system.
+    /// The malformed descriptors are ignored.
+    pub fn ca_descriptors(&self) -> Vec<CaDescriptor> {
+        descriptor::decode_ca_descriptors(&self.descriptors)
+    }
+
+    pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let psi = track!(Psi::read_from(reader))?;
+        track!(Self::from_tables(psi.tables))
+    }
+
+    /// Makes a `Cat` instance from all the sections of a table.
+    pub(super) fn from_tables(tables: Vec<PsiTable>) -> Result<Self> {
+        track_assert!(!tables.is_empty(), ErrorKind::InvalidInput);
+        let last_section_number = tables.len() - 1;
+
+        let mut cat: Option<Cat> = None;
+        for (section_number, table) in tables.into_iter().enumerate() {
+            let header = table.header;
+            track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+            track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.section_number as usize,
+                section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.last_section_number as usize,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+            let cat = cat.get_or_insert_with(|| Cat {
+                version_number: syntax.version_number,
+                descriptors: Vec::new(),
+            });
+            track_assert_eq!(
+                syntax.version_number,
+                cat.version_number,
+                ErrorKind::InvalidInput
+            );
+
+            let mut reader = &syntax.table_data[..];
+            while !reader.is_empty() {
+                cat.descriptors
+                    .push(track!(Descriptor::read_from(&mut reader))?);
+            }
+        }
+        Ok(cat.expect("Never fails"))
+    }
+
+    pub(super) fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        track!(self.to_psi().and_then(|psi| psi.write_to(writer)))
+    }
+
+    fn to_psi(&self) -> Result<Psi> {
+        let mut entries = Vec::with_capacity(self.descriptors.len());
+        for d in &self.descriptors {
+            let mut entry = Vec::new();
+            track!(d.write_to(&mut entry))?;
+            entries.push(entry);
+        }
+
+        let header = PsiTableHeader {
+            table_id: Self::TABLE_ID,
+            private_bit: false,
+        };
+        let tables = track!(PsiTable::split(
+            &header,
+            0xFFFF, // reserved
+            self.version_number,
+            &[],
+            &entries
+        ))?;
+        Ok(Psi { tables })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use ts::Pid;
+
+    use super::*;
+
+    #[test]
+    fn cat() {
+        let ca = CaDescriptor {
+            ca_system_id: 0x0B00,
+            ca_pid: Pid::new(0x1F0).unwrap(),
+            private_data: vec![1, 2, 3],
+        };
+        let cat = Cat {
+            version_number: VersionNumber::from_u8(3).unwrap(),
+            descriptors: vec![
+                ca.to_descriptor().unwrap(),
+                Descriptor {
+                    tag: 0x80,
+                    data: vec![0; 10],
+                },
+            ],
+        };
+        let mut buf = Vec::new();
+        cat.write_to(&mut buf).unwrap();
+        let cat = Cat::read_from(&buf[..]).unwrap();
+        assert_eq!(cat.descriptors.len(), 2);
+        assert_eq!(cat.ca_descriptors(), [ca]);
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+
+use ts::{ContinuityCounter, Pid, TsPacket};
+
+/// Continuity error detected in a transport stream.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ContinuityEvent {
+    pub kind: ContinuityEventKind,
+    pub pid: Pid,
+    pub expected: ContinuityCounter,
+    pub actual: ContinuityCounter,
+
+    /// The byte offset of the packet in the stream.
+    pub offset: u64,
+}
+
+/// Kind of a continuity error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ContinuityEventKind {
+    /// One or more packets are lost before the packet.
+    PacketLoss,
+
+    /// The packet has the same continuity counter as the previous one.
+    DuplicatePacket,
+}
+
+/// Per-PID continuity counter checker.
+#[derive(Debug, Default)]
+pub(crate) struct ContinuityChecker {
+    counters: HashMap<Pid, ContinuityCounter>,
+}
+impl ContinuityChecker {
+    /// Checks the continuity counter of the given packet.
+    ///
+    /// If an error is detected, it returns the kind of the error and the expected counter.
+    pub fn check(&mut self, packet: &TsPacket) -> Option<(ContinuityEventKind, ContinuityCounter)> {
+        let pid = packet.header.pid;
+        if pid.as_u16() == Pid::NULL || packet.payload.is_none() {
+            // The counter is not incremented for the packets without payload.
+            return None;
+        }
+
+        let actual = packet.header.continuity_counter;
+        let last = self.counters.insert(pid, actual);
+        let discontinuity = matches!(
+            packet.adaptation_field,
+            Some(ref a) if a.discontinuity_indicator
+        );
+        let last = match last {
+            Some(last) if !discontinuity => last,
+            _ => return None,
+        };
+
+        let mut expected = last;
+        expected.increment();
+        if actual == expected {
+            None
+        } else if actual == last {
+            Some((ContinuityEventKind::DuplicatePacket, expected))
+        } else {
+            Some((ContinuityEventKind::PacketLoss, expected))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ts::payload::Bytes;
+    use ts::{AdaptationField, AdaptationFieldControl, TransportScramblingControl, TsHeader,
+             TsPayload};
+
+    fn packet(pid: u16, counter: u8, has_payload: bool) -> TsPacket {
+        TsPacket {
+            header: TsHeader {
+                transport_error_indicator: false,
+                payload_unit_start_indicator: false,
+                transport_priority: false,
+                pid: Pid::new(pid).unwrap(),
+                transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: if has_payload {
+                    AdaptationFieldControl::AdaptationFieldAndPayload
+                } else {
+                    AdaptationFieldControl::AdaptationFieldOnly
+                },
+                continuity_counter: ContinuityCounter::from_u8(counter).unwrap(),
+            },
+            adaptation_field: None,
+            payload: if has_payload {
+                Some(TsPayload::Raw(Bytes::new(&[]).unwrap()))
+            } else {
+                None
+            },
+            tp_extra_header: None,
+            position: None,
+        }
+    }
+
+    fn counter(n: u8) -> ContinuityCounter {
+        ContinuityCounter::from_u8(n).unwrap()
+    }
+
+    #[test]
+    fn continuity_checker() {
+        let mut checker = ContinuityChecker::default();
+        assert_eq!(checker.check(&packet(0x100, 14, true)), None);
+        assert_eq!(checker.check(&packet(0x100, 15, true)), None);
+        assert_eq!(checker.check(&packet(0x101, 3, true)), None);
+        assert_eq!(checker.check(&packet(0x100, 0, true)), None);
+
+        // Adaptation field only
+        assert_eq!(checker.check(&packet(0x100, 0, false)), None);
+
+        assert_eq!(
+            checker.check(&packet(0x100, 0, true)),
+            Some((ContinuityEventKind::DuplicatePacket, counter(1)))
+        );
+        assert_eq!(
+            checker.check(&packet(0x100, 3, true)),
+            Some((ContinuityEventKind::PacketLoss, counter(1)))
+        );
+        assert_eq!(checker.check(&packet(0x100, 4, true)), None);
+
+        let mut p = packet(0x100, 9, true);
+        p.adaptation_field = Some(AdaptationField {
+            discontinuity_indicator: true,
+            random_access_indicator: false,
+            es_priority_indicator: false,
+            pcr: None,
+            opcr: None,
+            splice_countdown: None,
+            transport_private_data: Vec::new(),
+            extension: None,
+        });
+        assert_eq!(checker.check(&p), None);
+        assert_eq!(checker.check(&packet(0x100, 10, true)), None);
+    }
+}
@@ -0,0 +1,1333 @@
+//! Program and elementary stream descriptors.
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+use ts::{DvbText, Pid};
+use {ErrorKind, Result};
+
+pub use ts::pmt::Descriptor;
+
+/// Implements `from_descriptor()` and `to_descriptor()` on top of `read_data()` and `write_data()`.
+macro_rules! impl_descriptor {
+    ($t:ident, $tag:expr) => {
+        impl $t {
+            /// Descriptor tag.
+            pub const TAG: u8 = $tag;
+
+            /// Decodes the descriptor.
+            ///
+            /// The trailing bytes that follow the known fields (e.g., the fields added by
+            /// a later revision of the specification) are ignored,
+            /// so they are not written back by `to_descriptor()`.
+            ///
+            /// # Errors
+            ///
+            /// If the tag of `descriptor` does not match or the data is malformed,
+            /// it will return an `ErrorKind::InvalidInput` error.
+            pub fn from_descriptor(descriptor: &Descriptor) -> Result<Self> {
+                track_assert_eq!(descriptor.tag, Self::TAG, ErrorKind::InvalidInput);
+                let mut reader = &descriptor.data[..];
+                let this = track!(Self::read_data(&mut reader))?;
+                if !reader.is_empty() {
+                    log::debug!(
+                        "Ignored trailing bytes: tag={}, len={}",
+                        Self::TAG,
+                        reader.len()
+                    );
+                }
+                Ok(this)
+            }
+
+            /// Encodes the descriptor.
+            pub fn to_descriptor(&self) -> Result<Descriptor> {
+                let mut data = Vec::new();
+                track!(self.write_data(&mut data))?;
+                track_assert!(
+                    data.len() <= 0xFF,
+                    ErrorKind::InvalidInput,
+                    "Too large descriptor: {} bytes",
+                    data.len()
+                );
+                Ok(Descriptor {
+                    tag: Self::TAG,
+                    data,
+                })
+            }
+        }
+    };
+}
+
+/// Descriptor decoded according to its tag.
+///
+/// The tags of the DVB descriptors (EN 300 468) are assumed for the user private range (`0x40..`).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypedDescriptor {
+    Registration(RegistrationDescriptor),
+    DataStreamAlignment(DataStreamAlignmentDescriptor),
+    Ca(CaDescriptor),
+    Iso639Language(Iso639LanguageDescriptor),
+    MaximumBitrate(MaximumBitrateDescriptor),
+    Mpeg4Audio(Mpeg4AudioDescriptor),
+    AvcVideo(AvcVideoDescriptor),
+    HevcVideo(HevcVideoDescriptor),
+    NetworkName(NetworkNameDescriptor),
+    ServiceList(ServiceListDescriptor),
+    SatelliteDeliverySystem(SatelliteDeliverySystemDescriptor),
+    CableDeliverySystem(CableDeliverySystemDescriptor),
+    Service(ServiceDescriptor),
+    StreamIdentifier(StreamIdentifierDescriptor),
+    Teletext(TeletextDescriptor),
+    Subtitling(SubtitlingDescriptor),
+    TerrestrialDeliverySystem(TerrestrialDeliverySystemDescriptor),
+    Ac3(Ac3Descriptor),
+    EnhancedAc3(EnhancedAc3Descriptor),
+
+    /// Descriptor that has an unsupported tag.
+    Raw(Descriptor),
+}
+impl TypedDescriptor {
+    /// Decodes the given descriptor.
+    ///
+    /// # Errors
+    ///
+    /// If the tag of `descriptor` is supported but the data is malformed,
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub fn from_descriptor(descriptor: &Descriptor) -> Result<Self> {
+        let d = descriptor;
+        Ok(match d.tag {
+            RegistrationDescriptor::TAG => {
+                TypedDescriptor::Registration(track!(RegistrationDescriptor::from_descriptor(d))?)
+            }
+            DataStreamAlignmentDescriptor::TAG => TypedDescriptor::DataStreamAlignment(track!(
+                DataStreamAlignmentDescriptor::from_descriptor(d)
+            )?),
+            CaDescriptor::TAG => TypedDescriptor::Ca(track!(CaDescriptor::from_descriptor(d))?),
+            Iso639LanguageDescriptor::TAG => TypedDescriptor::Iso639Language(track!(
+                Iso639LanguageDescriptor::from_descriptor(d)
+            )?),
+            MaximumBitrateDescriptor::TAG => TypedDescriptor::MaximumBitrate(track!(
+                MaximumBitrateDescriptor::from_descriptor(d)
+            )?),
+            Mpeg4AudioDescriptor::TAG => {
+                TypedDescriptor::Mpeg4Audio(track!(Mpeg4AudioDescriptor::from_descriptor(d))?)
+            }
+            AvcVideoDescriptor::TAG => {
+                TypedDescriptor::AvcVideo(track!(AvcVideoDescriptor::from_descriptor(d))?)
+            }
+            HevcVideoDescriptor::TAG => {
+                TypedDescriptor::HevcVideo(track!(HevcVideoDescriptor::from_descriptor(d))?)
+            }
+            NetworkNameDescriptor::TAG => {
+                TypedDescriptor::NetworkName(track!(NetworkNameDescriptor::from_descriptor(d))?)
+            }
+            ServiceListDescriptor::TAG => {
+                TypedDescriptor::ServiceList(track!(ServiceListDescriptor::from_descriptor(d))?)
+            }
+            SatelliteDeliverySystemDescriptor::TAG => TypedDescriptor::SatelliteDeliverySystem(
+                track!(SatelliteDeliverySystemDescriptor::from_descriptor(d))?,
+            ),
+            CableDeliverySystemDescriptor::TAG => TypedDescriptor::CableDeliverySystem(track!(
+                CableDeliverySystemDescriptor::from_descriptor(d)
+            )?),
+            ServiceDescriptor::TAG => {
+                TypedDescriptor::Service(track!(ServiceDescriptor::from_descriptor(d))?)
+            }
+            StreamIdentifierDescriptor::TAG => TypedDescriptor::StreamIdentifier(track!(
+                StreamIdentifierDescriptor::from_descriptor(d)
+            )?),
+            TeletextDescriptor::TAG => {
+                TypedDescriptor::Teletext(track!(TeletextDescriptor::from_descriptor(d))?)
+            }
+            SubtitlingDescriptor::TAG => {
+                TypedDescriptor::Subtitling(track!(SubtitlingDescriptor::from_descriptor(d))?)
+            }
+            TerrestrialDeliverySystemDescriptor::TAG => {
+                TypedDescriptor::TerrestrialDeliverySystem(track!(
+                    TerrestrialDeliverySystemDescriptor::from_descriptor(d)
+                )?)
+            }
+            Ac3Descriptor::TAG => TypedDescriptor::Ac3(track!(Ac3Descriptor::from_descriptor(d))?),
+            EnhancedAc3Descriptor::TAG => {
+                TypedDescriptor::EnhancedAc3(track!(EnhancedAc3Descriptor::from_descriptor(d))?)
+            }
+            _ => TypedDescriptor::Raw(d.clone()),
+        })
+    }
+
+    /// Encodes the descriptor.
+    pub fn to_descriptor(&self) -> Result<Descriptor> {
+        match *self {
+            TypedDescriptor::Registration(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::DataStreamAlignment(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Ca(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Iso639Language(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::MaximumBitrate(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Mpeg4Audio(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::AvcVideo(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::HevcVideo(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::NetworkName(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::ServiceList(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::SatelliteDeliverySystem(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::CableDeliverySystem(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Service(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::StreamIdentifier(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Teletext(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Subtitling(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::TerrestrialDeliverySystem(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Ac3(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::EnhancedAc3(ref x) => track!(x.to_descriptor()),
+            TypedDescriptor::Raw(ref x) => Ok(x.clone()),
+        }
+    }
+}
+
+/// Registration descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RegistrationDescriptor {
+    /// The format identifier registered by the registration authority (e.g., `b"CUEI"`).
+    pub format_identifier: [u8; 4],
+
+    pub additional_identification_info: Vec<u8>,
+}
+impl_descriptor!(RegistrationDescriptor, 0x05);
+impl RegistrationDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mut format_identifier = [0; 4];
+        track_io!(reader.read_exact(&mut format_identifier))?;
+        let additional_identification_info = reader.to_owned();
+        *reader = &[];
+        Ok(RegistrationDescriptor {
+            format_identifier,
+            additional_identification_info,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_all(&self.format_identifier))?;
+        track_io!(writer.write_all(&self.additional_identification_info))?;
+        Ok(())
+    }
+}
+
+/// Data stream alignment descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DataStreamAlignmentDescriptor {
+    pub alignment_type: u8,
+}
+impl_descriptor!(DataStreamAlignmentDescriptor, 0x06);
+impl DataStreamAlignmentDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let alignment_type = track_io!(reader.read_u8())?;
+        Ok(DataStreamAlignmentDescriptor { alignment_type })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.alignment_type))?;
+        Ok(())
+    }
+}
+
+/// Conditional access descriptor.
+///
+/// In a CAT, `ca_pid` is the PID of the EMMs (Entitlement Management Messages).
+/// In a PMT, it is the PID of the ECMs (Entitlement Control Messages).
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CaDescriptor {
+    pub ca_system_id: u16,
+    pub ca_pid: Pid,
+    pub private_data: Vec<u8>,
+}
+impl_descriptor!(CaDescriptor, 0x09);
+impl CaDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let ca_system_id = track_io!(reader.read_u16::<BigEndian>())?;
+        let ca_pid = track!(Pid::read_from(&mut *reader))?;
+        let private_data = reader.to_owned();
+        *reader = &[];
+        Ok(CaDescriptor {
+            ca_system_id,
+            ca_pid,
+            private_data,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u16::<BigEndian>(self.ca_system_id))?;
+        track!(self.ca_pid.write_to(&mut writer))?;
+        track_io!(writer.write_all(&self.private_data))?;
+        Ok(())
+    }
+}
+
+/// ISO 639 language descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iso639LanguageDescriptor {
+    pub languages: Vec<Iso639Language>,
+}
+impl_descriptor!(Iso639LanguageDescriptor, 0x0A);
+impl Iso639LanguageDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mut languages = Vec::new();
+        while !reader.is_empty() {
+            let language_code = track!(read_language_code(&mut *reader))?;
+            let audio_type = track_io!(reader.read_u8())?;
+            languages.push(Iso639Language {
+                language_code,
+                audio_type,
+            });
+        }
+        Ok(Iso639LanguageDescriptor { languages })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        for l in &self.languages {
+            track_io!(writer.write_all(&l.language_code))?;
+            track_io!(writer.write_u8(l.audio_type))?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of an ISO 639 language descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iso639Language {
+    /// The ISO 639-2 language code (e.g., `b"eng"`).
+    pub language_code: [u8; 3],
+
+    /// `0` (undefined), `1` (clean effects), `2` (hearing impaired),
+    /// `3` (visual impaired commentary) or a reserved value.
+    pub audio_type: u8,
+}
+
+/// Maximum bitrate descriptor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MaximumBitrateDescriptor {
+    /// The maximum bitrate in units of 50 bytes/second.
+    pub maximum_bitrate: u32,
+}
+impl_descriptor!(MaximumBitrateDescriptor, 0x0E);
+impl MaximumBitrateDescriptor {
+    /// Maximum value of `maximum_bitrate`.
+    pub const MAX: u32 = (1 << 22) - 1;
+
+    /// Returns the maximum bitrate in bits per second.
+    pub fn bits_per_second(&self) -> u64 {
+        u64::from(self.maximum_bitrate) * 50 * 8
+    }
+
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let n = track_io!(reader.read_u24::<BigEndian>())?;
+        Ok(MaximumBitrateDescriptor {
+            maximum_bitrate: n & Self::MAX,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(
+            self.maximum_bitrate <= Self::MAX,
+            ErrorKind::InvalidInput,
+            "Too large bitrate: {}",
+            self.maximum_bitrate
+        );
+        let n = 0b1100_0000_0000_0000_0000_0000 | self.maximum_bitrate;
+        track_io!(writer.write_u24::<BigEndian>(n))?;
+        Ok(())
+    }
+}
+
+/// MPEG-4 audio descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Mpeg4AudioDescriptor {
+    pub mpeg4_audio_profile_and_level: u8,
+}
+impl_descriptor!(Mpeg4AudioDescriptor, 0x1C);
+impl Mpeg4AudioDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mpeg4_audio_profile_and_level = track_io!(reader.read_u8())?;
+        Ok(Mpeg4AudioDescriptor {
+            mpeg4_audio_profile_and_level,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.mpeg4_audio_profile_and_level))?;
+        Ok(())
+    }
+}
+
+/// AVC video descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AvcVideoDescriptor {
+    pub profile_idc: u8,
+
+    /// The `constraint_set0_flag` to `constraint_set5_flag` (MSB first)
+    /// and the two bits of `AVC_compatible_flags`.
+    pub constraint_flags: u8,
+
+    pub level_idc: u8,
+    pub avc_still_present: bool,
+    pub avc_24_hour_picture_flag: bool,
+    pub frame_packing_sei_not_present_flag: bool,
+}
+impl_descriptor!(AvcVideoDescriptor, 0x28);
+impl AvcVideoDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let profile_idc = track_io!(reader.read_u8())?;
+        let constraint_flags = track_io!(reader.read_u8())?;
+        let level_idc = track_io!(reader.read_u8())?;
+        let n = track_io!(reader.read_u8())?;
+        Ok(AvcVideoDescriptor {
+            profile_idc,
+            constraint_flags,
+            level_idc,
+            avc_still_present: (n & 0b1000_0000) != 0,
+            avc_24_hour_picture_flag: (n & 0b0100_0000) != 0,
+            frame_packing_sei_not_present_flag: (n & 0b0010_0000) != 0,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.profile_idc))?;
+        track_io!(writer.write_u8(self.constraint_flags))?;
+        track_io!(writer.write_u8(self.level_idc))?;
+        let n = ((self.avc_still_present as u8) << 7)
+            | ((self.avc_24_hour_picture_flag as u8) << 6)
+            | ((self.frame_packing_sei_not_present_flag as u8) << 5)
+            | 0b1_1111;
+        track_io!(writer.write_u8(n))?;
+        Ok(())
+    }
+}
+
+/// HEVC video descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HevcVideoDescriptor {
+    pub profile_space: u8,
+    pub tier_flag: bool,
+    pub profile_idc: u8,
+    pub profile_compatibility_indication: u32,
+    pub progressive_source_flag: bool,
+    pub interlaced_source_flag: bool,
+    pub non_packed_constraint_flag: bool,
+    pub frame_only_constraint_flag: bool,
+
+    /// The 44 bits copied from the `general_reserved_zero_43bits` and the following flag.
+    pub copied_44bits: u64,
+
+    pub level_idc: u8,
+
+    /// The `temporal_id_min` and `temporal_id_max` of the temporal layer subset.
+    pub temporal_layer_subset: Option<(u8, u8)>,
+
+    pub hevc_still_present_flag: bool,
+    pub hevc_24hr_picture_present_flag: bool,
+    pub sub_pic_hrd_params_not_present_flag: bool,
+
+    /// `3` means that no HDR/WCG indication is present.
+    pub hdr_wcg_idc: u8,
+}
+impl_descriptor!(HevcVideoDescriptor, 0x38);
+impl HevcVideoDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let n = track_io!(reader.read_u8())?;
+        let profile_space = n >> 6;
+        let tier_flag = (n & 0b0010_0000) != 0;
+        let profile_idc = n & 0b1_1111;
+        let profile_compatibility_indication = track_io!(reader.read_u32::<BigEndian>())?;
+        let n = track_io!(reader.read_u48::<BigEndian>())?;
+        let level_idc = track_io!(reader.read_u8())?;
+        let flags = track_io!(reader.read_u8())?;
+        let temporal_layer_subset = if (flags & 0b1000_0000) != 0 {
+            let min = track_io!(reader.read_u8())? >> 5;
+            let max = track_io!(reader.read_u8())? >> 5;
+            Some((min, max))
+        } else {
+            None
+        };
+        Ok(HevcVideoDescriptor {
+            profile_space,
+            tier_flag,
+            profile_idc,
+            profile_compatibility_indication,
+            progressive_source_flag: (n & (1 << 47)) != 0,
+            interlaced_source_flag: (n & (1 << 46)) != 0,
+            non_packed_constraint_flag: (n & (1 << 45)) != 0,
+            frame_only_constraint_flag: (n & (1 << 44)) != 0,
+            copied_44bits: n & ((1 << 44) - 1),
+            level_idc,
+            temporal_layer_subset,
+            hevc_still_present_flag: (flags & 0b0100_0000) != 0,
+            hevc_24hr_picture_present_flag: (flags & 0b0010_0000) != 0,
+            sub_pic_hrd_params_not_present_flag: (flags & 0b0001_0000) != 0,
+            hdr_wcg_idc: flags & 0b11,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.profile_space <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.profile_idc <= 0b1_1111, ErrorKind::InvalidInput);
+        track_assert!(self.copied_44bits < (1 << 44), ErrorKind::InvalidInput);
+        track_assert!(self.hdr_wcg_idc <= 0b11, ErrorKind::InvalidInput);
+
+        let n = (self.profile_space << 6) | ((self.tier_flag as u8) << 5) | self.profile_idc;
+        track_io!(writer.write_u8(n))?;
+        track_io!(writer.write_u32::<BigEndian>(self.profile_compatibility_indication))?;
+        let n = ((self.progressive_source_flag as u64) << 47)
+            | ((self.interlaced_source_flag as u64) << 46)
+            | ((self.non_packed_constraint_flag as u64) << 45)
+            | ((self.frame_only_constraint_flag as u64) << 44)
+            | self.copied_44bits;
+        track_io!(writer.write_u48::<BigEndian>(n))?;
+        track_io!(writer.write_u8(self.level_idc))?;
+        let n = ((self.temporal_layer_subset.is_some() as u8) << 7)
+            | ((self.hevc_still_present_flag as u8) << 6)
+            | ((self.hevc_24hr_picture_present_flag as u8) << 5)
+            | ((self.sub_pic_hrd_params_not_present_flag as u8) << 4)
+            | 0b1100
+            | self.hdr_wcg_idc;
+        track_io!(writer.write_u8(n))?;
+        if let Some((min, max)) = self.temporal_layer_subset {
+            track_assert!(min <= 0b111, ErrorKind::InvalidInput);
+            track_assert!(max <= 0b111, ErrorKind::InvalidInput);
+            track_io!(writer.write_u8((min << 5) | 0b1_1111))?;
+            track_io!(writer.write_u8((max << 5) | 0b1_1111))?;
+        }
+        Ok(())
+    }
+}
+
+/// DVB network name descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NetworkNameDescriptor {
+    pub network_name: DvbText,
+}
+impl_descriptor!(NetworkNameDescriptor, 0x40);
+impl NetworkNameDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let network_name = DvbText::from_bytes(reader.to_owned());
+        *reader = &[];
+        Ok(NetworkNameDescriptor { network_name })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_all(self.network_name.as_bytes()))?;
+        Ok(())
+    }
+}
+
+/// DVB service list descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceListDescriptor {
+    pub services: Vec<ServiceListEntry>,
+}
+impl_descriptor!(ServiceListDescriptor, 0x41);
+impl ServiceListDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mut services = Vec::new();
+        while !reader.is_empty() {
+            let service_id = track_io!(reader.read_u16::<BigEndian>())?;
+            let service_type = track_io!(reader.read_u8())?;
+            services.push(ServiceListEntry {
+                service_id,
+                service_type,
+            });
+        }
+        Ok(ServiceListDescriptor { services })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        for s in &self.services {
+            track_io!(writer.write_u16::<BigEndian>(s.service_id))?;
+            track_io!(writer.write_u8(s.service_type))?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of a DVB service list descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ServiceListEntry {
+    /// The identifier of the service (i.e., the program number in the PAT).
+    pub service_id: u16,
+
+    /// The type of the service (see `ServiceDescriptor::service_type`).
+    pub service_type: u8,
+}
+
+/// DVB satellite delivery system descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SatelliteDeliverySystemDescriptor {
+    /// The frequency in units of 10 kHz.
+    pub frequency: u32,
+
+    /// The orbital position in units of 0.1 degrees.
+    pub orbital_position: u16,
+
+    /// `true` for the eastern position, `false` for the western position.
+    pub west_east_flag: bool,
+
+    /// `0` (linear horizontal), `1` (linear vertical), `2` (circular left) or `3` (circular right).
+    pub polarization: u8,
+
+    /// `0` (0.35), `1` (0.25) or `2` (0.20). It is always `0` for DVB-S.
+    pub roll_off: u8,
+
+    /// `false` for DVB-S, `true` for DVB-S2.
+    pub modulation_system: bool,
+
+    /// `0` (auto), `1` (QPSK), `2` (8PSK) or `3` (16-QAM).
+    pub modulation_type: u8,
+
+    /// The symbol rate in units of 100 symbols per second.
+    pub symbol_rate: u32,
+
+    /// The inner FEC scheme (e.g., `1` for 1/2, `15` for no convolutional coding).
+    pub fec_inner: u8,
+}
+impl_descriptor!(SatelliteDeliverySystemDescriptor, 0x43);
+impl SatelliteDeliverySystemDescriptor {
+    /// Returns the frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        u64::from(self.frequency) * 10_000
+    }
+
+    /// Returns the symbol rate in symbols per second.
+    pub fn symbols_per_second(&self) -> u64 {
+        u64::from(self.symbol_rate) * 100
+    }
+
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let frequency = track!(from_bcd(track_io!(reader.read_u32::<BigEndian>())?))?;
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        let orbital_position = track!(from_bcd(u32::from(n)))? as u16;
+        let b = track_io!(reader.read_u8())?;
+        let n = track_io!(reader.read_u32::<BigEndian>())?;
+        Ok(SatelliteDeliverySystemDescriptor {
+            frequency,
+            orbital_position,
+            west_east_flag: (b & 0b1000_0000) != 0,
+            polarization: (b >> 5) & 0b11,
+            roll_off: (b >> 3) & 0b11,
+            modulation_system: (b & 0b0000_0100) != 0,
+            modulation_type: b & 0b11,
+            symbol_rate: track!(from_bcd(n >> 4))?,
+            fec_inner: (n & 0b1111) as u8,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.polarization <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.roll_off <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.modulation_type <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.fec_inner <= 0b1111, ErrorKind::InvalidInput);
+        track_io!(writer.write_u32::<BigEndian>(track!(to_bcd(self.frequency, 8))?))?;
+        let n = track!(to_bcd(u32::from(self.orbital_position), 4))?;
+        track_io!(writer.write_u16::<BigEndian>(n as u16))?;
+        let b = ((self.west_east_flag as u8) << 7)
+            | (self.polarization << 5)
+            | (self.roll_off << 3)
+            | ((self.modulation_system as u8) << 2)
+            | self.modulation_type;
+        track_io!(writer.write_u8(b))?;
+        let n = (track!(to_bcd(self.symbol_rate, 7))? << 4) | u32::from(self.fec_inner);
+        track_io!(writer.write_u32::<BigEndian>(n))?;
+        Ok(())
+    }
+}
+
+/// DVB cable delivery system descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CableDeliverySystemDescriptor {
+    /// The frequency in units of 100 Hz.
+    pub frequency: u32,
+
+    /// The outer FEC scheme (`1` for no outer FEC, `2` for RS(204/188)).
+    pub fec_outer: u8,
+
+    /// `1` (16-QAM), `2` (32-QAM), `3` (64-QAM), `4` (128-QAM) or `5` (256-QAM).
+    pub modulation: u8,
+
+    /// The symbol rate in units of 100 symbols per second.
+    pub symbol_rate: u32,
+
+    /// The inner FEC scheme (e.g., `1` for 1/2, `15` for no convolutional coding).
+    pub fec_inner: u8,
+}
+impl_descriptor!(CableDeliverySystemDescriptor, 0x44);
+impl CableDeliverySystemDescriptor {
+    /// Returns the frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        u64::from(self.frequency) * 100
+    }
+
+    /// Returns the symbol rate in symbols per second.
+    pub fn symbols_per_second(&self) -> u64 {
+        u64::from(self.symbol_rate) * 100
+    }
+
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let frequency = track!(from_bcd(track_io!(reader.read_u32::<BigEndian>())?))?;
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        let modulation = track_io!(reader.read_u8())?;
+        let m = track_io!(reader.read_u32::<BigEndian>())?;
+        Ok(CableDeliverySystemDescriptor {
+            frequency,
+            fec_outer: (n & 0b1111) as u8,
+            modulation,
+            symbol_rate: track!(from_bcd(m >> 4))?,
+            fec_inner: (m & 0b1111) as u8,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.fec_outer <= 0b1111, ErrorKind::InvalidInput);
+        track_assert!(self.fec_inner <= 0b1111, ErrorKind::InvalidInput);
+        track_io!(writer.write_u32::<BigEndian>(track!(to_bcd(self.frequency, 8))?))?;
+        track_io!(writer.write_u16::<BigEndian>(0xFFF0 | u16::from(self.fec_outer)))?;
+        track_io!(writer.write_u8(self.modulation))?;
+        let n = (track!(to_bcd(self.symbol_rate, 7))? << 4) | u32::from(self.fec_inner);
+        track_io!(writer.write_u32::<BigEndian>(n))?;
+        Ok(())
+    }
+}
+
+/// DVB service descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceDescriptor {
+    /// The type of the service (e.g., `0x01` for digital television).
+    pub service_type: u8,
+
+    pub service_provider_name: DvbText,
+    pub service_name: DvbText,
+}
+impl_descriptor!(ServiceDescriptor, 0x48);
+impl ServiceDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let service_type = track_io!(reader.read_u8())?;
+        let service_provider_name = track!(read_text(&mut *reader))?;
+        let service_name = track!(read_text(&mut *reader))?;
+        Ok(ServiceDescriptor {
+            service_type,
+            service_provider_name,
+            service_name,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.service_type))?;
+        track!(write_text(&mut writer, &self.service_provider_name))?;
+        track!(write_text(&mut writer, &self.service_name))?;
+        Ok(())
+    }
+}
+
+/// DVB stream identifier descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StreamIdentifierDescriptor {
+    pub component_tag: u8,
+}
+impl_descriptor!(StreamIdentifierDescriptor, 0x52);
+impl StreamIdentifierDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let component_tag = track_io!(reader.read_u8())?;
+        Ok(StreamIdentifierDescriptor { component_tag })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u8(self.component_tag))?;
+        Ok(())
+    }
+}
+
+/// DVB teletext descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TeletextDescriptor {
+    pub pages: Vec<TeletextPage>,
+}
+impl_descriptor!(TeletextDescriptor, 0x56);
+impl TeletextDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mut pages = Vec::new();
+        while !reader.is_empty() {
+            let language_code = track!(read_language_code(&mut *reader))?;
+            let n = track_io!(reader.read_u8())?;
+            let page_number = track_io!(reader.read_u8())?;
+            pages.push(TeletextPage {
+                language_code,
+                teletext_type: n >> 3,
+                magazine_number: n & 0b111,
+                page_number,
+            });
+        }
+        Ok(TeletextDescriptor { pages })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        for p in &self.pages {
+            track_assert!(p.teletext_type <= 0b1_1111, ErrorKind::InvalidInput);
+            track_assert!(p.magazine_number <= 0b111, ErrorKind::InvalidInput);
+            track_io!(writer.write_all(&p.language_code))?;
+            track_io!(writer.write_u8((p.teletext_type << 3) | p.magazine_number))?;
+            track_io!(writer.write_u8(p.page_number))?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of a DVB teletext descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TeletextPage {
+    pub language_code: [u8; 3],
+
+    /// `1` (initial page), `2` (subtitle page), `5` (subtitle page for hearing impaired), etc.
+    pub teletext_type: u8,
+
+    pub magazine_number: u8,
+
+    /// The page number in binary-coded decimal.
+    pub page_number: u8,
+}
+
+/// DVB subtitling descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SubtitlingDescriptor {
+    pub subtitles: Vec<Subtitling>,
+}
+impl_descriptor!(SubtitlingDescriptor, 0x59);
+impl SubtitlingDescriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let mut subtitles = Vec::new();
+        while !reader.is_empty() {
+            let language_code = track!(read_language_code(&mut *reader))?;
+            let subtitling_type = track_io!(reader.read_u8())?;
+            let composition_page_id = track_io!(reader.read_u16::<BigEndian>())?;
+            let ancillary_page_id = track_io!(reader.read_u16::<BigEndian>())?;
+            subtitles.push(Subtitling {
+                language_code,
+                subtitling_type,
+                composition_page_id,
+                ancillary_page_id,
+            });
+        }
+        Ok(SubtitlingDescriptor { subtitles })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        for s in &self.subtitles {
+            track_io!(writer.write_all(&s.language_code))?;
+            track_io!(writer.write_u8(s.subtitling_type))?;
+            track_io!(writer.write_u16::<BigEndian>(s.composition_page_id))?;
+            track_io!(writer.write_u16::<BigEndian>(s.ancillary_page_id))?;
+        }
+        Ok(())
+    }
+}
+
+/// An entry of a DVB subtitling descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Subtitling {
+    pub language_code: [u8; 3],
+    pub subtitling_type: u8,
+    pub composition_page_id: u16,
+    pub ancillary_page_id: u16,
+}
+
+/// DVB terrestrial delivery system descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TerrestrialDeliverySystemDescriptor {
+    /// The centre frequency in units of 10 Hz.
+    pub centre_frequency: u32,
+
+    /// `0` (8 MHz), `1` (7 MHz), `2` (6 MHz), `3` (5 MHz).
+    pub bandwidth: u8,
+
+    /// `true` if the stream is the high priority (or non-hierarchical) stream.
+    pub priority: bool,
+
+    /// `false` if at least one elementary stream uses time slicing.
+    pub time_slicing_indicator: bool,
+
+    /// `false` if at least one elementary stream uses MPE-FEC.
+    pub mpe_fec_indicator: bool,
+
+    /// `0` (QPSK), `1` (16-QAM) or `2` (64-QAM).
+    pub constellation: u8,
+
+    pub hierarchy_information: u8,
+
+    /// `0` (1/2), `1` (2/3), `2` (3/4), `3` (5/6) or `4` (7/8).
+    pub code_rate_hp_stream: u8,
+
+    /// Same as `code_rate_hp_stream`.
+    pub code_rate_lp_stream: u8,
+
+    /// `0` (1/32), `1` (1/16), `2` (1/8) or `3` (1/4).
+    pub guard_interval: u8,
+
+    /// `0` (2k mode), `1` (8k mode) or `2` (4k mode).
+    pub transmission_mode: u8,
+
+    /// `true` if other frequencies are in use.
+    pub other_frequency_flag: bool,
+}
+impl_descriptor!(TerrestrialDeliverySystemDescriptor, 0x5A);
+impl TerrestrialDeliverySystemDescriptor {
+    /// Returns the centre frequency in Hz.
+    pub fn frequency_hz(&self) -> u64 {
+        u64::from(self.centre_frequency) * 10
+    }
+
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let centre_frequency = track_io!(reader.read_u32::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        let _reserved = track_io!(reader.read_u32::<BigEndian>())?;
+        Ok(TerrestrialDeliverySystemDescriptor {
+            centre_frequency,
+            bandwidth: b >> 5,
+            priority: (b & 0b0001_0000) != 0,
+            time_slicing_indicator: (b & 0b0000_1000) != 0,
+            mpe_fec_indicator: (b & 0b0000_0100) != 0,
+            constellation: (n >> 14) as u8,
+            hierarchy_information: ((n >> 11) & 0b111) as u8,
+            code_rate_hp_stream: ((n >> 8) & 0b111) as u8,
+            code_rate_lp_stream: ((n >> 5) & 0b111) as u8,
+            guard_interval: ((n >> 3) & 0b11) as u8,
+            transmission_mode: ((n >> 1) & 0b11) as u8,
+            other_frequency_flag: (n & 1) != 0,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.bandwidth <= 0b111, ErrorKind::InvalidInput);
+        track_assert!(self.constellation <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.hierarchy_information <= 0b111, ErrorKind::InvalidInput);
+        track_assert!(self.code_rate_hp_stream <= 0b111, ErrorKind::InvalidInput);
+        track_assert!(self.code_rate_lp_stream <= 0b111, ErrorKind::InvalidInput);
+        track_assert!(self.guard_interval <= 0b11, ErrorKind::InvalidInput);
+        track_assert!(self.transmission_mode <= 0b11, ErrorKind::InvalidInput);
+        track_io!(writer.write_u32::<BigEndian>(self.centre_frequency))?;
+        let b = (self.bandwidth << 5)
+            | ((self.priority as u8) << 4)
+            | ((self.time_slicing_indicator as u8) << 3)
+            | ((self.mpe_fec_indicator as u8) << 2)
+            | 0b11;
+        track_io!(writer.write_u8(b))?;
+        let n = (u16::from(self.constellation) << 14)
+            | (u16::from(self.hierarchy_information) << 11)
+            | (u16::from(self.code_rate_hp_stream) << 8)
+            | (u16::from(self.code_rate_lp_stream) << 5)
+            | (u16::from(self.guard_interval) << 3)
+            | (u16::from(self.transmission_mode) << 1)
+            | self.other_frequency_flag as u16;
+        track_io!(writer.write_u16::<BigEndian>(n))?;
+        track_io!(writer.write_u32::<BigEndian>(0xFFFF_FFFF))?; // reserved_future_use
+        Ok(())
+    }
+}
+
+/// DVB AC-3 descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Ac3Descriptor {
+    pub component_type: Option<u8>,
+    pub bsid: Option<u8>,
+    pub mainid: Option<u8>,
+    pub asvc: Option<u8>,
+    pub additional_info: Vec<u8>,
+}
+impl_descriptor!(Ac3Descriptor, 0x6A);
+impl Ac3Descriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let flags = track_io!(reader.read_u8())?;
+        let component_type = track!(read_optional_u8(&mut *reader, flags, 7))?;
+        let bsid = track!(read_optional_u8(&mut *reader, flags, 6))?;
+        let mainid = track!(read_optional_u8(&mut *reader, flags, 5))?;
+        let asvc = track!(read_optional_u8(&mut *reader, flags, 4))?;
+        let additional_info = reader.to_owned();
+        *reader = &[];
+        Ok(Ac3Descriptor {
+            component_type,
+            bsid,
+            mainid,
+            asvc,
+            additional_info,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        let fields = [self.component_type, self.bsid, self.mainid, self.asvc];
+        let flags = fields
+            .iter()
+            .enumerate()
+            .fold(0b1111, |n, (i, f)| n | ((f.is_some() as u8) << (7 - i)));
+        track_io!(writer.write_u8(flags))?;
+        for &n in fields.iter().flatten() {
+            track_io!(writer.write_u8(n))?;
+        }
+        track_io!(writer.write_all(&self.additional_info))?;
+        Ok(())
+    }
+}
+
+/// DVB enhanced AC-3 descriptor.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EnhancedAc3Descriptor {
+    pub component_type: Option<u8>,
+    pub bsid: Option<u8>,
+    pub mainid: Option<u8>,
+    pub asvc: Option<u8>,
+    pub mixinfoexists: bool,
+    pub substream1: Option<u8>,
+    pub substream2: Option<u8>,
+    pub substream3: Option<u8>,
+    pub additional_info: Vec<u8>,
+}
+impl_descriptor!(EnhancedAc3Descriptor, 0x7A);
+impl EnhancedAc3Descriptor {
+    fn read_data(reader: &mut &[u8]) -> Result<Self> {
+        let flags = track_io!(reader.read_u8())?;
+        let component_type = track!(read_optional_u8(&mut *reader, flags, 7))?;
+        let bsid = track!(read_optional_u8(&mut *reader, flags, 6))?;
+        let mainid = track!(read_optional_u8(&mut *reader, flags, 5))?;
+        let asvc = track!(read_optional_u8(&mut *reader, flags, 4))?;
+        let substream1 = track!(read_optional_u8(&mut *reader, flags, 2))?;
+        let substream2 = track!(read_optional_u8(&mut *reader, flags, 1))?;
+        let substream3 = track!(read_optional_u8(&mut *reader, flags, 0))?;
+        let additional_info = reader.to_owned();
+        *reader = &[];
+        Ok(EnhancedAc3Descriptor {
+            component_type,
+            bsid,
+            mainid,
+            asvc,
+            mixinfoexists: (flags & 0b1000) != 0,
+            substream1,
+            substream2,
+            substream3,
+            additional_info,
+        })
+    }
+
+    fn write_data<W: Write>(&self, mut writer: W) -> Result<()> {
+        let fields = [
+            self.component_type,
+            self.bsid,
+            self.mainid,
+            self.asvc,
+            None,
+            self.substream1,
+            self.substream2,
+            self.substream3,
+        ];
+        let flags = fields
+            .iter()
+            .enumerate()
+            .fold(0, |n, (i, f)| n | ((f.is_some() as u8) << (7 - i)))
+            | ((self.mixinfoexists as u8) << 3);
+        track_io!(writer.write_u8(flags))?;
+        for &n in fields.iter().flatten() {
+            track_io!(writer.write_u8(n))?;
+        }
+        track_io!(writer.write_all(&self.additional_info))?;
+        Ok(())
+    }
+}
+
+/// Decodes the CA descriptors in `descriptors`, ignoring the malformed ones.
+pub(super) fn decode_ca_descriptors(descriptors: &[Descriptor]) -> Vec<CaDescriptor> {
+    descriptors
+        .iter()
+        .filter(|d| d.tag == CaDescriptor::TAG)
+        .filter_map(|d| CaDescriptor::from_descriptor(d).ok())
+        .collect()
+}
+
+fn read_language_code<R: Read>(mut reader: R) -> Result<[u8; 3]> {
+    let mut code = [0; 3];
+    track_io!(reader.read_exact(&mut code))?;
+    Ok(code)
+}
+
+/// Reads a text preceded by its length byte.
+fn read_text<R: Read>(mut reader: R) -> Result<DvbText> {
+    let len = track_io!(reader.read_u8())?;
+    let mut bytes = vec![0; len as usize];
+    track_io!(reader.read_exact(&mut bytes))?;
+    Ok(DvbText::from_bytes(bytes))
+}
+
+fn write_text<W: Write>(mut writer: W, text: &DvbText) -> Result<()> {
+    let bytes = text.as_bytes();
+    track_assert!(bytes.len() <= 0xFF, ErrorKind::InvalidInput);
+    track_io!(writer.write_u8(bytes.len() as u8))?;
+    track_io!(writer.write_all(bytes))?;
+    Ok(())
+}
+
+/// Decodes a binary-coded decimal number.
+fn from_bcd(bcd: u32) -> Result<u32> {
+    let mut n = 0;
+    for i in (0..8).rev() {
+        let digit = (bcd >> (i * 4)) & 0b1111;
+        track_assert!(digit < 10, ErrorKind::InvalidInput, "Invalid BCD: {:#x}", bcd);
+        n = n * 10 + digit;
+    }
+    Ok(n)
+}
+
+/// Encodes a number as a binary-coded decimal of `digits` digits.
+fn to_bcd(mut n: u32, digits: u32) -> Result<u32> {
+    let mut bcd = 0;
+    for i in 0..digits {
+        bcd |= (n % 10) << (i * 4);
+        n /= 10;
+    }
+    track_assert_eq!(n, 0, ErrorKind::InvalidInput, "Too large number for {} digits", digits);
+    Ok(bcd)
+}
+
+fn read_optional_u8<R: Read>(mut reader: R, flags: u8, bit: u8) -> Result<Option<u8>> {
+    if (flags & (1 << bit)) == 0 {
+        return Ok(None);
+    }
+    let n = track_io!(reader.read_u8())?;
+    Ok(Some(n))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(bytes: &[u8]) -> TypedDescriptor {
+        let descriptor = Descriptor {
+            tag: bytes[0],
+            data: bytes[2..].to_owned(),
+        };
+        assert_eq!(bytes[1] as usize, descriptor.data.len());
+        let typed = track_try_unwrap!(TypedDescriptor::from_descriptor(&descriptor));
+        assert_eq!(track_try_unwrap!(typed.to_descriptor()), descriptor);
+        typed
+    }
+
+    #[test]
+    fn typed_descriptors() {
+        let d = round_trip(&[0x05, 0x04, b'C', b'U', b'E', b'I']);
+        match d {
+            TypedDescriptor::Registration(ref d) => assert_eq!(d.format_identifier, *b"CUEI"),
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x09, 0x06, 0x0B, 0x00, 0xE1, 0xF0, 1, 2]);
+        match d {
+            TypedDescriptor::Ca(ref d) => {
+                assert_eq!(d.ca_system_id, 0x0B00);
+                assert_eq!(d.ca_pid.as_u16(), 0x1F0);
+                assert_eq!(d.private_data, [1, 2]);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x0A, 0x08, b'e', b'n', b'g', 0, b'j', b'p', b'n', 3]);
+        match d {
+            TypedDescriptor::Iso639Language(ref d) => {
+                assert_eq!(d.languages.len(), 2);
+                assert_eq!(d.languages[1].language_code, *b"jpn");
+                assert_eq!(d.languages[1].audio_type, 3);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x0E, 0x03, 0xC0, 0x4E, 0x20]);
+        match d {
+            TypedDescriptor::MaximumBitrate(ref d) => {
+                assert_eq!(d.maximum_bitrate, 20000);
+                assert_eq!(d.bits_per_second(), 8_000_000);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x28, 0x04, 0x64, 0x00, 0x28, 0x3F]);
+        match d {
+            TypedDescriptor::AvcVideo(ref d) => {
+                assert_eq!((d.profile_idc, d.level_idc), (100, 40));
+                assert!(!d.avc_still_present);
+                assert!(d.frame_packing_sei_not_present_flag);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[
+            0x38, 0x0F, 0x01, 0x60, 0x00, 0x00, 0x00, 0x90, 0x00, 0x00, 0x00, 0x00, 0x00, 0x5D,
+            0xDF, 0x1F, 0x5F,
+        ]);
+        match d {
+            TypedDescriptor::HevcVideo(ref d) => {
+                assert_eq!((d.profile_idc, d.level_idc), (1, 93));
+                assert!(d.progressive_source_flag);
+                assert!(d.frame_only_constraint_flag);
+                assert_eq!(d.temporal_layer_subset, Some((0, 2)));
+                assert!(d.hevc_still_present_flag);
+                assert_eq!(d.hdr_wcg_idc, 3);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x40, 0x04, b'T', b'e', b's', b't']);
+        match d {
+            TypedDescriptor::NetworkName(ref d) => {
+                assert_eq!(track_try_unwrap!(d.network_name.decode()), "Test");
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x41, 0x06, 0x00, 0x01, 0x01, 0x00, 0x02, 0x02]);
+        match d {
+            TypedDescriptor::ServiceList(ref d) => {
+                assert_eq!(d.services.len(), 2);
+                assert_eq!(d.services[1].service_id, 2);
+                assert_eq!(d.services[1].service_type, 2);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[
+            0x43, 0x0B, 0x01, 0x13, 0x62, 0x00, 0x01, 0x92, 0x81, 0x02, 0x20, 0x00, 0x03,
+        ]);
+        match d {
+            TypedDescriptor::SatelliteDeliverySystem(ref d) => {
+                assert_eq!(d.frequency_hz(), 11_362_000_000);
+                assert_eq!(d.orbital_position, 192);
+                assert!(d.west_east_flag);
+                assert_eq!(d.polarization, 0);
+                assert!(!d.modulation_system);
+                assert_eq!(d.modulation_type, 1);
+                assert_eq!(d.symbols_per_second(), 22_000_000);
+                assert_eq!(d.fec_inner, 3);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[
+            0x44, 0x0B, 0x03, 0x46, 0x00, 0x00, 0xFF, 0xF2, 0x05, 0x00, 0x69, 0x00, 0x0F,
+        ]);
+        match d {
+            TypedDescriptor::CableDeliverySystem(ref d) => {
+                assert_eq!(d.frequency_hz(), 346_000_000);
+                assert_eq!(d.fec_outer, 2);
+                assert_eq!(d.modulation, 5);
+                assert_eq!(d.symbols_per_second(), 6_900_000);
+                assert_eq!(d.fec_inner, 15);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[
+            0x5A, 0x0B, 0x02, 0xF7, 0xE3, 0x40, 0x1F, 0x82, 0x02, 0xFF, 0xFF, 0xFF, 0xFF,
+        ]);
+        match d {
+            TypedDescriptor::TerrestrialDeliverySystem(ref d) => {
+                assert_eq!(d.frequency_hz(), 498_000_000);
+                assert_eq!(d.bandwidth, 0);
+                assert!(d.priority);
+                assert_eq!(d.constellation, 2);
+                assert_eq!(d.code_rate_hp_stream, 2);
+                assert_eq!(d.transmission_mode, 1);
+                assert!(!d.other_frequency_flag);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x56, 0x05, b'd', b'e', b'u', 0x11, 0x50]);
+        match d {
+            TypedDescriptor::Teletext(ref d) => {
+                assert_eq!(d.pages[0].teletext_type, 2);
+                assert_eq!(d.pages[0].magazine_number, 1);
+                assert_eq!(d.pages[0].page_number, 0x50);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x59, 0x08, b'f', b'r', b'a', 0x10, 0, 1, 0, 2]);
+        match d {
+            TypedDescriptor::Subtitling(ref d) => {
+                assert_eq!(d.subtitles[0].subtitling_type, 0x10);
+                assert_eq!(d.subtitles[0].composition_page_id, 1);
+                assert_eq!(d.subtitles[0].ancillary_page_id, 2);
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x6A, 0x03, 0xCF, 0x42, 0x08]);
+        match d {
+            TypedDescriptor::Ac3(ref d) => {
+                assert_eq!(
+                    (d.component_type, d.bsid, d.mainid),
+                    (Some(0x42), Some(8), None)
+                );
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        let d = round_trip(&[0x7A, 0x03, 0x8C, 0x42, 0x07]);
+        match d {
+            TypedDescriptor::EnhancedAc3(ref d) => {
+                assert_eq!(d.component_type, Some(0x42));
+                assert!(d.mixinfoexists);
+                assert_eq!(d.substream1, Some(7));
+            }
+            _ => panic!("{:?}", d),
+        }
+
+        round_trip(&[0x06, 0x01, 0x01]);
+        round_trip(&[0x09, 0x04, 0x0B, 0x00, 0xE1, 0x00]);
+        round_trip(&[0x1C, 0x01, 0x58]);
+        round_trip(&[0x52, 0x01, 0x03]);
+        let d = round_trip(&[0xF0, 0x02, 0x01, 0x02]);
+        assert!(matches!(d, TypedDescriptor::Raw(_)));
+
+        let d = Descriptor {
+            tag: 0x0A,
+            data: vec![b'e', b'n', b'g'],
+        };
+        assert!(TypedDescriptor::from_descriptor(&d).is_err());
+
+        // Trailing bytes are ignored
+        let d = Descriptor {
+            tag: 0x06,
+            data: vec![0x01, 0xFF],
+        };
+        let typed = track_try_unwrap!(TypedDescriptor::from_descriptor(&d));
+        let d = track_try_unwrap!(typed.to_descriptor());
+        assert_eq!(d.data, [0x01]);
+
+        // Invalid BCD digits
+        let d = Descriptor {
+            tag: 0x44,
+            data: vec![0x03, 0x4A, 0, 0, 0xFF, 0xF2, 0x05, 0x00, 0x69, 0x00, 0x0F],
+        };
+        assert!(TypedDescriptor::from_descriptor(&d).is_err());
+    }
+}
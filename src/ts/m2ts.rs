@@ -0,0 +1,172 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+use time::ClockReference;
+use ts::TsPacket;
+use {ErrorKind, Result};
+
+/// Extra header that precedes each TS packet in a M2TS (BDAV MPEG-2 transport stream) file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TpExtraHeader {
+    copy_permission_indicator: u8,
+    arrival_time_stamp: u32,
+}
+impl TpExtraHeader {
+    /// Size of a header in bytes.
+    pub const SIZE: usize = 4;
+
+    /// Maximum copy permission indicator value.
+    pub const MAX_COPY_PERMISSION_INDICATOR: u8 = (1 << 2) - 1;
+
+    /// Maximum arrival time stamp value.
+    pub const MAX_ARRIVAL_TIME_STAMP: u32 = (1 << 30) - 1;
+
+    /// Makes a new `TpExtraHeader` instance.
+    ///
+    /// # Errors
+    ///
+    /// If `copy_permission_indicator` exceeds `TpExtraHeader::MAX_COPY_PERMISSION_INDICATOR` or
+    /// `arrival_time_stamp` exceeds `TpExtraHeader::MAX_ARRIVAL_TIME_STAMP`,
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub fn new(copy_permission_indicator: u8, arrival_time_stamp: u32) -> Result<Self> {
+        track_assert!(
+            copy_permission_indicator <= Self::MAX_COPY_PERMISSION_INDICATOR,
+            ErrorKind::InvalidInput,
+            "Too large copy permission indicator: {}",
+            copy_permission_indicator
+        );
+        track_assert!(
+            arrival_time_stamp <= Self::MAX_ARRIVAL_TIME_STAMP,
+            ErrorKind::InvalidInput,
+            "Too large arrival time stamp: {}",
+            arrival_time_stamp
+        );
+        Ok(TpExtraHeader {
+            copy_permission_indicator,
+            arrival_time_stamp,
+        })
+    }
+
+    /// Returns the copy permission indicator.
+    pub fn copy_permission_indicator(&self) -> u8 {
+        self.copy_permission_indicator
+    }
+
+    /// Returns the arrival time stamp of the packet.
+    ///
+    /// The value is based on a 27MHz clock and is wrapped around every 2^30 ticks.
+    pub fn arrival_time_stamp(&self) -> u32 {
+        self.arrival_time_stamp
+    }
+
+    pub(super) fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let n = track_io!(reader.read_u32::<BigEndian>())?;
+        Ok(TpExtraHeader {
+            copy_permission_indicator: (n >> 30) as u8,
+            arrival_time_stamp: n & Self::MAX_ARRIVAL_TIME_STAMP,
+        })
+    }
+
+    pub(super) fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let n = (u32::from(self.copy_permission_indicator) << 30) | self.arrival_time_stamp;
+        track_io!(writer.write_u32::<BigEndian>(n))?;
+        Ok(())
+    }
+}
+
+/// Clock that generates the arrival time stamps of M2TS packets.
+///
+/// It assumes that the TS packets arrive at a constant bitrate.
+#[derive(Debug, Clone)]
+pub struct ArrivalTimeClock {
+    initial_time_stamp: u32,
+    bitrate: u64,
+    copy_permission_indicator: u8,
+    packets: u64,
+}
+impl ArrivalTimeClock {
+    /// Makes a new `ArrivalTimeClock` instance.
+    ///
+    /// `initial_time_stamp` is the arrival time stamp of the first packet and
+    /// `bitrate` is the transport rate of the stream in bits per second.
+    ///
+    /// # Errors
+    ///
+    /// If `initial_time_stamp` exceeds `TpExtraHeader::MAX_ARRIVAL_TIME_STAMP` or
+    /// `bitrate` is `0`, it will return an `ErrorKind::InvalidInput` error.
+    pub fn new(initial_time_stamp: u32, bitrate: u64) -> Result<Self> {
+        track_assert!(
+            initial_time_stamp <= TpExtraHeader::MAX_ARRIVAL_TIME_STAMP,
+            ErrorKind::InvalidInput,
+            "Too large arrival time stamp: {}",
+            initial_time_stamp
+        );
+        track_assert_ne!(bitrate, 0, ErrorKind::InvalidInput);
+        Ok(ArrivalTimeClock {
+            initial_time_stamp,
+            bitrate,
+            copy_permission_indicator: 0,
+            packets: 0,
+        })
+    }
+
+    /// Sets the copy permission indicator of the generated headers.
+    ///
+    /// # Errors
+    ///
+    /// If `n` exceeds `TpExtraHeader::MAX_COPY_PERMISSION_INDICATOR`,
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub fn set_copy_permission_indicator(&mut self, n: u8) -> Result<()> {
+        track_assert!(
+            n <= TpExtraHeader::MAX_COPY_PERMISSION_INDICATOR,
+            ErrorKind::InvalidInput,
+            "Too large copy permission indicator: {}",
+            n
+        );
+        self.copy_permission_indicator = n;
+        Ok(())
+    }
+
+    /// Returns the header for the next packet and advances the clock.
+    pub fn next_header(&mut self) -> TpExtraHeader {
+        let bits = u128::from(self.packets) * (TsPacket::SIZE as u128) * 8;
+        let elapsed = bits * u128::from(ClockReference::RESOLUTION) / u128::from(self.bitrate);
+        let arrival_time_stamp = (u128::from(self.initial_time_stamp) + elapsed)
+            & u128::from(TpExtraHeader::MAX_ARRIVAL_TIME_STAMP);
+        self.packets += 1;
+        TpExtraHeader {
+            copy_permission_indicator: self.copy_permission_indicator,
+            arrival_time_stamp: arrival_time_stamp as u32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tp_extra_header() {
+        let header = TpExtraHeader::new(3, 0x1234_5678).unwrap();
+        let mut buf = Vec::new();
+        header.write_to(&mut buf).unwrap();
+        assert_eq!(buf, [0xD2, 0x34, 0x56, 0x78]);
+        assert_eq!(TpExtraHeader::read_from(&buf[..]).unwrap(), header);
+
+        assert!(TpExtraHeader::new(4, 0).is_err());
+        assert!(TpExtraHeader::new(0, 1 << 30).is_err());
+    }
+
+    #[test]
+    fn arrival_time_clock() {
+        // 188 bytes per packet at 1504000 bps => 1ms (27000 ticks) per packet.
+        let mut clock =
+            ArrivalTimeClock::new(TpExtraHeader::MAX_ARRIVAL_TIME_STAMP - 9999, 1_504_000).unwrap();
+        assert_eq!(
+            clock.next_header().arrival_time_stamp(),
+            TpExtraHeader::MAX_ARRIVAL_TIME_STAMP - 9999
+        );
+        assert_eq!(clock.next_header().arrival_time_stamp(), 17_000);
+        assert_eq!(clock.next_header().arrival_time_stamp(), 44_000);
+    }
+}
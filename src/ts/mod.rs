@@ -3,33 +3,87 @@
 //! # References
 //!
 //! - [MPEG transport stream](https://en.wikipedia.org/wiki/MPEG_transport_stream)
-pub use self::adaptation_field::{AdaptationExtensionField, AdaptationField};
-pub use self::packet::{TsHeader, TsPacket, TsPayload};
+pub use self::adaptation_field::{AdaptationExtensionField, AdaptationField,
+                                 AdaptationFieldControl};
+pub use self::continuity::{ContinuityEvent, ContinuityEventKind};
+pub use self::descriptor::CaDescriptor;
+pub use self::m2ts::{ArrivalTimeClock, TpExtraHeader};
+pub use self::nit::{DeliverySystem, TransportStreamInfo};
+pub use self::packet::{PacketPosition, TsHeader, TsPacket, TsPayload};
+pub use self::packet_ref::{TsPacketRef, TsPacketRefIter};
 pub use self::pat::ProgramAssociation;
+pub use self::pid_filter::PidFilter;
 pub use self::pmt::{Descriptor, EsInfo};
-pub use self::reader::{ReadTsPacket, TsPacketReader};
-pub use self::types::{ContinuityCounter, LegalTimeWindow, Pid, PiecewiseRate, SeamlessSplice,
-                      TransportScramblingControl, VersionNumber};
+pub use self::program::{PidKind, Program, ProgramEvent, ProgramMap};
+pub use self::reader::{ReadTsPacket, TableEvent, TsPacketReader};
+#[cfg(feature = "tokio")]
+pub use self::reader::{AsyncReadTsPacket, ReadTsPacketFuture};
+pub use self::sdt::{RunningStatus, ServiceInfo};
+pub use self::seek::SeekClock;
+pub use self::text::DvbText;
+pub use self::types::{ContinuityCounter, LegalTimeWindow, PacketFormat, Pid, PiecewiseRate,
+                      SeamlessSplice, TransportScramblingControl, VersionNumber};
 pub use self::writer::{TsPacketWriter, WriteTsPacket};
+#[cfg(feature = "tokio")]
+pub use self::writer::{AsyncWriteTsPacket, FlushFuture, WriteTsPacketFuture};
+
+#[cfg(feature = "rayon")]
+pub(crate) use self::continuity::ContinuityChecker;
+#[cfg(feature = "rayon")]
+pub(crate) use self::reader::PsiState;
+#[cfg(feature = "rayon")]
+pub(crate) use self::sync::next_packet_boundary;
+
+pub mod descriptor;
 
 pub mod payload {
     //! Transport stream payloads.
 
+    pub use super::cat::Cat;
+    pub use super::nit::Nit;
     pub use super::null::Null;
     pub use super::pat::Pat;
     pub use super::pes::Pes;
     pub use super::pmt::Pmt;
+    pub use super::sdt::Sdt;
+    pub use super::splice::SpliceInfoSection;
     pub use super::types::Bytes;
 }
 
+pub mod scte35 {
+    //! SCTE-35 splice information.
+
+    pub use super::splice::{AvailDescriptor, BreakDuration, DeliveryRestrictions,
+                            DtmfDescriptor, ScheduledSplice, ScheduledSpliceComponent,
+                            ScheduledSpliceEvent, Segmentation, SegmentationComponent,
+                            SegmentationDescriptor, SegmentationType, SpliceCommand,
+                            SpliceDescriptor, SpliceInfoSection, SpliceInsert,
+                            SpliceInsertComponent, SpliceInsertEvent, SpliceSchedule,
+                            SpliceTime, TimeDescriptor};
+}
+
 mod adaptation_field;
+mod cat;
+mod continuity;
+mod m2ts;
+mod nit;
 mod null;
 mod packet;
+mod packet_ref;
 mod pat;
 mod pes;
+mod pid_filter;
 mod pmt;
+mod program;
 mod psi;
 mod reader;
+mod reed_solomon;
+mod sdt;
+mod section;
+mod seek;
+mod splice;
+mod sync;
+mod text;
 mod types;
 mod writer;
 
@@ -43,6 +97,13 @@ mod test {
         let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
         assert_eq!(packet, pat_packet());
         assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(
+            reader.take_program_events(),
+            [ProgramEvent::ProgramAdded {
+                program_num: 1,
+                pmt_pid: Pid::new(480).unwrap(),
+            }]
+        );
 
         let mut writer = TsPacketWriter::new(Vec::new());
         track_try_unwrap!(writer.write_ts_packet(&packet));
@@ -54,6 +115,383 @@ mod test {
         assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
     }
 
+    #[test]
+    fn packet_ref() {
+        let packet = track_try_unwrap!(TsPacketRef::new(pat_packet_bytes()));
+        assert_eq!(packet.pid(), Pid::from(0));
+        assert!(packet.payload_unit_start_indicator());
+        assert!(!packet.transport_error_indicator());
+        assert_eq!(packet.continuity_counter().as_u8(), 1);
+        assert_eq!(track_try_unwrap!(packet.header()), pat_packet().header);
+        assert_eq!(track_try_unwrap!(packet.adaptation_field_bytes()), None);
+        assert_eq!(track_try_unwrap!(packet.payload()), Some(&pat_packet_bytes()[4..]));
+        assert_eq!(track_try_unwrap!(packet.to_packet()), pat_packet());
+
+        let mut bytes = pid17_packet_bytes().to_owned();
+        bytes.extend_from_slice(pat_packet_bytes());
+        let packets = TsPacketRef::iter(&bytes)
+            .map(|p| track_try_unwrap!(p.and_then(|p| p.to_packet())))
+            .collect::<Vec<_>>();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].header.pid, Pid::from(17));
+        assert!(matches!(packets[0].payload, Some(TsPayload::Raw(_))));
+        assert_eq!(packets[1], pat_packet());
+
+        assert!(TsPacketRef::new(&pat_packet_bytes()[1..]).is_err());
+        assert!(TsPacketRef::iter(&bytes[..200]).nth(1).unwrap().is_err());
+    }
+
+    #[test]
+    fn resync() {
+        let mut bytes = vec![0x47, 0x00, 0x12];
+        for _ in 0..3 {
+            bytes.extend_from_slice(pat_packet_bytes());
+        }
+        bytes.extend_from_slice(&pat_packet_bytes()[..100]);
+        bytes.extend_from_slice(&pat_packet_bytes()[101..]);
+        for _ in 0..2 {
+            bytes.extend_from_slice(pat_packet_bytes());
+        }
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        for (i, &offset) in [3, 191, 379, 754, 942].iter().enumerate() {
+            let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+            assert_eq!(packet, pat_packet());
+            assert_eq!(packet.position, position(offset, i as u64));
+        }
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(reader.skipped_bytes(), 3 + TsPacket::SIZE as u64 - 1);
+
+        // All the packets have the same continuity counter.
+        let events = reader.take_continuity_events();
+        assert_eq!(events.len(), 4);
+        assert!(events
+            .iter()
+            .all(|e| e.kind == ContinuityEventKind::DuplicatePacket));
+        assert_eq!(events[0].offset, 3 + TsPacket::SIZE as u64);
+    }
+
+    #[test]
+    fn error_policy() {
+        let mut bytes = Vec::new();
+        for i in 0..3 {
+            bytes.extend_from_slice(pat_packet_bytes());
+            if i == 1 {
+                bytes[TsPacket::SIZE + 20] ^= 0xFF; // CRC32
+            }
+        }
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        reader.set_error_policy(::ErrorPolicy::Strict);
+        assert!(reader.read_ts_packet().is_ok());
+        assert!(reader.try_peek_ts_packet().is_err());
+        assert!(track_try_unwrap!(reader.read_ts_packet()).is_some());
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        reader.set_error_policy(::ErrorPolicy::Strict);
+        assert!(reader.read_ts_packet().is_ok());
+        assert!(reader.peek_ts_packet().is_none());
+        assert!(reader.peek_ts_packet().is_some());
+
+        // Strict mode does not resynchronize silently
+        let mut unsynced = vec![0; 3];
+        for _ in 0..3 {
+            unsynced.extend_from_slice(pat_packet_bytes());
+        }
+        let mut reader = TsPacketReader::new(&unsynced[..]);
+        reader.set_error_policy(::ErrorPolicy::Strict);
+        assert!(reader.read_ts_packet().is_err());
+        for _ in 0..3 {
+            assert!(track_try_unwrap!(reader.read_ts_packet()).is_some());
+        }
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(reader.skipped_bytes(), 3);
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        for _ in 0..2 {
+            assert!(track_try_unwrap!(reader.read_ts_packet()).is_some());
+        }
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(reader.error_count(), 1);
+
+        let errors = ::std::sync::Arc::new(::std::sync::Mutex::new(Vec::new()));
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let errors_clone = errors.clone();
+        reader.set_error_policy(::ErrorPolicy::Callback(Box::new(move |e| {
+            errors_clone.lock().unwrap().push((e.pid, e.offset));
+        })));
+        while track_try_unwrap!(reader.read_ts_packet()).is_some() {}
+        assert_eq!(
+            *errors.lock().unwrap(),
+            [(Pid::from(0), Some(TsPacket::SIZE as u64))]
+        );
+    }
+
+    #[test]
+    fn m2ts() {
+        let mut writer = TsPacketWriter::with_format(Vec::new(), PacketFormat::M2ts);
+        writer.set_arrival_time_clock(track_try_unwrap!(ArrivalTimeClock::new(100, 1_504_000)));
+        for _ in 0..3 {
+            track_try_unwrap!(writer.write_ts_packet(&pat_packet()));
+        }
+        assert_eq!(writer.stream().len(), 3 * PacketFormat::M2ts.packet_size());
+
+        let mut reader = TsPacketReader::with_format(&writer.stream()[..], PacketFormat::M2ts);
+        for i in 0..3 {
+            let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+            let header = packet.tp_extra_header.unwrap();
+            assert_eq!(header.copy_permission_indicator(), 0);
+            assert_eq!(header.arrival_time_stamp(), 100 + i * 27_000);
+            assert_eq!(packet.payload, pat_packet().payload);
+        }
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(reader.skipped_bytes(), 0);
+    }
+
+    #[test]
+    fn m2ts_split_psi() {
+        let mut packet = pat_packet();
+        packet.tp_extra_header = Some(track_try_unwrap!(TpExtraHeader::new(1, 1000)));
+        packet.payload = Some(TsPayload::Pat(payload::Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: (0..100)
+                .map(|i| ProgramAssociation {
+                    program_num: i + 1,
+                    program_map_pid: Pid::new(0x100 + i).unwrap(),
+                })
+                .collect(),
+        }));
+
+        // Without a clock, the arrival time stamp is incremented
+        let mut writer = TsPacketWriter::with_format(Vec::new(), PacketFormat::M2ts);
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        let bytes = writer.into_stream();
+        let mut reader = TsPacketReader::with_format(&bytes[..], PacketFormat::M2ts);
+        let mut count = 0;
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            let header = packet.tp_extra_header.unwrap();
+            assert_eq!(header.copy_permission_indicator(), 1);
+            assert_eq!(header.arrival_time_stamp(), 1000 + count);
+            count += 1;
+        }
+        assert_eq!(count, 3);
+
+        // With a clock, the subsequent packets get the arrival time stamps of the clock
+        let mut writer = TsPacketWriter::with_format(Vec::new(), PacketFormat::M2ts);
+        writer.set_arrival_time_clock(track_try_unwrap!(ArrivalTimeClock::new(100, 1_504_000)));
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        let bytes = writer.into_stream();
+        let mut reader = TsPacketReader::with_format(&bytes[..], PacketFormat::M2ts);
+        let stamps = (0..3)
+            .map(|_| {
+                let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+                packet.tp_extra_header.unwrap().arrival_time_stamp()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(stamps, [1000, 100, 100 + 27_000]);
+    }
+
+    #[test]
+    fn dvb() {
+        let mut writer = TsPacketWriter::with_format(Vec::new(), PacketFormat::Dvb);
+        for _ in 0..3 {
+            track_try_unwrap!(writer.write_ts_packet(&pat_packet()));
+        }
+        let mut bytes = writer.into_stream();
+        assert_eq!(bytes.len(), 3 * PacketFormat::Dvb.packet_size());
+
+        // Correctable errors
+        for i in 0..8 {
+            bytes[20 + i * 10] ^= 0xFF;
+        }
+        // Uncorrectable errors (in the parity bytes)
+        for i in 0..9 {
+            bytes[PacketFormat::Dvb.packet_size() * 2 + TsPacket::SIZE + i] ^= 0x01;
+        }
+
+        let mut reader = TsPacketReader::with_format(&bytes[..], PacketFormat::Dvb);
+        reader.set_reed_solomon_decoding(true);
+        for i in 0..3 {
+            let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+            assert_eq!(packet.header.transport_error_indicator, i == 2);
+            assert_eq!(packet.header.pid, pat_packet().header.pid);
+            assert_eq!(packet.payload, pat_packet().payload);
+        }
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+    }
+
+    #[test]
+    fn multi_packet_pmt() {
+        let pmt = payload::Pmt {
+            program_num: 1,
+            pcr_pid: None,
+            version_number: VersionNumber::new(),
+            program_descriptors: Vec::new(),
+            table: (0..40)
+                .map(|i| EsInfo {
+                    stream_type: ::es::StreamType::AdtsAac,
+                    elementary_pid: Pid::new(0x100 + i).unwrap(),
+                    descriptors: Vec::new(),
+                })
+                .collect(),
+        };
+        let mut section = Vec::new();
+        track_try_unwrap!(pmt.write_to(&mut section));
+        assert!(section.len() > 184);
+
+        let mut bytes = pat_packet_bytes().to_owned();
+        for (i, chunk) in section.chunks(184).enumerate() {
+            let pusi = if i == 0 { 0x40 } else { 0 };
+            bytes.extend_from_slice(&[0x47, pusi | 0x01, 0xE0, 0x10 | i as u8]);
+            bytes.extend_from_slice(chunk);
+            bytes.resize(TsPacket::SIZE * (i + 2), 0xFF);
+        }
+        // A packet of the last elementary stream
+        bytes.extend_from_slice(&[0x47, 0x01, 0x27, 0x10]);
+        bytes.resize(TsPacket::SIZE * 4, 0);
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(packet, pat_packet());
+        for _ in 0..2 {
+            let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+            assert_eq!(packet.header.pid.as_u16(), 480);
+            assert!(matches!(packet.payload, Some(TsPayload::Raw(_))));
+        }
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(packet.header.pid.as_u16(), 0x127);
+        assert!(matches!(packet.payload, Some(TsPayload::Raw(_))));
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+
+        // PAT and PMT are processed even if they are filtered out.
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let pids = vec![Pid::new(0x127).unwrap()].into_iter().collect();
+        reader.set_pid_filter(PidFilter::Allow(pids));
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(packet.header.pid.as_u16(), 0x127);
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+        assert_eq!(reader.program_map().program(1).unwrap().es_list().len(), 40);
+    }
+
+    #[test]
+    fn multi_section_pat() {
+        let mut pat = payload::Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: (0..300)
+                .map(|i| ProgramAssociation {
+                    program_num: i + 1,
+                    program_map_pid: Pid::new(0x100 + i).unwrap(),
+                })
+                .collect(),
+        };
+        let pmt = payload::Pmt {
+            program_num: 300,
+            pcr_pid: None,
+            version_number: VersionNumber::new(),
+            program_descriptors: Vec::new(),
+            table: Vec::new(),
+        };
+
+        let mut packet = pat_packet();
+        packet.payload = Some(TsPayload::Pat(pat.clone()));
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        let packets = writer.stream().len() / TsPacket::SIZE;
+        assert!(packets > 1);
+
+        packet.header.pid = Pid::new(0x100 + 299).unwrap();
+        packet.payload = Some(TsPayload::Pmt(pmt.clone()));
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+
+        let mut reader = TsPacketReader::new(&writer.stream()[..]);
+        for i in 0..packets {
+            let p = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+            assert_eq!(p.header.continuity_counter.as_u8(), 1 + i as u8);
+            assert!(matches!(p.payload, Some(TsPayload::Raw(_))));
+        }
+        let p = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(p.payload, Some(TsPayload::Pmt(pmt)));
+        assert_eq!(track_try_unwrap!(reader.read_ts_packet()), None);
+
+        // A table fitting into a packet is written as a single section.
+        pat.table.truncate(1);
+        let mut writer = TsPacketWriter::new(Vec::new());
+        packet.payload = Some(TsPayload::Pat(pat));
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        assert_eq!(writer.stream().len(), TsPacket::SIZE);
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn async_read_write() {
+        use es::{StreamId, StreamType};
+        use pes::{AsyncReadPesPacket, PesHeader, PesPacketReader};
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let mut pmt_packet = pat_packet();
+        pmt_packet.header.pid = Pid::new(480).unwrap();
+        pmt_packet.payload = Some(TsPayload::Pmt(payload::Pmt {
+            program_num: 1,
+            pcr_pid: None,
+            version_number: VersionNumber::new(),
+            program_descriptors: Vec::new(),
+            table: vec![EsInfo {
+                stream_type: StreamType::AdtsAac,
+                elementary_pid: Pid::new(0x100).unwrap(),
+                descriptors: Vec::new(),
+            }],
+        }));
+        let mut pes_packet = pat_packet();
+        pes_packet.header.pid = Pid::new(0x100).unwrap();
+        pes_packet.header.adaptation_field_control =
+            AdaptationFieldControl::AdaptationFieldAndPayload;
+        pes_packet.payload = Some(TsPayload::Pes(payload::Pes {
+            header: PesHeader {
+                stream_id: StreamId::new(StreamId::AUDIO_MIN),
+                priority: false,
+                data_alignment_indicator: false,
+                copyright: false,
+                original_or_copy: false,
+                pts: None,
+                dts: None,
+                escr: None,
+            },
+            pes_packet_len: 3 + 32,
+            data: payload::Bytes::new(&[1; 32]).unwrap(),
+        }));
+        let packets = vec![pat_packet(), pmt_packet, pes_packet];
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        for packet in &packets {
+            track_try_unwrap!(runtime.block_on(writer.write_ts_packet_async(packet)));
+        }
+        track_try_unwrap!(runtime.block_on(writer.flush_async()));
+        let bytes = writer.into_stream();
+        assert_eq!(bytes.len(), TsPacket::SIZE * 3);
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        for packet in &packets {
+            let p = track_try_unwrap!(runtime.block_on(reader.read_ts_packet_async())).unwrap();
+            assert_eq!(p.header, packet.header);
+            assert_eq!(p.payload, packet.payload);
+        }
+        assert_eq!(
+            track_try_unwrap!(runtime.block_on(reader.read_ts_packet_async())),
+            None
+        );
+
+        let mut reader = PesPacketReader::new(TsPacketReader::new(&bytes[..]));
+        let p = track_try_unwrap!(runtime.block_on(reader.read_pes_packet_async())).unwrap();
+        assert_eq!(p.data, [1; 32]);
+        let p = track_try_unwrap!(runtime.block_on(reader.read_pes_packet_async()));
+        assert!(p.is_none());
+    }
+
     fn pat_packet_bytes() -> &'static [u8] {
         &[
             71, 64, 0, 17, 0, 0, 176, 13, 0, 0, 195, 0, 0, 0, 1, 225, 224, 232, 95, 116, 236, 255,
@@ -70,16 +508,589 @@ mod test {
         ][..]
     }
 
+    #[test]
+    fn seek() {
+        use pes::{PesPacketReader, ReadPesPacket};
+        use std::io::Cursor;
+        use time::Timestamp;
+
+        let pid = Pid::new(0x100).unwrap();
+        let bytes = seek_stream_bytes(pid, 5000, |i| Some(i * 3000));
+        let pts = |packet: &TsPacket| match packet.payload {
+            Some(TsPayload::Pes(ref pes)) => pes.header.pts.map(|t| t.as_u64()),
+            _ => None,
+        };
+
+        let mut reader = TsPacketReader::new(Cursor::new(&bytes[..]));
+        for _ in 0..2 {
+            track_try_unwrap!(reader.read_ts_packet());
+        }
+        let target = Timestamp::new(2345 * 3000 + 10).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, (2 + 2300) * TsPacket::SIZE as u64);
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(pts(&packet), Some(2300 * 3000));
+        assert!(packet.adaptation_field.unwrap().random_access_indicator);
+
+        let offset = track_try_unwrap!(reader.seek_to_offset(TsPacket::SIZE as u64 * 10 + 5));
+        assert_eq!(offset, TsPacket::SIZE as u64 * 10);
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(packet.position, position(offset, 10));
+        assert_eq!(pts(&packet), Some(8 * 3000));
+        assert!(reader.take_continuity_events().is_empty());
+
+        let target = Timestamp::new(0).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, TsPacket::SIZE as u64 * 2);
+
+        let mut reader = PesPacketReader::new(TsPacketReader::new(Cursor::new(&bytes[..])));
+        track_try_unwrap!(reader.read_pes_packet());
+        let target = Timestamp::new(4000 * 3000).unwrap();
+        track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        let packet = track_try_unwrap!(reader.read_pes_packet()).unwrap();
+        assert_eq!(packet.header.pts, Some(target));
+        assert_eq!(
+            packet.first_ts_packet_position.map(|p| p.offset),
+            Some((2 + 4000) * TsPacket::SIZE as u64)
+        );
+    }
+
+    #[test]
+    fn seek_across_wraparound() {
+        use std::io::Cursor;
+        use time::Timestamp;
+
+        let pid = Pid::new(0x100).unwrap();
+        let first = Timestamp::MAX - 3000 * 3000 + 1;
+        let bytes = seek_stream_bytes(pid, 5000, |i| {
+            Some((first + i * 3000) % (Timestamp::MAX + 1))
+        });
+        let mut reader = TsPacketReader::new(Cursor::new(&bytes[..]));
+
+        let target = Timestamp::new(1000 * 3000 + 10).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, (2 + 4000) * TsPacket::SIZE as u64);
+
+        let target = Timestamp::new(first + 2345 * 3000 + 10).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, (2 + 2300) * TsPacket::SIZE as u64);
+
+        // A timestamp slightly before the first one
+        let target = Timestamp::new(first - 10).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, 2 * TsPacket::SIZE as u64);
+    }
+
+    #[test]
+    fn seek_over_timestamp_gap() {
+        use std::io::Cursor;
+        use time::Timestamp;
+
+        // The gap is larger than a probe window
+        let pid = Pid::new(0x100).unwrap();
+        let bytes = seek_stream_bytes(pid, 5000, |i| {
+            if (2000..4500).contains(&i) {
+                None
+            } else {
+                Some(i * 3000)
+            }
+        });
+        let mut reader = TsPacketReader::new(Cursor::new(&bytes[..]));
+        let target = Timestamp::new(4800 * 3000).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, (2 + 4800) * TsPacket::SIZE as u64);
+    }
+
+    #[test]
+    fn seek_on_single_clock() {
+        use std::io::Cursor;
+        use time::Timestamp;
+
+        // The PTSs lead the PCRs by 10 seconds, and only every other packet has a PTS
+        let pid = Pid::new(0x100).unwrap();
+        let delay = 300 * 3000;
+        let bytes = seek_stream_bytes_with_pcr(
+            pid,
+            5000,
+            |i| if i % 2 == 0 { Some(i * 3000 + delay) } else { None },
+            |i| Some(i * 3000),
+        );
+        let mut reader = TsPacketReader::new(Cursor::new(&bytes[..]));
+
+        let target = Timestamp::new(4321 * 3000 + delay + 10).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, SeekClock::Pts));
+        assert_eq!(offset, (2 + 4300) * TsPacket::SIZE as u64);
+
+        let clock = SeekClock::Pcr(pid);
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, clock));
+        assert_eq!(offset, (2 + 4300) * TsPacket::SIZE as u64);
+
+        // The PCRs of the first 300 packets precede the first PTS
+        let target = Timestamp::new(250 * 3000 + delay).unwrap();
+        let offset = track_try_unwrap!(reader.seek_to_timestamp(pid, target, clock));
+        assert_eq!(offset, (2 + 200) * TsPacket::SIZE as u64);
+    }
+
+    #[test]
+    fn split_psi_packets() {
+        let mut pat = pat_packet();
+        pat.payload = Some(TsPayload::Pat(payload::Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: (0..100)
+                .map(|i| ProgramAssociation {
+                    program_num: i + 1,
+                    program_map_pid: Pid::new(0x100 + i).unwrap(),
+                })
+                .collect(),
+        }));
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat));
+        assert_eq!(
+            writer.last_continuity_counter(Pid::from(0)),
+            Some(ContinuityCounter::from_u8(3).unwrap())
+        );
+        let bytes = writer.into_stream();
+        assert_eq!(bytes.len(), TsPacket::SIZE * 3);
+
+        let packets = TsPacketRef::iter(&bytes)
+            .map(|p| track_try_unwrap!(p.and_then(|p| p.to_packet())))
+            .collect::<Vec<_>>();
+        for (i, packet) in packets.iter().enumerate() {
+            assert_eq!(packet.header.continuity_counter.as_u8(), i as u8 + 1);
+            assert_eq!(
+                packet.header.adaptation_field_control,
+                AdaptationFieldControl::PayloadOnly
+            );
+        }
+        // The last packet is filled with the section stuffing bytes
+        assert_eq!(bytes[bytes.len() - 1], 0xFF);
+    }
+
+    #[test]
+    fn table_events() {
+        let mut pat = pat_packet();
+        let table = (0..100)
+            .map(|i| ProgramAssociation {
+                program_num: i + 1,
+                program_map_pid: Pid::new(0x100 + i).unwrap(),
+            })
+            .collect::<Vec<_>>();
+        pat.payload = Some(TsPayload::Pat(payload::Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: table.clone(),
+        }));
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat));
+
+        let bytes = writer.into_stream();
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        reader.enable_table_events();
+        let mut events = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            assert!(matches!(packet.payload, Some(TsPayload::Raw(_))));
+            events.extend(reader.take_table_events());
+        }
+        assert_eq!(events.len(), 1);
+        match events[0] {
+            TableEvent::Pat(ref pat) => assert_eq!(pat.table, table),
+            ref e => panic!("Unexpected event: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn header_round_trip() {
+        // The multi-packet PAT is read as `Raw` payloads, and the PSI section stuffing is kept
+        let mut pat = pat_packet();
+        pat.payload = Some(TsPayload::Pat(payload::Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: (0..100)
+                .map(|i| ProgramAssociation {
+                    program_num: i + 1,
+                    program_map_pid: Pid::new(0x100 + i).unwrap(),
+                })
+                .collect(),
+        }));
+        let mut writer = TsPacketWriter::new(pat_packet_bytes().to_owned());
+        track_try_unwrap!(writer.write_ts_packet(&pat));
+        let bytes = writer.into_stream();
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut writer = TsPacketWriter::new(Vec::new());
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            track_try_unwrap!(writer.write_ts_packet(&packet));
+        }
+        assert_eq!(writer.into_stream(), bytes);
+
+        // Inconsistent headers
+        let mut writer = TsPacketWriter::new(Vec::new());
+        let mut packet = pat_packet();
+        packet.header.payload_unit_start_indicator = false;
+        assert!(writer.write_ts_packet(&packet).is_err());
+
+        let mut packet = pat_packet();
+        packet.payload = None;
+        assert!(writer.write_ts_packet(&packet).is_err());
+        packet.header.payload_unit_start_indicator = false;
+        packet.header.adaptation_field_control = AdaptationFieldControl::AdaptationFieldOnly;
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+
+        let mut packet = pat_packet();
+        packet.payload = Some(TsPayload::Raw(payload::Bytes::new(&[0; 100]).unwrap()));
+        assert!(writer.write_ts_packet(&packet).is_err());
+        packet.header.adaptation_field_control = AdaptationFieldControl::AdaptationFieldAndPayload;
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        packet.payload = Some(TsPayload::Raw(payload::Bytes::new(&[0; 184]).unwrap()));
+        assert!(writer.write_ts_packet(&packet).is_err());
+        assert_eq!(writer.into_stream().len(), TsPacket::SIZE * 2);
+    }
+
+    #[test]
+    fn cat() {
+        let ca = |ca_system_id: u16, pid: u16| CaDescriptor {
+            ca_system_id,
+            ca_pid: Pid::new(pid).unwrap(),
+            private_data: Vec::new(),
+        };
+        let cat = payload::Cat {
+            version_number: VersionNumber::new(),
+            descriptors: vec![ca(0x0B00, 0x120).to_descriptor().unwrap()],
+        };
+        let mut packets = vec![pat_packet(), pat_packet()];
+        packets[1].header.pid = Pid::from(Pid::CAT as u8);
+        packets[1].payload = Some(TsPayload::Cat(cat.clone()));
+
+        let mut bytes = seek_stream_bytes(Pid::new(0x100).unwrap(), 0, |i| Some(i * 3000));
+        let mut writer = TsPacketWriter::new(Vec::new());
+        for packet in &packets {
+            track_try_unwrap!(writer.write_ts_packet(packet));
+        }
+        bytes.extend(writer.into_stream());
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 4);
+        assert_eq!(packets[3].payload, Some(TsPayload::Cat(cat.clone())));
+        assert_eq!(reader.program_map().cat(), Some(&cat));
+        assert_eq!(
+            reader.program_map().ca_pids(),
+            [(Pid::new(0x120).unwrap(), PidKind::Emm { ca_system_id: 0x0B00 })]
+        );
+    }
+
+    #[test]
+    fn scte35() {
+        use es::StreamType;
+        use time::Timestamp;
+        use ts::scte35::{BreakDuration, SpliceCommand, SpliceInfoSection, SpliceInsert};
+
+        let pmt = |program_descriptors: Vec<Descriptor>| {
+            let mut packet = pat_packet();
+            packet.header.pid = Pid::new(480).unwrap();
+            packet.payload = Some(TsPayload::Pmt(payload::Pmt {
+                program_num: 1,
+                pcr_pid: None,
+                version_number: VersionNumber::new(),
+                program_descriptors,
+                table: vec![EsInfo {
+                    stream_type: StreamType::SCTE_35,
+                    elementary_pid: Pid::new(0x1F0).unwrap(),
+                    descriptors: Vec::new(),
+                }],
+            }));
+            packet
+        };
+        let duration = BreakDuration {
+            auto_return: true,
+            duration: 90_000 * 30,
+        };
+        let cue_out = SpliceInsert::cue_out(1, Some(Timestamp::from(90_000)), Some(duration));
+        let section = SpliceInfoSection::new(SpliceCommand::SpliceInsert(cue_out));
+        let mut packet = pat_packet();
+        packet.header.pid = Pid::new(0x1F0).unwrap();
+        packet.payload = Some(TsPayload::Scte35(section.clone()));
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat_packet()));
+        track_try_unwrap!(writer.write_ts_packet(&pmt(Vec::new())));
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        let bytes = writer.into_stream();
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[2].payload, Some(TsPayload::Scte35(section)));
+        assert_eq!(
+            reader.program_map().pid_kind(Pid::new(0x1F0).unwrap()),
+            Some(PidKind::Scte35)
+        );
+
+        // Encrypted sections are kept as raw bytes
+        let mut bytes = bytes;
+        let packet_bytes = &mut bytes[TsPacket::SIZE * 2..];
+        let start = packet_bytes.iter().position(|&b| b == 0xFC).unwrap();
+        let section_len = (packet_bytes[start + 2] as usize) + 3;
+        let section = &mut packet_bytes[start..][..section_len];
+        section[4] |= 0b1000_0000; // encrypted_packet
+        let mut crc32 = ::crc::Crc32::new();
+        crc32.update(&section[..section_len - 4]);
+        section[section_len - 4..].copy_from_slice(&crc32.value().to_be_bytes());
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 3);
+        assert!(matches!(packets[2].payload, Some(TsPayload::Raw(_))));
+        let packet_ref = track_try_unwrap!(TsPacketRef::new(&bytes[TsPacket::SIZE * 2..]));
+        assert_eq!(track_try_unwrap!(packet_ref.to_packet()), packets[2]);
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&packets[2]));
+        assert_eq!(writer.stream()[..], bytes[TsPacket::SIZE * 2..]);
+
+        // Blu-ray streams use the same stream type for DTS-HD Master Audio
+        let hdmv = Descriptor {
+            tag: 0x05,
+            data: b"HDMV".to_vec(),
+        };
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat_packet()));
+        track_try_unwrap!(writer.write_ts_packet(&pmt(vec![hdmv])));
+        let bytes = writer.into_stream();
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        while track_try_unwrap!(reader.read_ts_packet()).is_some() {}
+        assert_eq!(
+            reader.program_map().pid_kind(Pid::new(0x1F0).unwrap()),
+            Some(PidKind::Pes)
+        );
+    }
+
+    #[test]
+    fn nit() {
+        use ts::descriptor::{NetworkNameDescriptor, ServiceListDescriptor, ServiceListEntry};
+
+        let network_name = NetworkNameDescriptor {
+            network_name: DvbText::encode("Network"),
+        };
+        let service_list = ServiceListDescriptor {
+            services: vec![ServiceListEntry {
+                service_id: 1,
+                service_type: 1,
+            }],
+        };
+        let nit = |actual: bool| payload::Nit {
+            actual,
+            network_id: 0x3001,
+            version_number: VersionNumber::new(),
+            network_descriptors: vec![track_try_unwrap!(network_name.to_descriptor())],
+            transport_streams: vec![TransportStreamInfo {
+                transport_stream_id: 0,
+                original_network_id: 0x233A,
+                descriptors: vec![track_try_unwrap!(service_list.to_descriptor())],
+            }],
+        };
+
+        let mut pat = pat_packet();
+        if let Some(TsPayload::Pat(ref mut pat)) = pat.payload {
+            pat.table.insert(
+                0,
+                ProgramAssociation {
+                    program_num: 0,
+                    program_map_pid: Pid::new(0x20).unwrap(),
+                },
+            );
+        }
+        let mut actual = pat_packet();
+        actual.header.pid = Pid::new(0x20).unwrap();
+        actual.payload = Some(TsPayload::Nit(nit(true)));
+        let mut other = pat_packet();
+        other.header.pid = Pid::new(Pid::NIT).unwrap();
+        other.payload = Some(TsPayload::Nit(nit(false)));
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat));
+        track_try_unwrap!(writer.write_ts_packet(&actual));
+        track_try_unwrap!(writer.write_ts_packet(&other));
+        let bytes = writer.into_stream();
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[1].payload, Some(TsPayload::Nit(nit(true))));
+        assert_eq!(packets[2].payload, Some(TsPayload::Nit(nit(false))));
+
+        // The program number `0` is not a program
+        assert_eq!(
+            reader.take_program_events(),
+            [ProgramEvent::ProgramAdded {
+                program_num: 1,
+                pmt_pid: Pid::new(480).unwrap(),
+            }]
+        );
+        let map = reader.program_map();
+        assert_eq!(map.network_pid(), Some(Pid::new(0x20).unwrap()));
+        assert_eq!(map.pid_kind(Pid::new(0x20).unwrap()), Some(PidKind::Nit));
+        assert_eq!(map.programs().count(), 1);
+
+        let applied = map.nit().unwrap();
+        let name = applied.network_name().unwrap();
+        assert_eq!(track_try_unwrap!(name.decode()), "Network");
+        let ts = applied.transport_stream(0, 0x233A).unwrap();
+        assert_eq!(ts.services(), service_list.services);
+
+        let packet_ref = track_try_unwrap!(TsPacketRef::new(&bytes[TsPacket::SIZE * 2..]));
+        assert_eq!(
+            track_try_unwrap!(packet_ref.to_packet()).payload,
+            Some(TsPayload::Nit(nit(false)))
+        );
+    }
+
+    #[test]
+    fn unknown_pid() {
+        let mut data = [0xFF; 184];
+        data[..9].copy_from_slice(&[0, 0, 1, 0xE0, 0, 0, 0x80, 0, 0]);
+        let pes_packet = |pid: u16, counter: u8| {
+            let mut packet = pat_packet();
+            packet.header.pid = Pid::new(pid).unwrap();
+            packet.header.continuity_counter = ContinuityCounter::from_u8(counter).unwrap();
+            packet.header.payload_unit_start_indicator = counter == 0;
+            packet.payload = Some(TsPayload::Raw(payload::Bytes::new(&data).unwrap()));
+            packet
+        };
+
+        // The PES packet that precedes the PMT and a PID that is not listed in the PMT
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pes_packet(0x100, 0)));
+        let mut bytes = writer.into_stream();
+        bytes.extend(seek_stream_bytes(Pid::new(0x100).unwrap(), 1, |i| Some(i * 3000)));
+        let mut writer = TsPacketWriter::new(bytes);
+        track_try_unwrap!(writer.write_ts_packet(&pes_packet(0x200, 0)));
+        track_try_unwrap!(writer.write_ts_packet(&pes_packet(0x200, 1)));
+        let bytes = writer.into_stream();
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let mut packets = Vec::new();
+        while let Some(packet) = track_try_unwrap!(reader.read_ts_packet()) {
+            packets.push(packet);
+        }
+        assert_eq!(packets.len(), 6);
+        for &(i, pid, counter) in &[(0, 0x100, 0), (4, 0x200, 0), (5, 0x200, 1)] {
+            assert_eq!(packets[i], pes_packet(pid, counter));
+            let offset = i as u64 * TsPacket::SIZE as u64;
+            assert_eq!(packets[i].position, position(offset, i as u64));
+        }
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        for packet in &packets {
+            track_try_unwrap!(writer.write_ts_packet(packet));
+        }
+        assert_eq!(writer.into_stream(), bytes);
+    }
+
+    /// Makes a stream that has `count` PES packets with increasing PTSs on `pid`.
+    ///
+    /// Every 100th packet is a random access point.
+    fn seek_stream_bytes(pid: Pid, count: u64, pts: impl Fn(u64) -> Option<u64>) -> Vec<u8> {
+        seek_stream_bytes_with_pcr(pid, count, pts, |_| None)
+    }
+
+    /// Same as `seek_stream_bytes()`, but the packets also carry the PCR bases given by `pcr`.
+    fn seek_stream_bytes_with_pcr(
+        pid: Pid,
+        count: u64,
+        pts: impl Fn(u64) -> Option<u64>,
+        pcr: impl Fn(u64) -> Option<u64>,
+    ) -> Vec<u8> {
+        use es::{StreamId, StreamType};
+        use pes::PesHeader;
+        use time::{ClockReference, Timestamp};
+
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&pat_packet()));
+
+        let mut packet = pat_packet();
+        packet.header.pid = Pid::new(480).unwrap();
+        packet.payload = Some(TsPayload::Pmt(payload::Pmt {
+            program_num: 1,
+            pcr_pid: None,
+            version_number: VersionNumber::new(),
+            program_descriptors: Vec::new(),
+            table: vec![EsInfo {
+                stream_type: StreamType::H264,
+                elementary_pid: pid,
+                descriptors: Vec::new(),
+            }],
+        }));
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+
+        packet.header.pid = pid;
+        packet.header.adaptation_field_control = AdaptationFieldControl::AdaptationFieldAndPayload;
+        for i in 0..count {
+            let pcr = pcr(i).map(|t| ClockReference::new(t * 300).unwrap());
+            packet.adaptation_field = if i % 100 == 0 || pcr.is_some() {
+                Some(AdaptationField {
+                    discontinuity_indicator: false,
+                    random_access_indicator: i % 100 == 0,
+                    es_priority_indicator: false,
+                    pcr,
+                    opcr: None,
+                    splice_countdown: None,
+                    transport_private_data: Vec::new(),
+                    extension: None,
+                })
+            } else {
+                None
+            };
+            packet.payload = Some(TsPayload::Pes(payload::Pes {
+                header: PesHeader {
+                    stream_id: StreamId::new(StreamId::VIDEO_MIN),
+                    priority: false,
+                    data_alignment_indicator: false,
+                    copyright: false,
+                    original_or_copy: false,
+                    pts: pts(i).map(|t| Timestamp::new(t).unwrap()),
+                    dts: None,
+                    escr: None,
+                },
+                pes_packet_len: 0,
+                data: payload::Bytes::new(&[0; 100]).unwrap(),
+            }));
+            packet.header.continuity_counter.increment();
+            track_try_unwrap!(writer.write_ts_packet(&packet));
+        }
+        writer.into_stream()
+    }
+
+    fn position(offset: u64, index: u64) -> Option<PacketPosition> {
+        Some(PacketPosition { offset, index })
+    }
+
     fn pat_packet() -> TsPacket {
         TsPacket {
             header: TsHeader {
                 transport_error_indicator: false,
+                payload_unit_start_indicator: true,
                 transport_priority: false,
                 pid: Pid::from(0),
                 transport_scrambling_control: TransportScramblingControl::NotScrambled,
+                adaptation_field_control: AdaptationFieldControl::PayloadOnly,
                 continuity_counter: ContinuityCounter::from_u8(1).unwrap(),
             },
             adaptation_field: None,
+            tp_extra_header: None,
             payload: Some(TsPayload::Pat(payload::Pat {
                 transport_stream_id: 0,
                 version_number: VersionNumber::from_u8(1).unwrap(),
@@ -88,6 +1099,7 @@ mod test {
                     program_map_pid: Pid::new(480).unwrap(),
                 }],
             })),
+            position: None,
         }
     }
 
@@ -96,6 +1108,35 @@ mod test {
         let mut reader = TsPacketReader::new(pid17_packet_bytes());
         let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
         assert_eq!(packet.header.pid, Pid::from(17));
+
+        // The last byte of the packet is not a stuffing byte, so the payload is kept as is
+        assert!(matches!(packet.payload, Some(TsPayload::Raw(_))));
+        let sdt = reader.program_map().sdt().cloned().unwrap();
+        assert!(sdt.actual);
+        assert_eq!(sdt.transport_stream_id, 1);
+        assert_eq!(sdt.original_network_id, 1);
+        assert_eq!(sdt.services.len(), 1);
+        let service = &sdt.services[0];
+        assert_eq!(service.service_id, 1);
+        assert_eq!(service.running_status, RunningStatus::Running);
+        assert!(!service.free_ca_mode);
+        let d = service.service_descriptor().unwrap();
+        assert_eq!(d.service_type, 1);
+        assert_eq!(track_try_unwrap!(d.service_provider_name.decode()), "FFmpeg");
+        assert_eq!(track_try_unwrap!(d.service_name.decode()), "Big Buck Bunny");
+
+        let mut packet = pat_packet();
+        packet.header.pid = Pid::from(17);
+        packet.header.continuity_counter = ContinuityCounter::new();
+        packet.payload = Some(TsPayload::Sdt(sdt.clone()));
+        let mut writer = TsPacketWriter::new(Vec::new());
+        track_try_unwrap!(writer.write_ts_packet(&packet));
+        let bytes = writer.into_stream();
+        assert_eq!(bytes[..50], pid17_packet_bytes()[..50]);
+
+        let mut reader = TsPacketReader::new(&bytes[..]);
+        let packet = track_try_unwrap!(reader.read_ts_packet()).unwrap();
+        assert_eq!(packet.payload, Some(TsPayload::Sdt(sdt)));
     }
 
     fn pid17_packet_bytes() -> &'static [u8] {
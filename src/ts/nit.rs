@@ -0,0 +1,373 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+use ts::descriptor::{CableDeliverySystemDescriptor, NetworkNameDescriptor,
+                     SatelliteDeliverySystemDescriptor, ServiceListDescriptor, ServiceListEntry,
+                     TerrestrialDeliverySystemDescriptor};
+use ts::psi::{Psi, PsiTable, PsiTableHeader};
+use ts::{Descriptor, DvbText, VersionNumber};
+use {ErrorKind, Result};
+
+/// Payload for NIT(Network Information Table) packets.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Nit {
+    /// `true` if the table describes the actual network (`table_id` is `0x40`),
+    /// `false` if it describes another network (`table_id` is `0x41`).
+    pub actual: bool,
+
+    pub network_id: u16,
+    pub version_number: VersionNumber,
+    pub network_descriptors: Vec<Descriptor>,
+    pub transport_streams: Vec<TransportStreamInfo>,
+}
+impl Nit {
+    pub(super) const ACTUAL_TABLE_ID: u8 = 0x40;
+    pub(super) const OTHER_TABLE_ID: u8 = 0x41;
+
+    /// Returns the name of the network.
+    ///
+    /// If the table has no network name descriptor, it will return `None`.
+    pub fn network_name(&self) -> Option<DvbText> {
+        self.network_descriptors
+            .iter()
+            .filter(|d| d.tag == NetworkNameDescriptor::TAG)
+            .filter_map(|d| NetworkNameDescriptor::from_descriptor(d).ok())
+            .map(|d| d.network_name)
+            .next()
+    }
+
+    /// Returns the transport stream that has the given identifiers.
+    pub fn transport_stream(
+        &self,
+        transport_stream_id: u16,
+        original_network_id: u16,
+    ) -> Option<&TransportStreamInfo> {
+        self.transport_streams.iter().find(|ts| {
+            ts.transport_stream_id == transport_stream_id
+                && ts.original_network_id == original_network_id
+        })
+    }
+
+    pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let psi = track!(Psi::read_from(reader))?;
+        track!(Self::from_tables(psi.tables))
+    }
+
+    /// Makes a `Nit` instance from all the sections of a table.
+    ///
+    /// The network descriptors of the sections are concatenated.
+    pub(super) fn from_tables(tables: Vec<PsiTable>) -> Result<Self> {
+        track_assert!(!tables.is_empty(), ErrorKind::InvalidInput);
+        let last_section_number = tables.len() - 1;
+
+        let mut nit: Option<Nit> = None;
+        for (section_number, table) in tables.into_iter().enumerate() {
+            let header = table.header;
+            let actual = match header.table_id {
+                Self::ACTUAL_TABLE_ID => true,
+                Self::OTHER_TABLE_ID => false,
+                _ => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Unexpected table_id: {}",
+                    header.table_id
+                ),
+            };
+
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.section_number as usize,
+                section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.last_section_number as usize,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+            let nit = nit.get_or_insert_with(|| Nit {
+                actual,
+                network_id: syntax.table_id_extension,
+                version_number: syntax.version_number,
+                network_descriptors: Vec::new(),
+                transport_streams: Vec::new(),
+            });
+            track_assert_eq!(nit.actual, actual, ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.table_id_extension,
+                nit.network_id,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.version_number,
+                nit.version_number,
+                ErrorKind::InvalidInput
+            );
+
+            let mut reader = &syntax.table_data[..];
+            let n = track_io!(reader.read_u16::<BigEndian>())?;
+            let network_descriptors_len = n & 0b0000_1111_1111_1111;
+            let mut descriptors_reader = (&mut reader).take(u64::from(network_descriptors_len));
+            while descriptors_reader.limit() > 0 {
+                let d = track!(Descriptor::read_from(&mut descriptors_reader))?;
+                nit.network_descriptors.push(d);
+            }
+
+            let n = track_io!(reader.read_u16::<BigEndian>())?;
+            let transport_stream_loop_len = (n & 0b0000_1111_1111_1111) as usize;
+            track_assert_eq!(
+                reader.len(),
+                transport_stream_loop_len,
+                ErrorKind::InvalidInput
+            );
+            while !reader.is_empty() {
+                nit.transport_streams
+                    .push(track!(TransportStreamInfo::read_from(&mut reader))?);
+            }
+        }
+        Ok(nit.expect("Never fails"))
+    }
+
+    pub(super) fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        track!(self.to_psi().and_then(|psi| psi.write_to(writer)))
+    }
+
+    fn to_psi(&self) -> Result<Psi> {
+        let mut network_descriptors = Vec::new();
+        for d in &self.network_descriptors {
+            track!(d.write_to(&mut network_descriptors))?;
+        }
+        track_assert!(
+            network_descriptors.len() <= 0b0000_1111_1111_1111,
+            ErrorKind::InvalidInput
+        );
+
+        // The space for `transport_stream_loop_length` is reserved by `common_data`,
+        // and the actual value is filled after the entries are distributed over the sections.
+        let mut common_data = Vec::with_capacity(4 + network_descriptors.len());
+        let n = 0xF000 | network_descriptors.len() as u16;
+        track_io!(common_data.write_u16::<BigEndian>(n))?;
+        common_data.extend_from_slice(&network_descriptors);
+        track_io!(common_data.write_u16::<BigEndian>(0xF000))?;
+
+        let mut entries = Vec::with_capacity(self.transport_streams.len());
+        for ts in &self.transport_streams {
+            let mut entry = Vec::new();
+            track!(ts.write_to(&mut entry))?;
+            entries.push(entry);
+        }
+
+        let header = PsiTableHeader {
+            table_id: if self.actual {
+                Self::ACTUAL_TABLE_ID
+            } else {
+                Self::OTHER_TABLE_ID
+            },
+            private_bit: true, // reserved_future_use
+        };
+        let mut tables = track!(PsiTable::split(
+            &header,
+            self.network_id,
+            self.version_number,
+            &common_data,
+            &entries
+        ))?;
+        for (i, table) in tables.iter_mut().enumerate() {
+            let syntax = table.syntax.as_mut().expect("Never fails");
+            let transport_stream_loop = syntax.table_data.split_off(common_data.len());
+            if i > 0 {
+                // The network descriptors are written only in the first section
+                syntax.table_data = vec![0xF0, 0x00, 0xF0, 0x00];
+            }
+            let n = 0xF000 | transport_stream_loop.len() as u16;
+            let len = syntax.table_data.len();
+            syntax.table_data[len - 2] = (n >> 8) as u8;
+            syntax.table_data[len - 1] = n as u8;
+            syntax.table_data.extend_from_slice(&transport_stream_loop);
+        }
+        Ok(Psi { tables })
+    }
+}
+
+/// An entry of the transport stream loop of a NIT.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TransportStreamInfo {
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
+    pub descriptors: Vec<Descriptor>,
+}
+impl TransportStreamInfo {
+    /// Returns the services listed in the service list descriptors of the transport stream.
+    pub fn services(&self) -> Vec<ServiceListEntry> {
+        self.descriptors
+            .iter()
+            .filter(|d| d.tag == ServiceListDescriptor::TAG)
+            .filter_map(|d| ServiceListDescriptor::from_descriptor(d).ok())
+            .flat_map(|d| d.services)
+            .collect()
+    }
+
+    /// Returns the delivery system parameters of the transport stream.
+    ///
+    /// If the transport stream has no (or malformed) delivery system descriptor,
+    /// it will return `None`.
+    pub fn delivery_system(&self) -> Option<DeliverySystem> {
+        self.descriptors
+            .iter()
+            .filter_map(|d| match d.tag {
+                SatelliteDeliverySystemDescriptor::TAG => {
+                    SatelliteDeliverySystemDescriptor::from_descriptor(d)
+                        .ok()
+                        .map(DeliverySystem::Satellite)
+                }
+                CableDeliverySystemDescriptor::TAG => {
+                    CableDeliverySystemDescriptor::from_descriptor(d)
+                        .ok()
+                        .map(DeliverySystem::Cable)
+                }
+                TerrestrialDeliverySystemDescriptor::TAG => {
+                    TerrestrialDeliverySystemDescriptor::from_descriptor(d)
+                        .ok()
+                        .map(DeliverySystem::Terrestrial)
+                }
+                _ => None,
+            })
+            .next()
+    }
+
+    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let transport_stream_id = track_io!(reader.read_u16::<BigEndian>())?;
+        let original_network_id = track_io!(reader.read_u16::<BigEndian>())?;
+
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        let transport_descriptors_len = n & 0b0000_1111_1111_1111;
+        let mut reader = reader.take(u64::from(transport_descriptors_len));
+        let mut descriptors = Vec::new();
+        while reader.limit() > 0 {
+            let d = track!(Descriptor::read_from(&mut reader))?;
+            descriptors.push(d);
+        }
+
+        Ok(TransportStreamInfo {
+            transport_stream_id,
+            original_network_id,
+            descriptors,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u16::<BigEndian>(self.transport_stream_id))?;
+        track_io!(writer.write_u16::<BigEndian>(self.original_network_id))?;
+
+        let transport_descriptors_len: usize =
+            self.descriptors.iter().map(|d| 2 + d.data.len()).sum();
+        track_assert!(
+            transport_descriptors_len <= 0b0000_1111_1111_1111,
+            ErrorKind::InvalidInput
+        );
+        track_io!(writer.write_u16::<BigEndian>(0xF000 | transport_descriptors_len as u16))?;
+        for d in &self.descriptors {
+            track!(d.write_to(&mut writer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Delivery system parameters of a transport stream.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DeliverySystem {
+    Satellite(SatelliteDeliverySystemDescriptor),
+    Cable(CableDeliverySystemDescriptor),
+    Terrestrial(TerrestrialDeliverySystemDescriptor),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn terrestrial() -> TerrestrialDeliverySystemDescriptor {
+        TerrestrialDeliverySystemDescriptor {
+            centre_frequency: 49_800_000,
+            bandwidth: 0,
+            priority: true,
+            time_slicing_indicator: true,
+            mpe_fec_indicator: true,
+            constellation: 2,
+            hierarchy_information: 0,
+            code_rate_hp_stream: 2,
+            code_rate_lp_stream: 0,
+            guard_interval: 0,
+            transmission_mode: 1,
+            other_frequency_flag: false,
+        }
+    }
+
+    #[test]
+    fn nit() {
+        let network_name = NetworkNameDescriptor {
+            network_name: DvbText::encode("Network"),
+        };
+        let service_list = ServiceListDescriptor {
+            services: vec![ServiceListEntry {
+                service_id: 1,
+                service_type: 1,
+            }],
+        };
+        let nit = Nit {
+            actual: true,
+            network_id: 0x3001,
+            version_number: VersionNumber::from_u8(3).unwrap(),
+            network_descriptors: vec![network_name.to_descriptor().unwrap()],
+            transport_streams: vec![TransportStreamInfo {
+                transport_stream_id: 1,
+                original_network_id: 0x233A,
+                descriptors: vec![
+                    service_list.to_descriptor().unwrap(),
+                    terrestrial().to_descriptor().unwrap(),
+                ],
+            }],
+        };
+        let mut buf = Vec::new();
+        nit.write_to(&mut buf).unwrap();
+        let decoded = Nit::read_from(&buf[..]).unwrap();
+        assert_eq!(decoded, nit);
+
+        assert_eq!(decoded.network_name().unwrap().decode().unwrap(), "Network");
+        let ts = decoded.transport_stream(1, 0x233A).unwrap();
+        assert_eq!(ts.services(), service_list.services);
+        assert_eq!(
+            ts.delivery_system(),
+            Some(DeliverySystem::Terrestrial(terrestrial()))
+        );
+    }
+
+    #[test]
+    fn multi_section_nit() {
+        let network_name = NetworkNameDescriptor {
+            network_name: DvbText::encode("Network"),
+        };
+        let nit = Nit {
+            actual: false,
+            network_id: 0x3001,
+            version_number: VersionNumber::new(),
+            network_descriptors: vec![network_name.to_descriptor().unwrap()],
+            transport_streams: (0..100)
+                .map(|i| TransportStreamInfo {
+                    transport_stream_id: i,
+                    original_network_id: 0x233A,
+                    descriptors: vec![terrestrial().to_descriptor().unwrap()],
+                })
+                .collect(),
+        };
+        let psi = nit.to_psi().unwrap();
+        assert_eq!(psi.tables.len(), 2);
+
+        let mut buf = Vec::new();
+        nit.write_to(&mut buf).unwrap();
+        assert_eq!(Nit::read_from(&buf[..]).unwrap(), nit);
+    }
+}
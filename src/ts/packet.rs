@@ -1,18 +1,29 @@
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
-use std::io::{Cursor, Read, Write};
+use std::hash::{Hash, Hasher};
+use std::io::{Read, Write};
 
-use super::adaptation_field::AdaptationFieldControl;
-use ts::payload::{Bytes, Null, Pat, Pes, Pmt};
-use ts::{AdaptationField, ContinuityCounter, Pid, TransportScramblingControl};
+use ts::payload::{Bytes, Cat, Nit, Null, Pat, Pes, Pmt, Sdt, SpliceInfoSection};
+use ts::{AdaptationField, AdaptationFieldControl, ContinuityCounter, Pid, TpExtraHeader,
+         TransportScramblingControl};
 use {ErrorKind, Result};
 
 /// Transport stream packet.
 #[allow(missing_docs)]
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone)]
 pub struct TsPacket {
     pub header: TsHeader,
     pub adaptation_field: Option<AdaptationField>,
     pub payload: Option<TsPayload>,
+
+    /// The extra header preceding the packet in a M2TS stream.
+    ///
+    /// This is `None` for the packets read from a plain (188-byte packets) TS stream.
+    pub tp_extra_header: Option<TpExtraHeader>,
+
+    /// The position of the packet in the input stream.
+    ///
+    /// This is set by the readers, and is ignored by the writers and the comparison of packets.
+    pub position: Option<PacketPosition>,
 }
 impl TsPacket {
     /// Size of a packet in bytes.
@@ -23,15 +34,134 @@ impl TsPacket {
     /// Each packet starts with this byte.
     pub const SYNC_BYTE: u8 = 0x47;
 
-    pub(super) fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
-        let mut payload_buf = [0; TsPacket::SIZE - 4];
-        let payload_len = if let Some(ref payload) = self.payload {
-            let mut writer = Cursor::new(&mut payload_buf[..]);
-            track!(payload.write_to(&mut writer))?;
-            writer.position() as usize
+    /// Writes the packet.
+    ///
+    /// If the PSI (i.e., PAT, PMT, CAT, NIT or SDT) or SCTE-35 payload does not fit into a packet,
+    /// the payload is split into several packets.
+    /// In that case, the `payload_unit_start_indicator` and `adaptation_field_control` fields
+    /// of the packets are derived from the split payload.
+    /// If a PSI or SCTE-35 payload is shorter than a packet and `adaptation_field_control` has
+    /// no adaptation field, the rest of the packet is filled with the section stuffing bytes.
+    /// The last of the split packets is also filled with the section stuffing bytes.
+    ///
+    /// It returns the continuity counter of the last written packet.
+    ///
+    /// # Errors
+    ///
+    /// If the header is inconsistent with the adaptation field and payload,
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub(super) fn write_to<W: Write>(&self, mut writer: W) -> Result<ContinuityCounter> {
+        track!(self.check_payload_unit_start_indicator())?;
+
+        let mut payload_buf = Vec::with_capacity(TsPacket::SIZE - 4);
+        if let Some(ref payload) = self.payload {
+            track!(payload.write_to(&mut payload_buf))?;
+        }
+
+        let is_psi = matches!(
+            self.payload,
+            Some(TsPayload::Pat(_))
+                | Some(TsPayload::Pmt(_))
+                | Some(TsPayload::Cat(_))
+                | Some(TsPayload::Nit(_))
+                | Some(TsPayload::Sdt(_))
+                | Some(TsPayload::Scte35(_))
+        );
+        let first_len = (TsPacket::SIZE - 4).saturating_sub(
+            self.adaptation_field
+                .as_ref()
+                .map_or(0, |a| a.external_size()),
+        );
+        if is_psi && !self.header.adaptation_field_control.has_adaptation_field() {
+            // Fills the rest of the packet with the stuffing bytes of the section
+            if payload_buf.len() < first_len {
+                payload_buf.resize(first_len, 0xFF);
+            }
+        }
+        let payload = self.payload.as_ref().map(|_| &payload_buf[..]);
+        if !is_psi || payload_buf.len() <= first_len {
+            track!(self.write_with_payload(
+                writer,
+                payload,
+                self.header.payload_unit_start_indicator,
+                self.header.adaptation_field_control
+            ))?;
+            return Ok(self.header.continuity_counter);
+        }
+
+        // Fills the rest of the last packet with the stuffing bytes of the section
+        let max_len = TsPacket::SIZE - 4;
+        let last_len = (payload_buf.len() - first_len) % max_len;
+        if last_len != 0 {
+            let len = payload_buf.len() + max_len - last_len;
+            payload_buf.resize(len, 0xFF);
+        }
+
+        let (first, rest) = payload_buf.split_at(first_len);
+        let control = if self.adaptation_field.is_some() {
+            AdaptationFieldControl::AdaptationFieldAndPayload
         } else {
-            0
+            AdaptationFieldControl::PayloadOnly
+        };
+        track!(self.write_with_payload(&mut writer, Some(first), true, control))?;
+
+        let mut packet = TsPacket {
+            header: self.header.clone(),
+            adaptation_field: None,
+            payload: None,
+            tp_extra_header: None,
+            position: None,
         };
+        for chunk in rest.chunks(max_len) {
+            packet.header.continuity_counter.increment();
+            let control = AdaptationFieldControl::PayloadOnly;
+            track!(packet.write_with_payload(&mut writer, Some(chunk), false, control))?;
+        }
+        Ok(packet.header.continuity_counter)
+    }
+
+    fn check_payload_unit_start_indicator(&self) -> Result<()> {
+        let pusi = self.header.payload_unit_start_indicator;
+        match self.payload {
+            Some(TsPayload::Pat(_))
+            | Some(TsPayload::Pmt(_))
+            | Some(TsPayload::Cat(_))
+            | Some(TsPayload::Nit(_))
+            | Some(TsPayload::Sdt(_))
+            | Some(TsPayload::Scte35(_))
+            | Some(TsPayload::Pes(_)) => {
+                track_assert!(
+                    pusi,
+                    ErrorKind::InvalidInput,
+                    "payload_unit_start_indicator must be set for PSI and PES payloads"
+                );
+            }
+            Some(TsPayload::Null(_)) | None => {
+                track_assert!(
+                    !pusi,
+                    ErrorKind::InvalidInput,
+                    "payload_unit_start_indicator must not be set for packets without payload"
+                );
+            }
+            Some(TsPayload::Raw(_)) => {}
+        }
+        Ok(())
+    }
+
+    fn write_with_payload<W: Write>(
+        &self,
+        mut writer: W,
+        payload: Option<&[u8]>,
+        payload_unit_start_indicator: bool,
+        adaptation_field_control: AdaptationFieldControl,
+    ) -> Result<()> {
+        let payload_len = payload.map_or(0, |p| p.len());
+        track_assert!(
+            payload_len <= TsPacket::SIZE - 4,
+            ErrorKind::InvalidInput,
+            "Too large payload: {} bytes",
+            payload_len
+        );
 
         let required_len = self.adaptation_field
             .as_ref()
@@ -45,19 +175,28 @@ impl TsPacket {
             free_len,
         );
 
-        let adaptation_field_control = match (
-            self.adaptation_field.is_some() || free_len > 0,
-            self.payload.is_some(),
-        ) {
-            (true, true) => AdaptationFieldControl::AdaptationFieldAndPayload,
-            (true, false) => AdaptationFieldControl::AdaptationFieldOnly,
-            (false, true) => AdaptationFieldControl::PayloadOnly,
-            (false, false) => track_panic!(ErrorKind::InvalidInput, "Reserved for future use"),
-        };
-        let payload_unit_start_indicator = match self.payload {
-            Some(TsPayload::Raw(_)) | Some(TsPayload::Null(_)) | None => false,
-            _ => true,
-        };
+        track_assert_eq!(
+            adaptation_field_control.has_payload(),
+            payload.is_some(),
+            ErrorKind::InvalidInput,
+            "Inconsistent adaptation_field_control: {:?}",
+            adaptation_field_control
+        );
+        if adaptation_field_control.has_adaptation_field() {
+            track_assert!(
+                free_len > 0,
+                ErrorKind::InvalidInput,
+                "No space for adaptation field"
+            );
+        } else {
+            track_assert!(
+                self.adaptation_field.is_none() && free_len == 0,
+                ErrorKind::InvalidInput,
+                "Adaptation field is required: adaptation_field_control={:?}, stuffing={} bytes",
+                adaptation_field_control,
+                free_len
+            );
+        }
         track!(self.header.write_to(
             &mut writer,
             adaptation_field_control,
@@ -67,32 +206,84 @@ impl TsPacket {
         if let Some(ref adaptation_field) = self.adaptation_field {
             let adaptation_field_len = (free_len - 1) as u8;
             track!(adaptation_field.write_to(&mut writer, adaptation_field_len))?;
-        } else if free_len > 0 {
+        } else if adaptation_field_control.has_adaptation_field() {
             let adaptation_field_len = (free_len - 1) as u8;
             track!(AdaptationField::write_stuffing_bytes(
                 &mut writer,
                 adaptation_field_len
             ))?;
         }
-        track_io!(writer.write_all(&payload_buf[..payload_len]))?;
+        if let Some(payload) = payload {
+            track_io!(writer.write_all(payload))?;
+        }
         Ok(())
     }
 }
+impl PartialEq for TsPacket {
+    fn eq(&self, other: &Self) -> bool {
+        self.header == other.header
+            && self.adaptation_field == other.adaptation_field
+            && self.payload == other.payload
+            && self.tp_extra_header == other.tp_extra_header
+    }
+}
+impl Eq for TsPacket {}
+impl Hash for TsPacket {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.header.hash(hasher);
+        self.adaptation_field.hash(hasher);
+        self.payload.hash(hasher);
+        self.tp_extra_header.hash(hasher);
+    }
+}
+
+/// Position of a TS packet in the input stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PacketPosition {
+    /// The absolute byte offset of the packet.
+    ///
+    /// In a M2TS stream, this is the offset of the extra header preceding the packet.
+    pub offset: u64,
+
+    /// The zero-based index of the packet in the stream.
+    ///
+    /// The packets skipped by the PID filter or dropped due to errors are also counted.
+    pub index: u64,
+}
 
 /// TS packet header.
+///
+/// More fields may be added in the future, so use `TsHeader::new()` to construct it
+/// outside this crate.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
 pub struct TsHeader {
     pub transport_error_indicator: bool,
+    pub payload_unit_start_indicator: bool,
     pub transport_priority: bool,
     pub pid: Pid,
     pub transport_scrambling_control: TransportScramblingControl,
+    pub adaptation_field_control: AdaptationFieldControl,
     pub continuity_counter: ContinuityCounter,
 }
 impl TsHeader {
-    pub(super) fn read_from<R: Read>(
-        mut reader: R,
-    ) -> Result<(Self, AdaptationFieldControl, bool)> {
+    /// Makes a new `TsHeader` instance of a packet that has only a payload.
+    ///
+    /// The flags are cleared and the packet is not scrambled.
+    pub fn new(pid: Pid, continuity_counter: ContinuityCounter) -> Self {
+        TsHeader {
+            transport_error_indicator: false,
+            payload_unit_start_indicator: false,
+            transport_priority: false,
+            pid,
+            transport_scrambling_control: TransportScramblingControl::NotScrambled,
+            adaptation_field_control: AdaptationFieldControl::PayloadOnly,
+            continuity_counter,
+        }
+    }
+
+    pub(super) fn read_from<R: Read>(mut reader: R) -> Result<Self> {
         let sync_byte = track_io!(reader.read_u8())?;
         if sync_byte != TsPacket::SYNC_BYTE {
             log::trace!("Packet header does not start with sync byte. Got 0x{:X}, expecting 0x{:X}", sync_byte, TsPacket::SYNC_BYTE);
@@ -112,18 +303,15 @@ impl TsHeader {
         let adaptation_field_control = track!(AdaptationFieldControl::from_u8((n >> 4) & 0b11))?;
         let continuity_counter = track!(ContinuityCounter::from_u8(n & 0b1111))?;
 
-        let header = TsHeader {
+        Ok(TsHeader {
             transport_error_indicator,
+            payload_unit_start_indicator,
             transport_priority,
             pid,
             transport_scrambling_control,
-            continuity_counter,
-        };
-        Ok((
-            header,
             adaptation_field_control,
-            payload_unit_start_indicator,
-        ))
+            continuity_counter,
+        })
     }
 
     fn write_to<W: Write>(
@@ -149,14 +337,34 @@ impl TsHeader {
 }
 
 /// TS packet payload.
-#[cfg_attr(feature = "cargo-clippy", allow(large_enum_variant))]
+///
+/// The PSI payloads (e.g., `Pat` and `Pmt`) are only used for packets that contain
+/// a whole table by themselves.
+/// When `TsPacketReader` reads a table that spans several packets (or sections),
+/// every packet of the table is returned as `Raw`.
+/// The completed table is applied to `TsPacketReader::program_map()`,
+/// and its changes are reported by `TsPacketReader::take_program_events()`.
+/// The completed table itself can be taken by `TsPacketReader::take_table_events()`.
+#[allow(clippy::large_enum_variant)]
 #[allow(missing_docs)]
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum TsPayload {
     Pat(Pat),
     Pmt(Pmt),
+    Cat(Cat),
+    Nit(Nit),
+    Sdt(Sdt),
     Pes(Pes),
+
+    /// SCTE-35 splice information of a PID whose `PidKind` is `PidKind::Scte35`.
+    Scte35(SpliceInfoSection),
+
     Null(Null),
+
+    /// Payload that is not decoded by the reader.
+    ///
+    /// For example, the continuation packets of PES packets and multi-packet tables,
+    /// and the packets of PIDs that are not listed in any PMT (yet).
     Raw(Bytes),
 }
 impl TsPayload {
@@ -164,7 +372,11 @@ impl TsPayload {
         match *self {
             TsPayload::Pat(ref x) => track!(x.write_to(writer)),
             TsPayload::Pmt(ref x) => track!(x.write_to(writer)),
+            TsPayload::Cat(ref x) => track!(x.write_to(writer)),
+            TsPayload::Nit(ref x) => track!(x.write_to(writer)),
+            TsPayload::Sdt(ref x) => track!(x.write_to(writer)),
             TsPayload::Pes(ref x) => track!(x.write_to(writer)),
+            TsPayload::Scte35(ref x) => track!(x.write_to(writer)),
             TsPayload::Null(_) => Ok(()),
             TsPayload::Raw(ref x) => track!(x.write_to(writer)),
         }
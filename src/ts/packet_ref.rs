@@ -0,0 +1,237 @@
+use byteorder::{BigEndian, ByteOrder};
+
+use super::adaptation_field::AdaptationFieldControl;
+use ts::payload::{Bytes, Cat, Nit, Null, Pat, Pes, Pmt, Sdt, SpliceInfoSection};
+use ts::section;
+use ts::{AdaptationField, ContinuityCounter, Pid, TsHeader, TsPacket, TsPayload};
+use {ErrorKind, Result};
+
+/// Borrowed view of a TS packet.
+///
+/// Unlike `TsPacket`, it does not copy the packet data.
+/// The fields are decoded lazily when the corresponding accessors are called.
+///
+/// # Examples
+///
+/// ```
+/// use mpeg2ts::ts::{TsPacket, TsPacketRef};
+///
+/// let mut bytes = [0xFF; TsPacket::SIZE * 2];
+/// for chunk in bytes.chunks_mut(TsPacket::SIZE) {
+///     chunk[..4].copy_from_slice(&[0x47, 0x1F, 0xFF, 0x10]); // null packet
+/// }
+///
+/// for packet in TsPacketRef::iter(&bytes) {
+///     let packet = packet.unwrap();
+///     assert_eq!(packet.pid().as_u16(), 0x1FFF);
+///     assert_eq!(packet.payload().unwrap().map(|p| p.len()), Some(184));
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TsPacketRef<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> TsPacketRef<'a> {
+    /// Makes a new `TsPacketRef` instance.
+    ///
+    /// # Errors
+    ///
+    /// If the length of `bytes` is not `TsPacket::SIZE` or
+    /// the first byte is not `TsPacket::SYNC_BYTE`,
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub fn new(bytes: &'a [u8]) -> Result<Self> {
+        track_assert_eq!(bytes.len(), TsPacket::SIZE, ErrorKind::InvalidInput);
+        track_assert_eq!(bytes[0], TsPacket::SYNC_BYTE, ErrorKind::InvalidInput);
+        Ok(TsPacketRef { bytes })
+    }
+
+    /// Returns an iterator over the packets in the given byte sequence.
+    ///
+    /// `bytes` must start at a packet boundary.
+    pub fn iter(bytes: &'a [u8]) -> TsPacketRefIter<'a> {
+        TsPacketRefIter { bytes }
+    }
+
+    /// Returns the bytes of the packet.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Returns the packet identifier.
+    pub fn pid(&self) -> Pid {
+        Pid::new(BigEndian::read_u16(&self.bytes[1..]) & Pid::MAX).expect("Never fails")
+    }
+
+    /// Returns the value of the `transport_error_indicator` field.
+    pub fn transport_error_indicator(&self) -> bool {
+        (self.bytes[1] & 0b1000_0000) != 0
+    }
+
+    /// Returns the value of the `payload_unit_start_indicator` field.
+    pub fn payload_unit_start_indicator(&self) -> bool {
+        (self.bytes[1] & 0b0100_0000) != 0
+    }
+
+    /// Returns the continuity counter.
+    pub fn continuity_counter(&self) -> ContinuityCounter {
+        ContinuityCounter::from_u8(self.bytes[3] & 0b1111).expect("Never fails")
+    }
+
+    /// Decodes the header of the packet.
+    pub fn header(&self) -> Result<TsHeader> {
+        track!(TsHeader::read_from(self.bytes))
+    }
+
+    /// Returns the bytes of the adaptation field (excluding the `adaptation_field_length` byte).
+    pub fn adaptation_field_bytes(&self) -> Result<Option<&'a [u8]>> {
+        let control = track!(self.adaptation_field_control())?;
+        if !control.has_adaptation_field() {
+            return Ok(None);
+        }
+        let len = self.bytes[4] as usize;
+        let max_len = if control.has_payload() {
+            TsPacket::SIZE - 6
+        } else {
+            TsPacket::SIZE - 5
+        };
+        track_assert!(
+            len <= max_len,
+            ErrorKind::InvalidInput,
+            "Too large adaptation field: {} bytes",
+            len
+        );
+        Ok(Some(&self.bytes[5..5 + len]))
+    }
+
+    /// Decodes the adaptation field of the packet.
+    pub fn adaptation_field(&self) -> Result<Option<AdaptationField>> {
+        if track!(self.adaptation_field_bytes())?.is_none() {
+            return Ok(None);
+        }
+        track!(AdaptationField::read_from(&self.bytes[4..]))
+    }
+
+    /// Returns the payload bytes of the packet.
+    pub fn payload(&self) -> Result<Option<&'a [u8]>> {
+        let control = track!(self.adaptation_field_control())?;
+        if !control.has_payload() {
+            return Ok(None);
+        }
+        let offset = match track!(self.adaptation_field_bytes())? {
+            None => 4,
+            Some(x) => 5 + x.len(),
+        };
+        Ok(Some(&self.bytes[offset..]))
+    }
+
+    /// Converts the packet into an owned `TsPacket`.
+    ///
+    /// Because the packet is decoded without any information about the other packets,
+    /// the payload is decoded as follows:
+    ///
+    /// - PAT: if the PID is `Pid::PAT` and the packet contains a whole section
+    /// - CAT: if the PID is `Pid::CAT` and the packet contains a whole section
+    /// - NIT: if the PID is `Pid::NIT` and the packet contains a whole section of NIT
+    /// - SDT: if the PID is `Pid::SDT` and the packet contains a whole section of SDT
+    /// - Null: if the PID is `Pid::NULL`
+    /// - PMT: if the packet contains a whole section with the table identifier of PMT
+    /// - SCTE-35: if the packet contains a whole `splice_info_section` that is not encrypted
+    /// - PES: if the packet starts with a PES start code
+    /// - Raw: otherwise
+    ///
+    /// Use `TsPacketReader` to decode payloads by tracking the PAT and PMT of the stream.
+    pub fn to_packet(&self) -> Result<TsPacket> {
+        let header = track!(self.header())?;
+        let adaptation_field = track!(self.adaptation_field())?;
+        let payload = if let Some(mut reader) = track!(self.payload())? {
+            let pusi = self.payload_unit_start_indicator();
+            let payload = match header.pid.as_u16() {
+                Pid::PAT if pusi && section::is_single_section_table(reader) => {
+                    TsPayload::Pat(track!(Pat::read_from(&mut reader))?)
+                }
+                Pid::CAT if pusi && section::is_single_section_table(reader) => {
+                    TsPayload::Cat(track!(Cat::read_from(&mut reader))?)
+                }
+                Pid::NIT if pusi && Self::is_nit_section(reader) => {
+                    TsPayload::Nit(track!(Nit::read_from(&mut reader))?)
+                }
+                Pid::SDT if pusi && Self::is_sdt_section(reader) => {
+                    TsPayload::Sdt(track!(Sdt::read_from(&mut reader))?)
+                }
+                Pid::NULL => TsPayload::Null(track!(Null::read_from(&mut reader))?),
+                _ if pusi && reader.starts_with(&[0, 0, 1]) => {
+                    TsPayload::Pes(track!(Pes::read_from(&mut reader))?)
+                }
+                _ if pusi && Self::is_pmt_section(reader) => {
+                    TsPayload::Pmt(track!(Pmt::read_from(&mut reader))?)
+                }
+                _ if pusi && Self::is_splice_info_section(reader) => {
+                    if SpliceInfoSection::is_encrypted_section(&reader[1..]) {
+                        log::debug!(
+                            "Kept an encrypted splice_info_section as raw bytes: pid={}",
+                            header.pid.as_u16()
+                        );
+                        TsPayload::Raw(track!(Bytes::read_from(&mut reader))?)
+                    } else {
+                        TsPayload::Scte35(track!(SpliceInfoSection::read_from(&mut reader))?)
+                    }
+                }
+                _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
+            };
+            track_assert!(reader.is_empty(), ErrorKind::InvalidInput);
+            Some(payload)
+        } else {
+            None
+        };
+        Ok(TsPacket {
+            header,
+            adaptation_field,
+            payload,
+            tp_extra_header: None,
+            position: None,
+        })
+    }
+
+    pub(super) fn adaptation_field_control(&self) -> Result<AdaptationFieldControl> {
+        track!(AdaptationFieldControl::from_u8((self.bytes[3] >> 4) & 0b11))
+    }
+
+    fn is_pmt_section(payload: &[u8]) -> bool {
+        section::is_single_section_table(payload) && payload[1] == Pmt::TABLE_ID
+    }
+
+    fn is_nit_section(payload: &[u8]) -> bool {
+        section::is_single_section_table(payload)
+            && (payload[1] == Nit::ACTUAL_TABLE_ID || payload[1] == Nit::OTHER_TABLE_ID)
+    }
+
+    fn is_sdt_section(payload: &[u8]) -> bool {
+        section::is_single_section_table(payload)
+            && (payload[1] == Sdt::ACTUAL_TABLE_ID || payload[1] == Sdt::OTHER_TABLE_ID)
+    }
+
+    fn is_splice_info_section(payload: &[u8]) -> bool {
+        section::is_single_section_table(payload) && payload[1] == SpliceInfoSection::TABLE_ID
+    }
+}
+
+/// An iterator over the packets in a byte sequence.
+///
+/// This is created by `TsPacketRef::iter()`.
+#[derive(Debug)]
+pub struct TsPacketRefIter<'a> {
+    bytes: &'a [u8],
+}
+impl<'a> Iterator for TsPacketRefIter<'a> {
+    type Item = Result<TsPacketRef<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bytes.is_empty() {
+            return None;
+        }
+        let size = ::std::cmp::min(TsPacket::SIZE, self.bytes.len());
+        let (packet, rest) = self.bytes.split_at(size);
+        self.bytes = rest;
+        Some(track!(TsPacketRef::new(packet)))
+    }
+}
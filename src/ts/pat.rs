@@ -1,7 +1,7 @@
 use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
-use ts::psi::{Psi, PsiTable, PsiTableHeader, PsiTableSyntax};
+use ts::psi::{Psi, PsiTable, PsiTableHeader};
 use ts::{Pid, VersionNumber};
 use {ErrorKind, Result};
 
@@ -14,32 +14,69 @@ pub struct Pat {
     pub table: Vec<ProgramAssociation>,
 }
 impl Pat {
-    const TABLE_ID: u8 = 0;
+    pub(super) const TABLE_ID: u8 = 0;
+
+    /// Returns the PID of the NIT specified by the entry of the program number `0`.
+    ///
+    /// If the table has no such entry, it will return `None`.
+    pub fn network_pid(&self) -> Option<Pid> {
+        self.table
+            .iter()
+            .find(|pa| pa.program_num == 0)
+            .map(|pa| pa.program_map_pid)
+    }
 
     pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
-        let mut psi = track!(Psi::read_from(reader))?;
-        track_assert_eq!(psi.tables.len(), 1, ErrorKind::InvalidInput);
-
-        let table = psi.tables.pop().expect("Never fails");
-        let header = table.header;
-        track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
-        track_assert!(!header.private_bit, ErrorKind::InvalidInput);
-
-        let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.section_number, 0, ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.last_section_number, 0, ErrorKind::InvalidInput);
-        track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
-
-        let mut reader = &syntax.table_data[..];
-        let mut table = Vec::new();
-        while !reader.is_empty() {
-            table.push(track!(ProgramAssociation::read_from(&mut reader))?);
+        let psi = track!(Psi::read_from(reader))?;
+        track!(Self::from_tables(psi.tables))
+    }
+
+    /// Makes a `Pat` instance from all the sections of a table.
+    pub(super) fn from_tables(tables: Vec<PsiTable>) -> Result<Self> {
+        track_assert!(!tables.is_empty(), ErrorKind::InvalidInput);
+        let last_section_number = tables.len() - 1;
+
+        let mut pat: Option<Pat> = None;
+        for (section_number, table) in tables.into_iter().enumerate() {
+            let header = table.header;
+            track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+            track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.section_number as usize,
+                section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.last_section_number as usize,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+            let pat = pat.get_or_insert_with(|| Pat {
+                transport_stream_id: syntax.table_id_extension,
+                version_number: syntax.version_number,
+                table: Vec::new(),
+            });
+            track_assert_eq!(
+                syntax.table_id_extension,
+                pat.transport_stream_id,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.version_number,
+                pat.version_number,
+                ErrorKind::InvalidInput
+            );
+
+            let mut reader = &syntax.table_data[..];
+            while !reader.is_empty() {
+                pat.table.push(track!(ProgramAssociation::read_from(&mut reader))?);
+            }
         }
-        Ok(Pat {
-            transport_stream_id: syntax.table_id_extension,
-            version_number: syntax.version_number,
-            table,
-        })
+        Ok(pat.expect("Never fails"))
     }
 
     pub(super) fn write_to<W: Write>(&self, writer: W) -> Result<()> {
@@ -47,24 +84,24 @@ impl Pat {
     }
 
     fn to_psi(&self) -> Result<Psi> {
-        let mut table_data = Vec::new();
+        let mut entries = Vec::with_capacity(self.table.len());
         for pa in &self.table {
-            track!(pa.write_to(&mut table_data))?;
+            let mut entry = Vec::new();
+            track!(pa.write_to(&mut entry))?;
+            entries.push(entry);
         }
 
         let header = PsiTableHeader {
             table_id: Self::TABLE_ID,
             private_bit: false,
         };
-        let syntax = Some(PsiTableSyntax {
-            table_id_extension: self.transport_stream_id,
-            version_number: self.version_number,
-            current_next_indicator: true,
-            section_number: 0,
-            last_section_number: 0,
-            table_data,
-        });
-        let tables = vec![PsiTable { header, syntax }];
+        let tables = track!(PsiTable::split(
+            &header,
+            self.transport_stream_id,
+            self.version_number,
+            &[],
+            &entries
+        ))?;
         Ok(Psi { tables })
     }
 }
@@ -76,6 +113,8 @@ pub struct ProgramAssociation {
     pub program_num: u16,
 
     /// The packet identifier that contains the associated PMT.
+    ///
+    /// If `program_num` is `0`, it is the network PID that carries the NIT instead.
     pub program_map_pid: Pid,
 }
 impl ProgramAssociation {
@@ -94,3 +133,28 @@ impl ProgramAssociation {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn multi_section_pat() {
+        let pat = Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::new(),
+            table: (0..300)
+                .map(|i| ProgramAssociation {
+                    program_num: i + 1,
+                    program_map_pid: Pid::new(0x100 + i).unwrap(),
+                })
+                .collect(),
+        };
+        let psi = pat.to_psi().unwrap();
+        assert_eq!(psi.tables.len(), 2);
+
+        let mut buf = Vec::new();
+        pat.write_to(&mut buf).unwrap();
+        assert_eq!(Pat::read_from(&buf[..]).unwrap(), pat);
+    }
+}
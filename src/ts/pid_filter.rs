@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use ts::Pid;
+
+/// Filter that selects the TS packets returned by `TsPacketReader`.
+///
+/// The packets on the PIDs that are not selected are skipped right after the packet header
+/// is read, so their adaptation fields and payloads are not decoded.
+/// Note that the PAT and PMT packets are always decoded internally
+/// to keep the program map up to date (even if they are not selected).
+#[derive(Default)]
+pub enum PidFilter {
+    /// Selects all the PIDs.
+    #[default]
+    All,
+
+    /// Selects only the given PIDs.
+    Allow(HashSet<Pid>),
+
+    /// Selects the PIDs other than the given ones.
+    Deny(HashSet<Pid>),
+
+    /// Selects the PIDs for which the predicate returns `true`.
+    Predicate(Box<dyn Fn(Pid) -> bool + Send>),
+}
+impl PidFilter {
+    /// Returns `true` if the given PID is selected by the filter.
+    pub fn is_selected(&self, pid: Pid) -> bool {
+        match *self {
+            PidFilter::All => true,
+            PidFilter::Allow(ref pids) => pids.contains(&pid),
+            PidFilter::Deny(ref pids) => !pids.contains(&pid),
+            PidFilter::Predicate(ref f) => f(pid),
+        }
+    }
+}
+impl fmt::Debug for PidFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            PidFilter::All => write!(f, "All"),
+            PidFilter::Allow(ref pids) => write!(f, "Allow({:?})", pids),
+            PidFilter::Deny(ref pids) => write!(f, "Deny({:?})", pids),
+            PidFilter::Predicate(_) => write!(f, "Predicate(_)"),
+        }
+    }
+}
@@ -2,8 +2,9 @@ use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
 use std::io::{Read, Write};
 
 use es::StreamType;
-use ts::psi::{Psi, PsiTable, PsiTableHeader, PsiTableSyntax};
-use ts::{Pid, VersionNumber};
+use ts::descriptor::{self, TypedDescriptor};
+use ts::psi::{Psi, PsiTable, PsiTableHeader};
+use ts::{CaDescriptor, Pid, VersionNumber};
 use {ErrorKind, Result};
 
 /// Program Map Table.
@@ -19,71 +20,119 @@ pub struct Pmt {
     pub pcr_pid: Option<Pid>,
 
     pub version_number: VersionNumber,
+
+    /// The descriptors in the program info loop.
+    pub program_descriptors: Vec<Descriptor>,
+
     pub table: Vec<EsInfo>,
 }
 impl Pmt {
-    const TABLE_ID: u8 = 2;
+    pub(super) const TABLE_ID: u8 = 2;
+
+    /// Returns the CA descriptors in the program info loop.
+    ///
+    /// Their `ca_pid`s are the PIDs of the ECMs that apply to all the streams of the program.
+    /// The malformed descriptors are ignored.
+    pub fn ca_descriptors(&self) -> Vec<CaDescriptor> {
+        descriptor::decode_ca_descriptors(&self.program_descriptors)
+    }
 
     pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
-        let mut psi = track!(Psi::read_from(reader))?;
-        track_assert_eq!(psi.tables.len(), 1, ErrorKind::InvalidInput);
+        let psi = track!(Psi::read_from(reader))?;
+        track!(Self::from_tables(psi.tables))
+    }
 
-        let table = psi.tables.pop().expect("Never fails");
-        let header = table.header;
-        track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
-        track_assert!(!header.private_bit, ErrorKind::InvalidInput);
+    /// Makes a `Pmt` instance from all the sections of a table.
+    pub(super) fn from_tables(tables: Vec<PsiTable>) -> Result<Self> {
+        track_assert!(!tables.is_empty(), ErrorKind::InvalidInput);
+        let last_section_number = tables.len() - 1;
 
-        let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.section_number, 0, ErrorKind::InvalidInput);
-        track_assert_eq!(syntax.last_section_number, 0, ErrorKind::InvalidInput);
-        track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+        let mut pmt: Option<Pmt> = None;
+        for (section_number, table) in tables.into_iter().enumerate() {
+            let header = table.header;
+            track_assert_eq!(header.table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+            track_assert!(!header.private_bit, ErrorKind::InvalidInput);
 
-        let mut reader = &syntax.table_data[..];
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.section_number as usize,
+                section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.last_section_number as usize,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
 
-        let pcr_pid = track!(Pid::read_from(&mut reader))?;
-        let pcr_pid = if pcr_pid.as_u16() == 0b0001_1111_1111_1111 {
-            None
-        } else {
-            Some(pcr_pid)
-        };
+            let mut reader = &syntax.table_data[..];
 
-        let n = track_io!(reader.read_u16::<BigEndian>())?;
-        track_assert_eq!(
-            n & 0b1111_0000_0000_0000,
-            0b1111_0000_0000_0000,
-            ErrorKind::InvalidInput,
-            "Unexpected reserved bits"
-        );
-        track_assert_eq!(
-            n & 0b0000_1100_0000_0000,
-            0,
-            ErrorKind::InvalidInput,
-            "Unexpected program info length unused bits"
-        );
-        let program_info_len = n & 0b0000_0011_1111_1111;
+            let pcr_pid = track!(Pid::read_from(&mut reader))?;
+            let pcr_pid = if pcr_pid.as_u16() == 0b0001_1111_1111_1111 {
+                None
+            } else {
+                Some(pcr_pid)
+            };
 
-        if  program_info_len > 0 {
-            for _ in 0 .. program_info_len {
-                let _ = reader.read_u8();
+            let n = track_io!(reader.read_u16::<BigEndian>())?;
+            track_assert_eq!(
+                n & 0b1111_0000_0000_0000,
+                0b1111_0000_0000_0000,
+                ErrorKind::InvalidInput,
+                "Unexpected reserved bits"
+            );
+            track_assert_eq!(
+                n & 0b0000_1100_0000_0000,
+                0,
+                ErrorKind::InvalidInput,
+                "Unexpected program info length unused bits"
+            );
+            let program_info_len = n & 0b0000_0011_1111_1111;
+            track_assert!(
+                program_info_len as usize <= reader.len(),
+                ErrorKind::InvalidInput
+            );
+            let (mut program_info, rest) = reader.split_at(program_info_len as usize);
+            reader = rest;
+            let mut program_descriptors = Vec::new();
+            while !program_info.is_empty() {
+                program_descriptors.push(track!(Descriptor::read_from(&mut program_info))?);
             }
-        }
 
-        let mut table = Vec::new();
-        while !reader.is_empty() {
-            table.push(track!(EsInfo::read_from(&mut reader))?);
+            // The program info loop is repeated in every section, so the first one is used
+            let pmt = pmt.get_or_insert_with(|| Pmt {
+                program_num: syntax.table_id_extension,
+                pcr_pid,
+                version_number: syntax.version_number,
+                program_descriptors,
+                table: Vec::new(),
+            });
+            track_assert_eq!(
+                syntax.table_id_extension,
+                pmt.program_num,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.version_number,
+                pmt.version_number,
+                ErrorKind::InvalidInput
+            );
+
+            while !reader.is_empty() {
+                pmt.table.push(track!(EsInfo::read_from(&mut reader))?);
+            }
         }
-        Ok(Pmt {
-            program_num: syntax.table_id_extension,
-            pcr_pid,
-            version_number: syntax.version_number,
-            table,
-        })
+        Ok(pmt.expect("Never fails"))
     }
 
     pub(super) fn write_to<W: Write>(&self, writer: W) -> Result<()> {
         track!(self.to_psi().and_then(|psi| psi.write_to(writer)))
     }
 
+    /// Makes the PSI of the PMT.
+    ///
+    /// A PMT always consists of a single section (i.e., it cannot be split into sections).
     fn to_psi(&self) -> Result<Psi> {
         let mut table_data = Vec::new();
         if let Some(pid) = self.pcr_pid {
@@ -93,8 +142,17 @@ impl Pmt {
             track_io!(table_data.write_u16::<BigEndian>(0xFFFF))?;
         }
 
-        let n = 0b1111_0000_0000_0000;
+        let mut program_info = Vec::new();
+        for d in &self.program_descriptors {
+            track!(d.write_to(&mut program_info))?;
+        }
+        track_assert!(
+            program_info.len() <= 0b0011_1111_1111,
+            ErrorKind::InvalidInput
+        );
+        let n = 0b1111_0000_0000_0000 | program_info.len() as u16;
         track_io!(table_data.write_u16::<BigEndian>(n))?;
+        table_data.extend_from_slice(&program_info);
 
         for info in &self.table {
             track!(info.write_to(&mut table_data))?;
@@ -104,16 +162,15 @@ impl Pmt {
             table_id: Self::TABLE_ID,
             private_bit: false,
         };
-        let syntax = Some(PsiTableSyntax {
-            table_id_extension: self.program_num,
-            version_number: self.version_number,
-            current_next_indicator: true,
-            section_number: 0,
-            last_section_number: 0,
-            table_data,
-        });
-        let tables = vec![PsiTable { header, syntax }];
-        Ok(Psi { tables })
+        let table = track!(PsiTable::single(
+            &header,
+            self.program_num,
+            self.version_number,
+            table_data
+        ))?;
+        Ok(Psi {
+            tables: vec![table],
+        })
     }
 }
 
@@ -129,6 +186,30 @@ pub struct EsInfo {
     pub descriptors: Vec<Descriptor>,
 }
 impl EsInfo {
+    /// Decodes the descriptors of the elementary stream.
+    ///
+    /// The malformed descriptors are returned as `TypedDescriptor::Raw`
+    /// like the ones that have an unsupported tag.
+    pub fn typed_descriptors(&self) -> Vec<TypedDescriptor> {
+        self.descriptors
+            .iter()
+            .map(|d| {
+                TypedDescriptor::from_descriptor(d).unwrap_or_else(|e| {
+                    log::debug!("Malformed descriptor: tag={}, error={}", d.tag, e);
+                    TypedDescriptor::Raw(d.clone())
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the CA descriptors of the elementary stream.
+    ///
+    /// Their `ca_pid`s are the PIDs of the ECMs (Entitlement Control Messages) of the stream.
+    /// The malformed descriptors are ignored.
+    pub fn ca_descriptors(&self) -> Vec<CaDescriptor> {
+        descriptor::decode_ca_descriptors(&self.descriptors)
+    }
+
     fn read_from<R: Read>(mut reader: R) -> Result<Self> {
         let stream_type = track_io!(reader.read_u8()).and_then(StreamType::from_u8)?;
         let elementary_pid = track!(Pid::read_from(&mut reader))?;
@@ -164,7 +245,7 @@ impl EsInfo {
     }
 
     fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
-        track_io!(writer.write_u8(self.stream_type as u8))?;
+        track_io!(writer.write_u8(self.stream_type.as_u8()))?;
         track!(self.elementary_pid.write_to(&mut writer))?;
 
         let es_info_len: usize = self.descriptors.iter().map(|d| 2 + d.data.len()).sum();
@@ -188,7 +269,7 @@ pub struct Descriptor {
     pub data: Vec<u8>,
 }
 impl Descriptor {
-    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+    pub(super) fn read_from<R: Read>(mut reader: R) -> Result<Self> {
         let tag = track_io!(reader.read_u8())?;
         let len = track_io!(reader.read_u8())?;
         let mut data = vec![0; len as usize];
@@ -196,10 +277,84 @@ impl Descriptor {
         Ok(Descriptor { tag, data })
     }
 
-    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+    pub(super) fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
         track_io!(writer.write_u8(self.tag))?;
         track_io!(writer.write_u8(self.data.len() as u8))?;
         track_io!(writer.write_all(&self.data))?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn program_descriptors() {
+        let ca = CaDescriptor {
+            ca_system_id: 0x0B00,
+            ca_pid: Pid::new(0x110).unwrap(),
+            private_data: Vec::new(),
+        };
+        let pmt = Pmt {
+            program_num: 1,
+            pcr_pid: Some(Pid::new(0x101).unwrap()),
+            version_number: VersionNumber::new(),
+            program_descriptors: vec![
+                Descriptor {
+                    tag: 0x05, // registration
+                    data: b"CUEI".to_vec(),
+                },
+                ca.to_descriptor().unwrap(),
+                Descriptor {
+                    tag: 0x1D, // IOD
+                    data: vec![0x01, 0x02, 0x03],
+                },
+            ],
+            table: (0..150)
+                .map(|i| EsInfo {
+                    stream_type: StreamType::AdtsAac,
+                    elementary_pid: Pid::new(0x101 + i).unwrap(),
+                    descriptors: Vec::new(),
+                })
+                .collect(),
+        };
+        let psi = pmt.to_psi().unwrap();
+        assert_eq!(psi.tables.len(), 1);
+
+        let mut buf = Vec::new();
+        pmt.write_to(&mut buf).unwrap();
+        let actual = Pmt::read_from(&buf[..]).unwrap();
+        assert_eq!(actual, pmt);
+        assert_eq!(actual.ca_descriptors(), [ca]);
+
+        // A PMT cannot be split into sections
+        let mut pmt = pmt;
+        pmt.table.extend(actual.table.iter().take(50).cloned());
+        assert_eq!(
+            pmt.to_psi().err().map(|e| *e.kind()),
+            Some(ErrorKind::InvalidInput)
+        );
+    }
+
+    #[test]
+    fn typed_es_descriptors() {
+        let es_info = EsInfo {
+            stream_type: StreamType::H264,
+            elementary_pid: Pid::new(0x100).unwrap(),
+            descriptors: vec![
+                Descriptor {
+                    tag: 0x05, // registration
+                    data: b"HEVC".to_vec(),
+                },
+                Descriptor {
+                    tag: 0x28, // AVC video (too short)
+                    data: vec![0x64],
+                },
+            ],
+        };
+        let typed = es_info.typed_descriptors();
+        assert!(matches!(typed[0], TypedDescriptor::Registration(_)));
+        assert_eq!(typed[1], TypedDescriptor::Raw(es_info.descriptors[1].clone()));
+    }
+}
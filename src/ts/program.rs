@@ -0,0 +1,575 @@
+use std::collections::{BTreeMap, HashMap};
+use std::mem;
+
+use es::StreamType;
+use ts::descriptor::RegistrationDescriptor;
+use ts::payload::{Cat, Nit, Pat, Pmt, Sdt};
+use ts::{EsInfo, Pid, VersionNumber};
+
+/// Change of the programs in a transport stream.
+///
+/// `TsPacketReader` raises these events when it applies a new version of PAT or PMT.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProgramEvent {
+    /// A program was added to PAT.
+    ProgramAdded { program_num: u16, pmt_pid: Pid },
+
+    /// A program was removed from PAT (or its PMT PID was changed).
+    ///
+    /// This event is preceded by the `EsRemoved` events of the elementary streams of the program.
+    ProgramRemoved { program_num: u16, pmt_pid: Pid },
+
+    /// An elementary stream was added to PMT.
+    EsAdded {
+        program_num: u16,
+        pid: Pid,
+        stream_type: StreamType,
+    },
+
+    /// An elementary stream was removed from PMT.
+    EsRemoved {
+        program_num: u16,
+        pid: Pid,
+        stream_type: StreamType,
+    },
+
+    /// The stream type of an elementary stream was changed.
+    EsStreamTypeChanged {
+        program_num: u16,
+        pid: Pid,
+        old_stream_type: StreamType,
+        new_stream_type: StreamType,
+    },
+}
+
+/// Program model built from the PAT, CAT, PMTs, NIT and SDT of a transport stream.
+///
+/// This is maintained by `TsPacketReader` (see `TsPacketReader::program_map()`).
+#[derive(Debug, Default, Clone)]
+pub struct ProgramMap {
+    pat_version: Option<(u16, VersionNumber)>,
+    programs: BTreeMap<u16, Program>,
+    network_pid: Option<Pid>,
+    cat: Option<Cat>,
+    nit: Option<Nit>,
+    sdt: Option<Sdt>,
+    pids: HashMap<Pid, PidEntry>,
+}
+impl ProgramMap {
+    /// Returns the transport stream identifier of the current PAT.
+    ///
+    /// If no PAT has been applied yet, it will return `None`.
+    pub fn transport_stream_id(&self) -> Option<u16> {
+        self.pat_version.map(|(id, _)| id)
+    }
+
+    /// Returns an iterator over the programs in ascending order of the program numbers.
+    pub fn programs(&self) -> impl Iterator<Item = &Program> {
+        self.programs.values()
+    }
+
+    /// Returns the program that has the given program number.
+    pub fn program(&self, program_num: u16) -> Option<&Program> {
+        self.programs.get(&program_num)
+    }
+
+    /// Returns the program to which the given PID belongs.
+    ///
+    /// The PID may be the PMT PID, the PCR PID or an elementary stream PID of the program.
+    /// If the PID is shared by several programs, the one that has the smallest program number
+    /// is returned.
+    pub fn program_of(&self, pid: Pid) -> Option<&Program> {
+        self.pids
+            .get(&pid)
+            .and_then(|e| e.program_num)
+            .and_then(|n| self.programs.get(&n))
+    }
+
+    /// Returns the information of the elementary stream that has the given PID.
+    pub fn es_info(&self, pid: Pid) -> Option<&EsInfo> {
+        self.programs.values().filter_map(|p| p.es_info(pid)).next()
+    }
+
+    /// Returns the PID of the NIT specified by the program number `0` of the current PAT.
+    ///
+    /// If the PAT has no such entry (or no PAT has been applied yet), it will return `None`.
+    /// In that case, the NIT is expected to be carried by `Pid::NIT`.
+    pub fn network_pid(&self) -> Option<Pid> {
+        self.network_pid
+    }
+
+    /// Returns the current CAT.
+    ///
+    /// If no CAT has been applied yet, it will return `None`.
+    pub fn cat(&self) -> Option<&Cat> {
+        self.cat.as_ref()
+    }
+
+    /// Returns the current NIT of the actual network.
+    ///
+    /// If no NIT has been applied yet, it will return `None`.
+    pub fn nit(&self) -> Option<&Nit> {
+        self.nit.as_ref()
+    }
+
+    /// Returns the current SDT of the actual transport stream.
+    ///
+    /// The services in the SDT are identified by the program numbers.
+    /// If no SDT has been applied yet, it will return `None`.
+    pub fn sdt(&self) -> Option<&Sdt> {
+        self.sdt.as_ref()
+    }
+
+    /// Returns the kind of the packets of the given PID.
+    ///
+    /// If the PID is not referred by the PAT, CAT or PMTs, it will return `None`.
+    pub fn pid_kind(&self, pid: Pid) -> Option<PidKind> {
+        self.pids.get(&pid).and_then(|e| e.kind)
+    }
+
+    /// Returns the EMM and ECM PIDs of the stream in ascending order.
+    pub fn ca_pids(&self) -> Vec<(Pid, PidKind)> {
+        let mut pids = self
+            .pids
+            .iter()
+            .filter_map(|(&pid, e)| e.kind.map(|kind| (pid, kind)))
+            .filter(|&(_, kind)| kind.is_ca())
+            .collect::<Vec<_>>();
+        pids.sort();
+        pids
+    }
+
+    /// Applies the given CAT if its version differs from the current one.
+    pub(crate) fn apply_cat(&mut self, cat: &Cat) {
+        if self.cat.as_ref().map(|c| c.version_number) == Some(cat.version_number) {
+            return;
+        }
+        self.cat = Some(cat.clone());
+        self.rebuild_pids();
+    }
+
+    /// Applies the given NIT of the actual network.
+    pub(crate) fn apply_nit(&mut self, nit: &Nit) {
+        self.nit = Some(nit.clone());
+    }
+
+    /// Applies the given SDT of the actual transport stream.
+    pub(crate) fn apply_sdt(&mut self, sdt: &Sdt) {
+        self.sdt = Some(sdt.clone());
+    }
+
+    /// Applies the given PAT if its version differs from the current one.
+    pub(crate) fn apply_pat(&mut self, pat: &Pat, events: &mut Vec<ProgramEvent>) {
+        let version = (pat.transport_stream_id, pat.version_number);
+        if self.pat_version == Some(version) {
+            return;
+        }
+        self.pat_version = Some(version);
+        self.network_pid = pat.network_pid();
+
+        let mut old_programs = mem::take(&mut self.programs);
+        for pa in pat.table.iter().filter(|pa| pa.program_num != 0) {
+            match old_programs.remove(&pa.program_num) {
+                Some(program) if program.pmt_pid == pa.program_map_pid => {
+                    self.programs.insert(pa.program_num, program);
+                }
+                Some(program) => {
+                    Self::remove_program(pa.program_num, program, events);
+                    self.add_program(pa.program_num, pa.program_map_pid, events);
+                }
+                None => {
+                    self.add_program(pa.program_num, pa.program_map_pid, events);
+                }
+            }
+        }
+        for (program_num, program) in old_programs {
+            Self::remove_program(program_num, program, events);
+        }
+        self.rebuild_pids();
+    }
+
+    /// Applies the given PMT if its version differs from the current one.
+    pub(crate) fn apply_pmt(&mut self, pid: Pid, pmt: &Pmt, events: &mut Vec<ProgramEvent>) {
+        let program_num = pmt.program_num;
+        let program = match self.programs.get_mut(&program_num) {
+            Some(program) if program.pmt_pid == pid => program,
+            _ => {
+                log::debug!(
+                    "Ignored PMT of unknown program: pid={}, program_num={}",
+                    pid.as_u16(),
+                    program_num
+                );
+                return;
+            }
+        };
+        if program.pmt.as_ref().map(|p| p.version_number) == Some(pmt.version_number) {
+            return;
+        }
+
+        let old_table = program.pmt.take().map_or_else(Vec::new, |p| p.table);
+        for old in &old_table {
+            if !pmt
+                .table
+                .iter()
+                .any(|es| es.elementary_pid == old.elementary_pid)
+            {
+                events.push(ProgramEvent::EsRemoved {
+                    program_num,
+                    pid: old.elementary_pid,
+                    stream_type: old.stream_type,
+                });
+            }
+        }
+        for es in &pmt.table {
+            match old_table
+                .iter()
+                .find(|old| old.elementary_pid == es.elementary_pid)
+            {
+                None => events.push(ProgramEvent::EsAdded {
+                    program_num,
+                    pid: es.elementary_pid,
+                    stream_type: es.stream_type,
+                }),
+                Some(old) if old.stream_type != es.stream_type => {
+                    events.push(ProgramEvent::EsStreamTypeChanged {
+                        program_num,
+                        pid: es.elementary_pid,
+                        old_stream_type: old.stream_type,
+                        new_stream_type: es.stream_type,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        program.pmt = Some(pmt.clone());
+        self.rebuild_pids();
+    }
+
+    fn add_program(&mut self, program_num: u16, pmt_pid: Pid, events: &mut Vec<ProgramEvent>) {
+        events.push(ProgramEvent::ProgramAdded {
+            program_num,
+            pmt_pid,
+        });
+        let program = Program {
+            program_num,
+            pmt_pid,
+            pmt: None,
+        };
+        self.programs.insert(program_num, program);
+    }
+
+    fn remove_program(program_num: u16, program: Program, events: &mut Vec<ProgramEvent>) {
+        for es in program.pmt.iter().flat_map(|pmt| pmt.table.iter()) {
+            events.push(ProgramEvent::EsRemoved {
+                program_num,
+                pid: es.elementary_pid,
+                stream_type: es.stream_type,
+            });
+        }
+        events.push(ProgramEvent::ProgramRemoved {
+            program_num,
+            pmt_pid: program.pmt_pid,
+        });
+    }
+
+    fn rebuild_pids(&mut self) {
+        self.pids.clear();
+        for program in self.programs.values() {
+            let es_pids = program.es_list().iter().map(|es| es.elementary_pid);
+            let pids = Some(program.pmt_pid)
+                .into_iter()
+                .chain(program.pcr_pid())
+                .chain(es_pids);
+            for pid in pids {
+                let entry = self.pids.entry(pid).or_default();
+                entry.program_num.get_or_insert(program.program_num);
+            }
+        }
+
+        if let Some(pid) = self.network_pid {
+            self.pids.entry(pid).or_default().kind = Some(PidKind::Nit);
+        }
+        for program in self.programs.values() {
+            self.pids.entry(program.pmt_pid).or_default().kind = Some(PidKind::Pmt);
+        }
+        for pmt in self.programs.values().filter_map(|p| p.pmt.as_ref()) {
+            let is_blu_ray = pmt.program_descriptors.iter().any(|d| {
+                d.tag == RegistrationDescriptor::TAG && d.data.starts_with(b"HDMV")
+            });
+            for es in &pmt.table {
+                let kind = if es.stream_type == StreamType::SCTE_35 && !is_blu_ray {
+                    PidKind::Scte35
+                } else {
+                    PidKind::Pes
+                };
+                let entry = self.pids.entry(es.elementary_pid).or_default();
+                entry.kind.get_or_insert(kind);
+            }
+        }
+
+        let mut ca_pids = Vec::new();
+        for ca in self.cat.iter().flat_map(|cat| cat.ca_descriptors()) {
+            let kind = PidKind::Emm {
+                ca_system_id: ca.ca_system_id,
+            };
+            ca_pids.push((ca.ca_pid, kind));
+        }
+        for pmt in self.programs.values().filter_map(|p| p.pmt.as_ref()) {
+            let program_ecms = pmt.ca_descriptors().into_iter();
+            let es_ecms = pmt.table.iter().flat_map(|es| es.ca_descriptors());
+            for ca in program_ecms.chain(es_ecms) {
+                let kind = PidKind::Ecm {
+                    ca_system_id: ca.ca_system_id,
+                };
+                ca_pids.push((ca.ca_pid, kind));
+            }
+        }
+        for (pid, kind) in ca_pids {
+            if pid.as_u16() != Pid::NULL {
+                self.pids.entry(pid).or_default().kind.get_or_insert(kind);
+            }
+        }
+    }
+}
+
+/// An entry of the PID index of `ProgramMap`.
+#[derive(Debug, Default, Clone, Copy)]
+struct PidEntry {
+    kind: Option<PidKind>,
+
+    /// The smallest number of the programs to which the PID belongs.
+    program_num: Option<u16>,
+}
+
+/// Kind of the packets of a PID.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum PidKind {
+    /// PMT of a program.
+    Pmt,
+
+    /// NIT specified by the program number `0` of the PAT.
+    Nit,
+
+    /// Elementary stream of a program.
+    Pes,
+
+    /// SCTE-35 splice information of a program.
+    ///
+    /// The elementary streams of `StreamType::SCTE_35` are classified as this kind unless
+    /// the program has the Blu-ray registration descriptor (`"HDMV"`).
+    Scte35,
+
+    /// EMMs (Entitlement Management Messages) of a CA system listed in the CAT.
+    Emm { ca_system_id: u16 },
+
+    /// ECMs (Entitlement Control Messages) of a CA system listed in a PMT.
+    Ecm { ca_system_id: u16 },
+}
+impl PidKind {
+    /// Returns `true` if the PID carries EMMs or ECMs.
+    pub fn is_ca(&self) -> bool {
+        matches!(*self, PidKind::Emm { .. } | PidKind::Ecm { .. })
+    }
+}
+
+/// Program in a transport stream.
+#[derive(Debug, Clone)]
+pub struct Program {
+    program_num: u16,
+    pmt_pid: Pid,
+    pmt: Option<Pmt>,
+}
+impl Program {
+    /// Returns the program number.
+    pub fn program_num(&self) -> u16 {
+        self.program_num
+    }
+
+    /// Returns the PID of the packets that carry the PMT of the program.
+    pub fn pmt_pid(&self) -> Pid {
+        self.pmt_pid
+    }
+
+    /// Returns the current PMT of the program.
+    ///
+    /// If the PMT has not been received yet, it will return `None`.
+    pub fn pmt(&self) -> Option<&Pmt> {
+        self.pmt.as_ref()
+    }
+
+    /// Returns the PID of the packets that carry the PCR of the program.
+    pub fn pcr_pid(&self) -> Option<Pid> {
+        self.pmt.as_ref().and_then(|p| p.pcr_pid)
+    }
+
+    /// Returns the elementary streams of the program.
+    pub fn es_list(&self) -> &[EsInfo] {
+        self.pmt.as_ref().map_or(&[], |p| &p.table[..])
+    }
+
+    /// Returns the information of the elementary stream that has the given PID.
+    pub fn es_info(&self, pid: Pid) -> Option<&EsInfo> {
+        self.es_list().iter().find(|es| es.elementary_pid == pid)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ts::{EsInfo, ProgramAssociation};
+
+    fn pat(version: u8, programs: &[(u16, u16)]) -> Pat {
+        Pat {
+            transport_stream_id: 1,
+            version_number: VersionNumber::from_u8(version).unwrap(),
+            table: programs
+                .iter()
+                .map(|&(program_num, pid)| ProgramAssociation {
+                    program_num,
+                    program_map_pid: Pid::new(pid).unwrap(),
+                })
+                .collect(),
+        }
+    }
+
+    fn pmt(program_num: u16, version: u8, streams: &[(u16, StreamType)]) -> Pmt {
+        Pmt {
+            program_num,
+            pcr_pid: None,
+            version_number: VersionNumber::from_u8(version).unwrap(),
+            program_descriptors: Vec::new(),
+            table: streams
+                .iter()
+                .map(|&(pid, stream_type)| EsInfo {
+                    stream_type,
+                    elementary_pid: Pid::new(pid).unwrap(),
+                    descriptors: Vec::new(),
+                })
+                .collect(),
+        }
+    }
+
+    fn pid(pid: u16) -> Pid {
+        Pid::new(pid).unwrap()
+    }
+
+    #[test]
+    fn program_changes() {
+        let mut map = ProgramMap::default();
+        let mut events = Vec::new();
+
+        map.apply_pat(&pat(0, &[(1, 0x100), (2, 0x200)]), &mut events);
+        assert_eq!(events.len(), 2);
+        assert_eq!(map.pid_kind(pid(0x200)), Some(PidKind::Pmt));
+
+        let video = (0x101, StreamType::H264);
+        let audio = (0x102, StreamType::AdtsAac);
+        map.apply_pmt(pid(0x100), &pmt(1, 0, &[video, audio]), &mut events);
+        assert_eq!(map.pid_kind(pid(0x102)), Some(PidKind::Pes));
+        assert_eq!(map.transport_stream_id(), Some(1));
+        assert_eq!(map.programs().count(), 2);
+        assert_eq!(map.program_of(pid(0x102)).map(|p| p.program_num()), Some(1));
+        assert_eq!(map.program_of(pid(0x200)).map(|p| p.program_num()), Some(2));
+        assert_eq!(
+            map.es_info(pid(0x101)).map(|es| es.stream_type),
+            Some(StreamType::H264)
+        );
+        assert_eq!(map.program(2).map(|p| p.es_list().len()), Some(0));
+
+        // The same version is ignored.
+        events.clear();
+        map.apply_pmt(pid(0x100), &pmt(1, 0, &[video]), &mut events);
+        map.apply_pat(&pat(0, &[]), &mut events);
+        assert!(events.is_empty());
+
+        // The audio stream is swapped.
+        let new_audio = (0x103, StreamType::Mpeg1Audio);
+        let changed_video = (0x101, StreamType::H265);
+        map.apply_pmt(
+            pid(0x100),
+            &pmt(1, 1, &[changed_video, new_audio]),
+            &mut events,
+        );
+        assert_eq!(
+            events,
+            [
+                ProgramEvent::EsRemoved {
+                    program_num: 1,
+                    pid: pid(0x102),
+                    stream_type: StreamType::AdtsAac,
+                },
+                ProgramEvent::EsStreamTypeChanged {
+                    program_num: 1,
+                    pid: pid(0x101),
+                    old_stream_type: StreamType::H264,
+                    new_stream_type: StreamType::H265,
+                },
+                ProgramEvent::EsAdded {
+                    program_num: 1,
+                    pid: pid(0x103),
+                    stream_type: StreamType::Mpeg1Audio,
+                },
+            ]
+        );
+        assert_eq!(map.pid_kind(pid(0x102)), None);
+
+        // The program 1 is removed.
+        events.clear();
+        map.apply_pat(&pat(1, &[(2, 0x200)]), &mut events);
+        assert_eq!(events.len(), 3);
+        assert_eq!(
+            events[2],
+            ProgramEvent::ProgramRemoved {
+                program_num: 1,
+                pmt_pid: pid(0x100),
+            }
+        );
+        assert_eq!(map.pid_kind(pid(0x100)), None);
+        assert_eq!(map.pid_kind(pid(0x101)), None);
+        assert_eq!(map.pid_kind(pid(0x200)), Some(PidKind::Pmt));
+    }
+
+    #[test]
+    fn ca_pids() {
+        use ts::CaDescriptor;
+
+        let ca = |ca_system_id: u16, pid: u16| CaDescriptor {
+            ca_system_id,
+            ca_pid: Pid::new(pid).unwrap(),
+            private_data: Vec::new(),
+        };
+
+        let mut map = ProgramMap::default();
+        let mut events = Vec::new();
+        map.apply_pat(&pat(0, &[(1, 0x100)]), &mut events);
+
+        let mut pmt = pmt(1, 0, &[(0x101, StreamType::H264)]);
+        pmt.program_descriptors
+            .push(ca(0x0B00, 0x110).to_descriptor().unwrap());
+        pmt.table[0]
+            .descriptors
+            .push(ca(0x0100, 0x111).to_descriptor().unwrap());
+        map.apply_pmt(pid(0x100), &pmt, &mut events);
+
+        let cat = Cat {
+            version_number: VersionNumber::new(),
+            descriptors: vec![
+                ca(0x0B00, 0x120).to_descriptor().unwrap(),
+                ca(0x0B00, Pid::NULL).to_descriptor().unwrap(),
+            ],
+        };
+        map.apply_cat(&cat);
+        assert_eq!(map.cat(), Some(&cat));
+        assert_eq!(
+            map.ca_pids(),
+            [
+                (pid(0x110), PidKind::Ecm { ca_system_id: 0x0B00 }),
+                (pid(0x111), PidKind::Ecm { ca_system_id: 0x0100 }),
+                (pid(0x120), PidKind::Emm { ca_system_id: 0x0B00 }),
+            ]
+        );
+        assert_eq!(map.pid_kind(pid(0x101)), Some(PidKind::Pes));
+    }
+}
@@ -7,6 +7,12 @@ use {ErrorKind, Result};
 
 const MAX_SYNTAX_SECTION_LEN: usize = 1021;
 
+/// Maximum size of the table data of a section.
+const MAX_TABLE_DATA_LEN: usize = MAX_SYNTAX_SECTION_LEN - 9;
+
+/// Maximum number of sections of a table.
+const MAX_SECTIONS: usize = 256;
+
 /// Program-specific information.
 #[derive(Debug)]
 pub struct Psi {
@@ -43,12 +49,107 @@ impl Psi {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PsiTable {
     pub header: PsiTableHeader,
     pub syntax: Option<PsiTableSyntax>,
 }
 impl PsiTable {
+    /// Makes a table that consists of a single section.
+    ///
+    /// If the section exceeds the maximum section length (1021 bytes),
+    /// it will return an `ErrorKind::InvalidInput` error.
+    pub fn single(
+        header: &PsiTableHeader,
+        table_id_extension: u16,
+        version_number: VersionNumber,
+        table_data: Vec<u8>,
+    ) -> Result<Self> {
+        track_assert!(
+            table_data.len() <= MAX_TABLE_DATA_LEN,
+            ErrorKind::InvalidInput,
+            "Too large section: table_data={} bytes",
+            table_data.len()
+        );
+        Ok(PsiTable {
+            header: PsiTableHeader {
+                table_id: header.table_id,
+                private_bit: header.private_bit,
+            },
+            syntax: Some(PsiTableSyntax {
+                table_id_extension,
+                version_number,
+                current_next_indicator: true,
+                section_number: 0,
+                last_section_number: 0,
+                table_data,
+            }),
+        })
+    }
+
+    /// Makes the sections of a table.
+    ///
+    /// `common_data` is placed at the beginning of the table data of every section,
+    /// and the entries are distributed over as few sections as possible.
+    pub fn split(
+        header: &PsiTableHeader,
+        table_id_extension: u16,
+        version_number: VersionNumber,
+        common_data: &[u8],
+        entries: &[Vec<u8>],
+    ) -> Result<Vec<Self>> {
+        let mut table_data_list = Vec::new();
+        let mut table_data = common_data.to_owned();
+        for entry in entries {
+            track_assert!(
+                common_data.len() + entry.len() <= MAX_TABLE_DATA_LEN,
+                ErrorKind::InvalidInput,
+                "Too large table entry: {} bytes",
+                entry.len()
+            );
+            if table_data.len() + entry.len() > MAX_TABLE_DATA_LEN {
+                table_data_list.push(table_data);
+                table_data = common_data.to_owned();
+            }
+            table_data.extend_from_slice(entry);
+        }
+        table_data_list.push(table_data);
+        track_assert!(
+            table_data_list.len() <= MAX_SECTIONS,
+            ErrorKind::InvalidInput,
+            "Too many sections: {}",
+            table_data_list.len()
+        );
+
+        let last_section_number = (table_data_list.len() - 1) as u8;
+        let tables = table_data_list
+            .into_iter()
+            .enumerate()
+            .map(|(i, table_data)| PsiTable {
+                header: PsiTableHeader {
+                    table_id: header.table_id,
+                    private_bit: header.private_bit,
+                },
+                syntax: Some(PsiTableSyntax {
+                    table_id_extension,
+                    version_number,
+                    current_next_indicator: true,
+                    section_number: i as u8,
+                    last_section_number,
+                    table_data,
+                }),
+            })
+            .collect();
+        Ok(tables)
+    }
+
+    /// Reads a table from a complete section.
+    pub fn read_section(mut section: &[u8]) -> Result<Self> {
+        let table = track!(Self::read_from(&mut section))?;
+        track_assert!(section.is_empty(), ErrorKind::InvalidInput);
+        Ok(table)
+    }
+
     fn read_from<R: Read>(reader: R) -> Result<Self> {
         let mut reader = WithCrc32::new(reader);
         let (header, syntax_section_len) = track!(PsiTableHeader::read_from(&mut reader))?;
@@ -83,7 +184,7 @@ impl PsiTable {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PsiTableHeader {
     pub table_id: u8,
     pub private_bit: bool,
@@ -139,7 +240,7 @@ impl PsiTableHeader {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct PsiTableSyntax {
     pub table_id_extension: u16,
     pub version_number: VersionNumber,
@@ -1,73 +1,570 @@
+use byteorder::{BigEndian, ByteOrder};
+use std::cmp;
 use std::collections::HashMap;
-use std::io::Read;
+use std::io::{Read, Seek, SeekFrom};
+use std::mem;
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio::io::{AsyncRead, ReadBuf};
 
-use ts::payload::{Bytes, Null, Pat, Pes, Pmt};
-use ts::{AdaptationField, Pid, TsHeader, TsPacket, TsPayload};
-use {ErrorKind, Result};
+use error::ErrorHandler;
+use time::Timestamp;
+use ts::continuity::ContinuityChecker;
+use ts::payload::{Bytes, Cat, Nit, Null, Pat, Pes, Pmt, Sdt, SpliceInfoSection};
+use ts::reed_solomon::{self, ReedSolomon};
+use ts::program::PidKind;
+use ts::psi::PsiTable;
+use ts::section::{self, SectionAssembler, TableCollector};
+use ts::seek;
+use ts::sync::PacketSynchronizer;
+use ts::{ContinuityEvent, ContinuityEventKind, PacketFormat, PacketPosition, Pid, PidFilter,
+         ProgramEvent, ProgramMap, SeekClock, TpExtraHeader, TsPacket, TsPacketRef, TsPayload};
+use {ErrorKind, ErrorPolicy, PacketError, Result};
+
+const READ_BUF_SIZE: usize = TsPacket::SIZE * 8;
 
 /// The `ReadTsPacket` trait allows for reading TS packets from a source.
 pub trait ReadTsPacket {
     /// Reads a TS packet.
     ///
     /// If the end of the stream is reached, it will return `Ok(None)`.
+    ///
+    /// Note that the packets of a PSI table that spans several packets have `TsPayload::Raw`
+    /// payloads (see `TsPayload` for how to get the completed table).
     fn read_ts_packet(&mut self) -> Result<Option<TsPacket>>;
 
     /// Peeks at next packet without pulling the packet off the buffer.
+    ///
+    /// If the end of the stream is reached or an error occurs, it will return `None`
+    /// (see `try_peek_ts_packet()` for the error).
     fn peek_ts_packet(&mut self) -> Option<&TsPacket>;
+
+    /// Peeks at next packet without pulling the packet off the buffer.
+    ///
+    /// If the end of the stream is reached, it will return `Ok(None)`.
+    /// The default implementation delegates to `peek_ts_packet()`.
+    fn try_peek_ts_packet(&mut self) -> Result<Option<&TsPacket>> {
+        Ok(self.peek_ts_packet())
+    }
+
+    /// Returns the continuity error detected for the packet read (or peeked) last.
+    ///
+    /// `PesPacketReader` uses this to skip duplicate packets and to drop the PES packets
+    /// whose TS packets are lost.
+    /// The default implementation does not check the continuity counters and returns `None`.
+    fn last_continuity_error(&self) -> Option<ContinuityEventKind> {
+        None
+    }
+}
+
+/// The `AsyncReadTsPacket` trait allows for reading TS packets from an asynchronous source.
+#[cfg(feature = "tokio")]
+pub trait AsyncReadTsPacket {
+    /// Attempts to read a TS packet.
+    ///
+    /// If the end of the stream is reached, it will return `Poll::Ready(Ok(None))`.
+    fn poll_read_ts_packet(&mut self, cx: &mut Context) -> Poll<Result<Option<TsPacket>>>;
+
+    /// Returns the continuity error detected for the packet read last.
+    ///
+    /// See `ReadTsPacket::last_continuity_error()` for details.
+    fn last_continuity_error(&self) -> Option<ContinuityEventKind> {
+        None
+    }
+
+    /// Returns a future that reads a TS packet.
+    fn read_ts_packet_async(&mut self) -> ReadTsPacketFuture<'_, Self>
+    where
+        Self: Sized,
+    {
+        ReadTsPacketFuture { reader: self }
+    }
+}
+
+/// Future returned by `AsyncReadTsPacket::read_ts_packet_async()`.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct ReadTsPacketFuture<'a, R: 'a> {
+    reader: &'a mut R,
+}
+#[cfg(feature = "tokio")]
+impl<'a, R: AsyncReadTsPacket> Future for ReadTsPacketFuture<'a, R> {
+    type Output = Result<Option<TsPacket>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.get_mut().reader.poll_read_ts_packet(cx)
+    }
+}
+
+/// Event raised by `TsPacketReader` when a PSI table is completed.
+///
+/// See `TsPacketReader::enable_table_events()`.
+#[allow(missing_docs, clippy::large_enum_variant)]
+#[derive(Debug, Clone)]
+pub enum TableEvent {
+    /// A completed PAT.
+    Pat(Pat),
+
+    /// A completed CAT.
+    Cat(Cat),
+
+    /// A completed PMT carried by the packets of `pid`.
+    Pmt { pid: Pid, pmt: Pmt },
+
+    /// A completed NIT of the actual or another network carried by the packets of `pid`.
+    Nit { pid: Pid, nit: Nit },
+
+    /// A completed SDT of the actual or another transport stream.
+    Sdt(Sdt),
+
+    /// A completed SCTE-35 splice information section carried by the packets of `pid`.
+    SpliceInfo {
+        pid: Pid,
+        section: SpliceInfoSection,
+    },
+}
+
+/// Snapshot of the state used by `TsPacketReader` to decode the PSI payloads.
+#[cfg(feature = "rayon")]
+#[derive(Debug, Clone)]
+pub(crate) struct PsiState {
+    programs: ProgramMap,
+    sections: HashMap<Pid, SectionAssembler>,
+    tables: HashMap<Pid, TableCollector>,
 }
 
 /// TS packet reader.
+///
+/// The reader resynchronizes itself to the packet boundaries if the input stream
+/// contains corrupted or missing bytes.
+/// Because of that, it may read ahead some bytes from the underlaying stream.
+///
+/// If the `tokio` feature is enabled, it also implements `AsyncReadTsPacket`
+/// for the streams that implement `tokio::io::AsyncRead`.
 #[derive(Debug)]
 pub struct TsPacketReader<R> {
-    peeked_packet: Option<TsPacket>,
+    peeked_packet: Option<(TsPacket, Option<ContinuityEventKind>)>,
     stream: R,
-    pids: HashMap<Pid, PidKind>,
+    format: PacketFormat,
+    reed_solomon: Option<ReedSolomon>,
+    eos: bool,
+    sync: PacketSynchronizer,
+    programs: ProgramMap,
+    program_events: Vec<ProgramEvent>,
+    continuity: ContinuityChecker,
+    continuity_error: Option<ContinuityEventKind>,
+    continuity_events: Vec<ContinuityEvent>,
+    sections: HashMap<Pid, SectionAssembler>,
+    tables: HashMap<Pid, TableCollector>,
+    errors: ErrorHandler,
+    reported_skipped_bytes: u64,
+    pid_filter: PidFilter,
+    table_events: Option<Vec<TableEvent>>,
 }
-impl<R: Read> TsPacketReader<R> {
+impl<R> TsPacketReader<R> {
     /// Makes a new `TsPacketReader` instance.
     pub fn new(stream: R) -> Self {
+        Self::with_format(stream, PacketFormat::Ts)
+    }
+
+    /// Makes a new `TsPacketReader` instance that reads packets of the given format.
+    pub fn with_format(stream: R, format: PacketFormat) -> Self {
         TsPacketReader {
             peeked_packet: None,
             stream,
-            pids: HashMap::new(),
+            format,
+            reed_solomon: None,
+            eos: false,
+            sync: PacketSynchronizer::new(format),
+            programs: ProgramMap::default(),
+            program_events: Vec::new(),
+            continuity: ContinuityChecker::default(),
+            continuity_error: None,
+            continuity_events: Vec::new(),
+            sections: HashMap::new(),
+            tables: HashMap::new(),
+            errors: ErrorHandler::default(),
+            reported_skipped_bytes: 0,
+            pid_filter: PidFilter::default(),
+            table_events: None,
         }
     }
 
+    /// Returns the packet format of the stream.
+    pub fn format(&self) -> PacketFormat {
+        self.format
+    }
+
+    /// Enables or disables the Reed-Solomon decoding of `PacketFormat::Dvb` packets.
+    ///
+    /// If enabled, the reader corrects up to 8 erroneous bytes in each packet
+    /// by using the parity bytes.
+    /// Packets that cannot be corrected are returned with `transport_error_indicator` set.
+    ///
+    /// It is disabled by default, and has no effect on the other formats.
+    pub fn set_reed_solomon_decoding(&mut self, enabled: bool) {
+        self.reed_solomon = if enabled {
+            Some(ReedSolomon::new())
+        } else {
+            None
+        };
+    }
+
+    /// Sets the policy for the packets that cannot be decoded.
+    ///
+    /// The default policy is `ErrorPolicy::Lenient`.
+    /// Note that I/O errors are always returned to the caller regardless of the policy.
+    ///
+    /// With `ErrorPolicy::Strict`, the loss of the packet synchronization is also an error:
+    /// instead of resynchronizing silently, the reader returns an `ErrorKind::InvalidInput` error
+    /// and the next call continues from the resynchronized packet.
+    pub fn set_error_policy(&mut self, policy: ErrorPolicy) {
+        self.errors.set_policy(policy);
+    }
+
+    /// Returns the number of the packets dropped due to decoding errors.
+    pub fn error_count(&self) -> u64 {
+        self.errors.count()
+    }
+
+    /// Sets the filter that selects the packets to be returned.
+    ///
+    /// The default filter is `PidFilter::All`.
+    pub fn set_pid_filter(&mut self, filter: PidFilter) {
+        self.pid_filter = filter;
+    }
+
     /// Returns a reference to the underlaying byte stream.
     pub fn stream(&self) -> &R {
         &self.stream
     }
 
     /// Converts `TsPacketReader` into the underlaying byte stream `R`.
+    ///
+    /// Note that the bytes buffered by the reader are discarded.
     pub fn into_stream(self) -> R {
         self.stream
     }
 
-    fn read_next_packet(&mut self) -> Result<Option<TsPacket>> {
-        let mut reader = self.stream.by_ref().take(TsPacket::SIZE as u64);
-        let mut peek = [0; 1];
-        let eos = track_io!(reader.read(&mut peek))? == 0;
-        if eos {
-            return Ok(None);
+    /// Returns the total number of bytes skipped to (re)gain packet synchronization.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.sync.skipped_bytes()
+    }
+
+    /// Returns the programs of the stream built from the PAT and PMTs read so far.
+    pub fn program_map(&self) -> &ProgramMap {
+        &self.programs
+    }
+
+    /// Takes the program change events raised since the last call.
+    ///
+    /// The events are raised when a new version of PAT or PMT is applied.
+    /// Tables whose `current_next_indicator` is `0` are kept as the next versions,
+    /// and they are applied when a section of the version becomes current.
+    ///
+    /// Note that `peek_ts_packet()` may raise the events of the peeked packet.
+    pub fn take_program_events(&mut self) -> Vec<ProgramEvent> {
+        mem::take(&mut self.program_events)
+    }
+
+    /// Takes the continuity errors detected since the last call.
+    ///
+    /// The continuity counter of each PID is checked except for the packets that
+    /// have no payload. A packet whose `discontinuity_indicator` is set restarts the check.
+    ///
+    /// Note that `peek_ts_packet()` may detect the errors of the peeked packet.
+    pub fn take_continuity_events(&mut self) -> Vec<ContinuityEvent> {
+        mem::take(&mut self.continuity_events)
+    }
+
+    /// Makes the reader record the completed PSI tables as `TableEvent`s.
+    ///
+    /// Unlike the payloads of the returned packets, the events are raised
+    /// for the tables that span several packets (or sections) too.
+    /// It is disabled by default.
+    pub fn enable_table_events(&mut self) {
+        self.table_events = Some(Vec::new());
+    }
+
+    /// Takes the PSI tables completed since the last call.
+    ///
+    /// It always returns an empty vector unless `enable_table_events()` has been called.
+    ///
+    /// Note that `peek_ts_packet()` may raise the events of the peeked packet.
+    pub fn take_table_events(&mut self) -> Vec<TableEvent> {
+        self.table_events.as_mut().map(mem::take).unwrap_or_default()
+    }
+
+    /// Handles an error of a packet according to the error policy.
+    pub(crate) fn handle_error(&mut self, error: PacketError) -> Result<()> {
+        track!(self.errors.handle(error))
+    }
+
+    /// Returns a snapshot of the program map and the partially read PSI sections.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn psi_state(&self) -> PsiState {
+        PsiState {
+            programs: self.programs.clone(),
+            sections: self.sections.clone(),
+            tables: self.tables.clone(),
+        }
+    }
+
+    /// Restores the state taken by `psi_state()`.
+    ///
+    /// This should be called after `reset_position()`, which discards the partial sections.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn set_psi_state(&mut self, state: PsiState) {
+        self.programs = state.programs;
+        self.sections = state.sections;
+        self.tables = state.tables;
+    }
+
+    /// Returns the position of the next packet to be consumed.
+    ///
+    /// If the next bytes are not synchronized, the actual packet may start after the offset.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn next_packet_position(&self) -> PacketPosition {
+        PacketPosition {
+            offset: self.sync.next_offset(),
+            index: self.sync.packet_count(),
         }
+    }
 
-        let (header, adaptation_field_control, payload_unit_start_indicator) =
-            track!(TsHeader::read_from(peek.chain(&mut reader)))?;
+    /// Discards the buffered bytes and the stream-dependent state,
+    /// then restarts the reader at the given position.
+    ///
+    /// The program map is kept.
+    pub(crate) fn reset_position(&mut self, position: PacketPosition) {
+        self.sync.reset(position.offset, position.index);
+        self.peeked_packet = None;
+        self.eos = false;
+        self.continuity = ContinuityChecker::default();
+        self.sections.clear();
+        self.tables.clear();
+    }
 
-        let adaptation_field = if adaptation_field_control.has_adaptation_field() {
-            track!(AdaptationField::read_from(&mut reader))?
+    /// Consumes the buffered packets without returning them.
+    ///
+    /// Only the PSI packets are decoded to keep the PSI state up to date,
+    /// and their decoding errors are ignored.
+    /// To skip the other packets quickly, the Reed-Solomon decoding is applied only to
+    /// the packets whose uncorrected PID is a PSI PID.
+    /// It returns the number of the consumed packets.
+    #[cfg(feature = "rayon")]
+    pub(crate) fn skip_packets(&mut self) -> u64 {
+        let mut count = 0;
+        while self.sync.is_packet_ready(self.eos) {
+            count += 1;
+            let mut buf = [0; reed_solomon::CODEWORD_SIZE];
+            let tp_extra_header = match self.consume_packet(&mut buf) {
+                Ok(header) => header,
+                Err(_) => continue,
+            };
+            let pid = BigEndian::read_u16(&buf[1..]) & Pid::MAX;
+            let pid = Pid::new(pid).expect("Never fails");
+            if is_psi_pid(&self.programs, pid) {
+                self.correct_packet(&mut buf);
+                let _ = self.decode_packet(&buf[..TsPacket::SIZE], tp_extra_header);
+            }
+        }
+        self.continuity_events.clear();
+        count
+    }
+
+    /// Pushes the bytes read from the underlaying stream (empty bytes mean the end of the stream).
+    pub(crate) fn push_bytes(&mut self, bytes: &[u8]) {
+        if bytes.is_empty() {
+            self.eos = true;
         } else {
-            None
+            self.sync.push(bytes);
+        }
+    }
+
+    /// Returns the continuity error detected for the packet decoded last.
+    pub(crate) fn decoded_continuity_error(&self) -> Option<ContinuityEventKind> {
+        self.continuity_error
+    }
+
+    /// Decodes the next packet from the buffered bytes.
+    ///
+    /// It returns `Ok(None)` if more bytes are needed (or the end of the stream is reached).
+    pub(crate) fn decode_next_packet(&mut self) -> Result<Option<TsPacket>> {
+        while self.sync.is_packet_ready(self.eos) {
+            track!(self.check_skipped_bytes())?;
+
+            let mut buf = [0; reed_solomon::CODEWORD_SIZE];
+            let tp_extra_header = track!(self.consume_packet(&mut buf))?;
+            self.correct_packet(&mut buf);
+            let bytes = &buf[..TsPacket::SIZE];
+
+            let pid = BigEndian::read_u16(&bytes[1..]) & Pid::MAX;
+            let pid = Pid::new(pid).expect("Never fails");
+            let is_selected = self.pid_filter.is_selected(pid);
+            let is_psi = is_psi_pid(&self.programs, pid);
+            if !is_selected && !is_psi {
+                continue;
+            }
+
+            match self.decode_packet(bytes, tp_extra_header) {
+                Ok(packet) => {
+                    if is_selected {
+                        return Ok(Some(packet));
+                    }
+                }
+                Err(e) => {
+                    let error = PacketError {
+                        error: e,
+                        pid,
+                        offset: Some(self.sync.packet_offset()),
+                    };
+                    track!(self.errors.handle(error))?;
+                }
+            }
+        }
+        track!(self.check_skipped_bytes())?;
+        Ok(None)
+    }
+
+    /// Returns an error if bytes have been skipped to resynchronize and the policy is strict.
+    ///
+    /// Otherwise, the skipped bytes are only counted (see `skipped_bytes()`).
+    fn check_skipped_bytes(&mut self) -> Result<()> {
+        let skipped_bytes = self.sync.skipped_bytes() - self.reported_skipped_bytes;
+        self.reported_skipped_bytes = self.sync.skipped_bytes();
+        track_assert!(
+            skipped_bytes == 0 || !self.errors.is_strict(),
+            ErrorKind::InvalidInput,
+            "Lost packet synchronization: skipped_bytes={}, next_offset={}",
+            skipped_bytes,
+            self.sync.next_offset()
+        );
+        Ok(())
+    }
+
+    /// Copies the bytes of the next TS packet to `buf`.
+    ///
+    /// The errors of the packet are corrected separately by `correct_packet()`.
+    fn consume_packet(
+        &mut self,
+        buf: &mut [u8; reed_solomon::CODEWORD_SIZE],
+    ) -> Result<Option<TpExtraHeader>> {
+        let tp_extra_header = {
+            let mut bytes = self.sync.consume_packet();
+            match self.format {
+                PacketFormat::Ts => {
+                    buf[..TsPacket::SIZE].copy_from_slice(bytes);
+                    None
+                }
+                PacketFormat::M2ts => {
+                    let header = track!(TpExtraHeader::read_from(&mut bytes))?;
+                    buf[..TsPacket::SIZE].copy_from_slice(bytes);
+                    Some(header)
+                }
+                PacketFormat::Dvb => {
+                    buf.copy_from_slice(bytes);
+                    None
+                }
+            }
         };
+        Ok(tp_extra_header)
+    }
 
-        let payload = if adaptation_field_control.has_payload() {
+    /// Corrects the errors of the packet copied by `consume_packet()`
+    /// if the Reed-Solomon decoding is enabled.
+    fn correct_packet(&self, buf: &mut [u8; reed_solomon::CODEWORD_SIZE]) {
+        if self.format != PacketFormat::Dvb {
+            return;
+        }
+        if let Some(ref rs) = self.reed_solomon {
+            if rs.decode(buf).is_none() {
+                log::debug!("Uncorrectable packet: offset={}", self.sync.packet_offset());
+                buf[1] |= 0b1000_0000; // transport_error_indicator
+            }
+        }
+    }
+
+    fn decode_packet(
+        &mut self,
+        bytes: &[u8],
+        tp_extra_header: Option<TpExtraHeader>,
+    ) -> Result<TsPacket> {
+        let packet = track!(TsPacketRef::new(bytes))?;
+        let header = track!(packet.header())?;
+        let adaptation_field = track!(packet.adaptation_field())?;
+
+        let payload = if let Some(mut reader) = track!(packet.payload())? {
+            let pusi = packet.payload_unit_start_indicator();
             let payload = match header.pid.as_u16() {
                 Pid::PAT => {
-                    let pat = track!(Pat::read_from(&mut reader))?;
-                    for pa in &pat.table {
-                        self.pids.insert(pa.program_map_pid, PidKind::Pmt);
+                    let is_single_section = section::is_single_section_table(reader);
+                    let mut last_pat = None;
+                    let pid = header.pid;
+                    for tables in track!(self.collect_tables(pid, pusi, reader, &[Pat::TABLE_ID]))? {
+                        let pat = track!(Pat::from_tables(tables))?;
+                        self.programs.apply_pat(&pat, &mut self.program_events);
+                        if let Some(ref mut events) = self.table_events {
+                            events.push(TableEvent::Pat(pat.clone()));
+                        }
+                        last_pat = Some(pat);
+                    }
+                    self.discard_unused_sections();
+                    match last_pat {
+                        Some(pat) if is_single_section => {
+                            reader = &[];
+                            TsPayload::Pat(pat)
+                        }
+                        _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
+                    }
+                }
+                Pid::CAT => {
+                    let is_single_section = section::is_single_section_table(reader);
+                    let mut last_cat = None;
+                    let pid = header.pid;
+                    for tables in track!(self.collect_tables(pid, pusi, reader, &[Cat::TABLE_ID]))? {
+                        let cat = track!(Cat::from_tables(tables))?;
+                        self.programs.apply_cat(&cat);
+                        if let Some(ref mut events) = self.table_events {
+                            events.push(TableEvent::Cat(cat.clone()));
+                        }
+                        last_cat = Some(cat);
+                    }
+                    match last_cat {
+                        Some(cat) if is_single_section => {
+                            reader = &[];
+                            TsPayload::Cat(cat)
+                        }
+                        _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
+                    }
+                }
+                Pid::NIT => track!(self.decode_nit(header.pid, pusi, &mut reader))?,
+                Pid::SDT => {
+                    let is_single_section = section::is_single_section_table(reader);
+                    let mut last_sdt = None;
+                    let pid = header.pid;
+                    let table_ids = [Sdt::ACTUAL_TABLE_ID, Sdt::OTHER_TABLE_ID];
+                    for tables in track!(self.collect_tables(pid, pusi, reader, &table_ids))? {
+                        let sdt = track!(Sdt::from_tables(tables))?;
+                        if sdt.actual {
+                            self.programs.apply_sdt(&sdt);
+                        }
+                        if let Some(ref mut events) = self.table_events {
+                            events.push(TableEvent::Sdt(sdt.clone()));
+                        }
+                        last_sdt = Some(sdt);
+                    }
+                    match last_sdt {
+                        Some(sdt) if is_single_section => {
+                            reader = &[];
+                            TsPayload::Sdt(sdt)
+                        }
+                        _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
                     }
-                    TsPayload::Pat(pat)
                 }
                 Pid::NULL => {
                     let null = track!(Null::read_from(&mut reader))?;
@@ -78,93 +575,400 @@ impl<R: Read> TsPacketReader<R> {
                     let bytes = track!(Bytes::read_from(&mut reader))?;
                     TsPayload::Raw(bytes)
                 }
-                _ => {
-                    if !self.pids.contains_key(&header.pid) {
-                        let null = track!(Null::read_from(&mut reader))?;
-                        TsPayload::Null(null)
-                    } else {
-                        let kind = track_assert_some!(
-                            self.pids.get(&header.pid).cloned(),
-                            ErrorKind::InvalidInput,
-                            "Unknown PID: header={:?}",
-                            header
-                        );
-                        match kind {
-                            PidKind::Pmt => {
-                                let pmt = track!(Pmt::read_from(&mut reader))?;
-                                for es in &pmt.table {
-                                    self.pids.insert(es.elementary_pid, PidKind::Pes);
-                                }
+                _ => match self.programs.pid_kind(header.pid) {
+                    None => {
+                        // The PID is not (yet) listed in any PMT
+                        let bytes = track!(Bytes::read_from(&mut reader))?;
+                        TsPayload::Raw(bytes)
+                    }
+                    Some(PidKind::Pmt) => {
+                        let is_single_section = section::is_single_section_table(reader);
+                        let mut last_pmt = None;
+                        let pid = header.pid;
+                        for tables in
+                            track!(self.collect_tables(pid, pusi, reader, &[Pmt::TABLE_ID]))?
+                        {
+                            let pmt = track!(Pmt::from_tables(tables))?;
+                            self.programs.apply_pmt(pid, &pmt, &mut self.program_events);
+                            if let Some(ref mut events) = self.table_events {
+                                events.push(TableEvent::Pmt {
+                                    pid,
+                                    pmt: pmt.clone(),
+                                });
+                            }
+                            last_pmt = Some(pmt);
+                        }
+                        match last_pmt {
+                            Some(pmt) if is_single_section => {
+                                reader = &[];
                                 TsPayload::Pmt(pmt)
                             }
-                            PidKind::Pes => {
-                                if payload_unit_start_indicator {
-                                    let pes = track!(Pes::read_from(&mut reader))?;
-                                    TsPayload::Pes(pes)
-                                } else {
-                                    let bytes = track!(Bytes::read_from(&mut reader))?;
-                                    TsPayload::Raw(bytes)
-                                }
+                            _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
+                        }
+                    }
+                    Some(PidKind::Nit) => track!(self.decode_nit(header.pid, pusi, &mut reader))?,
+                    Some(PidKind::Emm { .. }) | Some(PidKind::Ecm { .. }) => {
+                        // The sections of the CA systems are not decoded
+                        TsPayload::Raw(track!(Bytes::read_from(&mut reader))?)
+                    }
+                    Some(PidKind::Scte35) => {
+                        let is_single_section = section::is_single_section_table(reader);
+                        let mut last_section = None;
+                        let pid = header.pid;
+                        for section in track!(self.collect_splice_sections(pid, pusi, reader))? {
+                            if let Some(ref mut events) = self.table_events {
+                                events.push(TableEvent::SpliceInfo {
+                                    pid,
+                                    section: section.clone(),
+                                });
+                            }
+                            last_section = Some(section);
+                        }
+                        match last_section {
+                            Some(section) if is_single_section => {
+                                reader = &[];
+                                TsPayload::Scte35(section)
                             }
+                            _ => TsPayload::Raw(track!(Bytes::read_from(&mut reader))?),
                         }
                     }
-                }
+                    Some(PidKind::Pes) => {
+                        if pusi {
+                            let pes = track!(Pes::read_from(&mut reader))?;
+                            TsPayload::Pes(pes)
+                        } else {
+                            let bytes = track!(Bytes::read_from(&mut reader))?;
+                            TsPayload::Raw(bytes)
+                        }
+                    }
+                },
             };
+            track_assert!(reader.is_empty(), ErrorKind::InvalidInput);
             Some(payload)
         } else {
             None
         };
 
-        track_assert_eq!(reader.limit(), 0, ErrorKind::InvalidInput);
-        Ok(Some(TsPacket {
+        let packet = TsPacket {
             header,
             adaptation_field,
             payload,
-        }))
+            tp_extra_header,
+            position: Some(PacketPosition {
+                offset: self.sync.packet_offset(),
+                index: self.sync.packet_index(),
+            }),
+        };
+        let continuity_error = self.continuity.check(&packet);
+        self.continuity_error = continuity_error.map(|(kind, _)| kind);
+        if let Some((kind, expected)) = continuity_error {
+            let event = ContinuityEvent {
+                kind,
+                pid: packet.header.pid,
+                expected,
+                actual: packet.header.continuity_counter,
+                offset: self.sync.packet_offset(),
+            };
+            log::debug!("Continuity error: {:?}", event);
+            self.continuity_events.push(event);
+        }
+        Ok(packet)
     }
 
-    fn get_next_available_packet(&mut self) -> Option<TsPacket> {
-        let mut next_packet = None;
+    /// Decodes the payload of a packet that carries NIT sections.
+    fn decode_nit(&mut self, pid: Pid, pusi: bool, reader: &mut &[u8]) -> Result<TsPayload> {
+        let is_single_section = section::is_single_section_table(reader);
+        let mut last_nit = None;
+        let table_ids = [Nit::ACTUAL_TABLE_ID, Nit::OTHER_TABLE_ID];
+        for tables in track!(self.collect_tables(pid, pusi, reader, &table_ids))? {
+            let nit = track!(Nit::from_tables(tables))?;
+            if nit.actual {
+                self.programs.apply_nit(&nit);
+            }
+            if let Some(ref mut events) = self.table_events {
+                events.push(TableEvent::Nit {
+                    pid,
+                    nit: nit.clone(),
+                });
+            }
+            last_nit = Some(nit);
+        }
+        match last_nit {
+            Some(nit) if is_single_section => {
+                *reader = &[];
+                Ok(TsPayload::Nit(nit))
+            }
+            _ => Ok(TsPayload::Raw(track!(Bytes::read_from(reader))?)),
+        }
+    }
+
+    /// Collects the sections carried by the packets of `pid` and returns the completed tables.
+    ///
+    /// The sections that have a table identifier not included in `table_ids` are ignored.
+    fn collect_tables(
+        &mut self,
+        pid: Pid,
+        pusi: bool,
+        payload: &[u8],
+        table_ids: &[u8],
+    ) -> Result<Vec<Vec<PsiTable>>> {
+        let sections = track!(self.sections.entry(pid).or_default().push(pusi, payload))?;
+        let collector = self.tables.entry(pid).or_default();
+        let mut tables = Vec::new();
+        for section in sections {
+            let table = track!(PsiTable::read_section(&section))?;
+            if !table_ids.contains(&table.header.table_id) {
+                log::debug!(
+                    "Ignored a section: pid={}, table_id={}",
+                    pid.as_u16(),
+                    table.header.table_id
+                );
+                continue;
+            }
+            tables.extend(track!(collector.push(table))?);
+        }
+        Ok(tables)
+    }
+
+    /// Collects the SCTE-35 sections carried by the packets of `pid`.
+    fn collect_splice_sections(
+        &mut self,
+        pid: Pid,
+        pusi: bool,
+        payload: &[u8],
+    ) -> Result<Vec<SpliceInfoSection>> {
+        let sections = track!(self.sections.entry(pid).or_default().push(pusi, payload))?;
+        let mut splice_sections = Vec::new();
+        for section in sections {
+            if section[0] != SpliceInfoSection::TABLE_ID {
+                log::debug!(
+                    "Ignored a section: pid={}, table_id={}",
+                    pid.as_u16(),
+                    section[0]
+                );
+                continue;
+            }
+            if SpliceInfoSection::is_encrypted_section(&section) {
+                log::debug!(
+                    "Kept an encrypted splice_info_section as raw bytes: pid={}",
+                    pid.as_u16()
+                );
+                continue;
+            }
+            splice_sections.push(track!(SpliceInfoSection::read_section(&section))?);
+        }
+        Ok(splice_sections)
+    }
 
+    fn discard_unused_sections(&mut self) {
+        let programs = &self.programs;
+        self.sections.retain(|pid, _| is_psi_pid(programs, *pid));
+        self.tables.retain(|pid, _| is_psi_pid(programs, *pid));
+    }
+}
+impl<R: Read> TsPacketReader<R> {
+    fn read_next_packet(&mut self) -> Result<Option<TsPacket>> {
+        let mut buf = [0; READ_BUF_SIZE];
         loop {
-            match self.read_next_packet() {
-                Ok(p) => {
-                    next_packet = p;
-                    break;
-                }
-                Err(e) => {
-                    log::trace!("Dropped packet: {:?}", e);
-                }
+            if let Some(packet) = track!(self.decode_next_packet())? {
+                return Ok(Some(packet));
+            }
+            if self.eos {
+                return Ok(None);
             }
+            let size = track_io!(self.stream.read(&mut buf))?;
+            self.push_bytes(&buf[..size]);
         }
+    }
+}
+impl<R: Read + Seek> TsPacketReader<R> {
+    /// Seeks to the packet at the given byte offset of the stream.
+    ///
+    /// The offset is rounded down to a multiple of the packet size.
+    /// If the stream is not aligned to the packet size,
+    /// the reader resynchronizes itself at the next packet boundary.
+    ///
+    /// The program map is kept across the jump, while the partially read PSI sections and
+    /// the continuity counters are discarded.
+    /// Note that the packet indices after the jump are estimated from the offset.
+    ///
+    /// It returns the rounded offset.
+    pub fn seek_to_offset(&mut self, offset: u64) -> Result<u64> {
+        let offset = offset - offset % self.format.packet_size() as u64;
+        track!(self.seek_to_packet(offset))?;
+        Ok(offset)
+    }
+
+    /// Seeks to the random access point preceding the given presentation time.
+    ///
+    /// The stream is binary-searched on the timestamps of `clock`,
+    /// then the reader backs up to the previous packet of `pid`
+    /// that has the `random_access_indicator` set.
+    /// If no random access point is found within 64 MiB,
+    /// the reader seeks to the packet found by the binary search.
+    ///
+    /// `SeekClock::Pts` searches on the PTSs of `pid`.
+    /// `SeekClock::Pcr` searches on the PCRs of the given PID (usually the PCR PID of the program),
+    /// which suits the streams whose PTSs are sparse;
+    /// `timestamp` is mapped to the PCR by the delay measured at the start of the stream.
+    ///
+    /// The timestamps are assumed to increase monotonically in the stream except for
+    /// the wraparounds of the 33-bit clock.
+    /// `timestamp` is located relative to the first timestamp of the stream:
+    /// a timestamp that falls outside of the stream is clamped to the nearer end of the stream.
+    /// The stream must be shorter than the period of the wraparound (about 26.5 hours).
+    /// A longer stream wraps more than once, so the same timestamp occurs in several places
+    /// and the position found in it is unspecified;
+    /// in that case, split the stream into shorter byte ranges.
+    /// Discontinuities are not taken into account.
+    ///
+    /// It returns the byte offset of the packet to be read next.
+    /// See `seek_to_offset()` for the state kept across the jump.
+    pub fn seek_to_timestamp(
+        &mut self,
+        pid: Pid,
+        timestamp: Timestamp,
+        clock: SeekClock,
+    ) -> Result<u64> {
+        let format = self.format;
+        let len = track_io!(self.stream.seek(SeekFrom::End(0)))?;
+        let timeline = seek::Timeline::new(pid, clock);
+
+        // The timestamps are unwrapped relative to the first one
+        let first = track!(seek::first_timestamp(&mut self.stream, format, timeline, 0, len))?;
+        let base = match first {
+            None => return track!(self.seek_to_offset(0)),
+            Some((_, t)) => t,
+        };
+        let last = track!(seek::last_timestamp(&mut self.stream, format, timeline, 0, len))?;
+        let timestamp = match clock {
+            SeekClock::Pts => timestamp.as_u64(),
+            SeekClock::Pcr(pcr_pid) => {
+                let delay = track!(seek::pts_delay(&mut self.stream, format, pid, pcr_pid, len))?;
+                (timestamp.as_u64() + seek::TIMESTAMP_CYCLE - delay) % seek::TIMESTAMP_CYCLE
+            }
+        };
+        let span = seek::elapsed(base, last.map_or(base, |(_, t)| t));
+        let target = match seek::elapsed(base, timestamp) {
+            t if t <= span => t,
+            t if t - span <= (seek::TIMESTAMP_CYCLE - span) / 2 => span,
+            _ => 0,
+        };
+        let is_before_target = |t: u64| seek::elapsed(base, t) <= target;
+
+        let mut lo = 0;
+        let mut hi = len;
+        while hi - lo > seek::PROBE_SIZE {
+            let mid = lo + (hi - lo) / 2;
+            match track!(seek::first_timestamp(&mut self.stream, format, timeline, mid, hi))? {
+                Some((_, t)) if is_before_target(t) => lo = mid,
+                _ => hi = mid,
+            }
+        }
+
+        let end = cmp::min(hi + seek::PROBE_SIZE, len);
+        let points = track!(seek::scan(&mut self.stream, format, &[timeline.pid()], lo, end))?;
+        let found = match points
+            .iter()
+            .rev()
+            .find(|p| matches!(timeline.timestamp(p), Some(t) if is_before_target(t)))
+        {
+            None => return track!(self.seek_to_offset(lo)),
+            Some(p) => p.offset,
+        };
 
-        next_packet
+        let mut end = found + 1;
+        loop {
+            let start = end.saturating_sub(seek::PROBE_SIZE);
+            let points = track!(seek::scan(&mut self.stream, format, &[pid], start, end))?;
+            if let Some(p) = points.iter().rev().find(|p| p.is_random_access) {
+                track!(self.seek_to_packet(p.offset))?;
+                return Ok(p.offset);
+            }
+            if start == 0 || found - start >= seek::MAX_BACKOFF {
+                break;
+            }
+            end = start;
+        }
+        track!(self.seek_to_packet(found))?;
+        Ok(found)
+    }
+
+    fn seek_to_packet(&mut self, offset: u64) -> Result<()> {
+        track_io!(self.stream.seek(SeekFrom::Start(offset)))?;
+        let index = offset / self.format.packet_size() as u64;
+        self.reset_position(PacketPosition { offset, index });
+        Ok(())
     }
 }
 impl<R: Read> ReadTsPacket for TsPacketReader<R> {
     fn peek_ts_packet(&mut self) -> Option<&TsPacket> {
-        if self.peeked_packet.is_none() {
-            let next_packet = self.get_next_available_packet();
-            self.peeked_packet = next_packet;
+        match self.try_peek_ts_packet() {
+            Ok(packet) => packet,
+            Err(e) => {
+                log::debug!("Failed to peek a packet: {}", e);
+                None
+            }
         }
+    }
 
-        self.peeked_packet.as_ref()
+    fn try_peek_ts_packet(&mut self) -> Result<Option<&TsPacket>> {
+        if self.peeked_packet.is_none() {
+            if let Some(packet) = track!(self.read_next_packet())? {
+                self.peeked_packet = Some((packet, self.continuity_error));
+            }
+        }
+        Ok(self.peeked_packet.as_ref().map(|(packet, _)| packet))
     }
 
     fn read_ts_packet(&mut self) -> Result<Option<TsPacket>> {
-        return if self.peeked_packet.is_some() {
-            let packet = self.peeked_packet.to_owned();
-            self.peeked_packet = None;
-            Ok(packet)
+        if let Some((packet, continuity_error)) = self.peeked_packet.take() {
+            self.continuity_error = continuity_error;
+            Ok(Some(packet))
         } else {
-            //TODO: This is currently a bit of a hack
-            Ok(self.get_next_available_packet())
-        };
+            track!(self.read_next_packet())
+        }
+    }
+
+    fn last_continuity_error(&self) -> Option<ContinuityEventKind> {
+        self.continuity_error
     }
 }
+#[cfg(feature = "tokio")]
+impl<R: AsyncRead + Unpin> AsyncReadTsPacket for TsPacketReader<R> {
+    fn poll_read_ts_packet(&mut self, cx: &mut Context) -> Poll<Result<Option<TsPacket>>> {
+        if let Some((packet, continuity_error)) = self.peeked_packet.take() {
+            self.continuity_error = continuity_error;
+            return Poll::Ready(Ok(Some(packet)));
+        }
 
-#[derive(Debug, Clone)]
-enum PidKind {
-    Pmt,
-    Pes,
+        let mut buf = [0; READ_BUF_SIZE];
+        loop {
+            if let Some(packet) = track!(self.decode_next_packet())? {
+                return Poll::Ready(Ok(Some(packet)));
+            }
+            if self.eos {
+                return Poll::Ready(Ok(None));
+            }
+            let mut read_buf = ReadBuf::new(&mut buf);
+            match Pin::new(&mut self.stream).poll_read(cx, &mut read_buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => track_io!(result)?,
+            }
+            self.push_bytes(read_buf.filled());
+        }
+    }
+
+    fn last_continuity_error(&self) -> Option<ContinuityEventKind> {
+        self.continuity_error
+    }
+}
+
+/// Returns `true` if the packets of `pid` carry the PSI sections that the reader decodes.
+fn is_psi_pid(programs: &ProgramMap, pid: Pid) -> bool {
+    matches!(pid.as_u16(), Pid::PAT | Pid::CAT | Pid::NIT | Pid::SDT)
+        || matches!(
+            programs.pid_kind(pid),
+            Some(PidKind::Pmt) | Some(PidKind::Nit) | Some(PidKind::Scte35)
+        )
 }
@@ -0,0 +1,265 @@
+//! Reed-Solomon RS(204,188) code used by DVB.
+//!
+//! It is a shortened RS(255,239) code over GF(2^8) that is defined by
+//! the field generator polynomial `x^8 + x^4 + x^3 + x^2 + 1` and
+//! the code generator polynomial `(x + a^0)(x + a^1)...(x + a^15)` (where `a = 0x02`).
+//!
+//! # References
+//!
+//! - ETSI EN 300 421 (4.4.2 Reed-Solomon (RS) coding)
+use ts::TsPacket;
+
+const FIELD_GENERATOR: u16 = 0x11D;
+
+/// Number of parity bytes.
+pub const PARITY_SIZE: usize = 16;
+
+/// Size of a codeword (i.e., a TS packet and its parity bytes).
+pub const CODEWORD_SIZE: usize = TsPacket::SIZE + PARITY_SIZE;
+
+#[derive(Clone)]
+pub struct ReedSolomon {
+    exp: [u8; 512],
+    log: [u8; 256],
+    generator: [u8; PARITY_SIZE + 1],
+}
+impl ReedSolomon {
+    pub fn new() -> Self {
+        let mut exp = [0; 512];
+        let mut log = [0; 256];
+        let mut x: u16 = 1;
+        for (i, e) in exp.iter_mut().take(255).enumerate() {
+            *e = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= FIELD_GENERATOR;
+            }
+        }
+        for i in 255..512 {
+            exp[i] = exp[i - 255];
+        }
+
+        let mut rs = ReedSolomon {
+            exp,
+            log,
+            generator: [0; PARITY_SIZE + 1],
+        };
+
+        // The coefficients are stored in descending order of degree.
+        let mut generator = vec![1];
+        for i in 0..PARITY_SIZE {
+            let root = rs.exp[i];
+            let mut next = vec![0; generator.len() + 1];
+            for (j, &c) in generator.iter().enumerate() {
+                next[j] ^= c;
+                next[j + 1] ^= rs.mul(c, root);
+            }
+            generator = next;
+        }
+        rs.generator.copy_from_slice(&generator);
+        rs
+    }
+
+    /// Calculates the parity bytes of the given TS packet.
+    pub fn encode(&self, packet: &[u8]) -> [u8; PARITY_SIZE] {
+        debug_assert_eq!(packet.len(), TsPacket::SIZE);
+        let mut parity = [0; PARITY_SIZE];
+        for &b in packet {
+            let feedback = b ^ parity[0];
+            for j in 0..PARITY_SIZE - 1 {
+                parity[j] = parity[j + 1] ^ self.mul(feedback, self.generator[j + 1]);
+            }
+            parity[PARITY_SIZE - 1] = self.mul(feedback, self.generator[PARITY_SIZE]);
+        }
+        parity
+    }
+
+    /// Corrects the errors in the given codeword.
+    ///
+    /// It returns the number of the corrected bytes,
+    /// or `None` if the codeword contains more errors than the code can correct.
+    pub fn decode(&self, codeword: &mut [u8]) -> Option<usize> {
+        debug_assert_eq!(codeword.len(), CODEWORD_SIZE);
+
+        let syndromes = self.syndromes(codeword);
+        if syndromes.iter().all(|&s| s == 0) {
+            return Some(0);
+        }
+
+        // Berlekamp-Massey algorithm (the coefficients are stored in ascending order of degree).
+        let mut locator = [0; PARITY_SIZE + 1];
+        let mut prev = [0; PARITY_SIZE + 1];
+        locator[0] = 1;
+        prev[0] = 1;
+        let mut errors = 0;
+        let mut shift = 1;
+        let mut prev_discrepancy = 1;
+        for n in 0..PARITY_SIZE {
+            let mut discrepancy = syndromes[n];
+            for i in 1..=errors {
+                discrepancy ^= self.mul(locator[i], syndromes[n - i]);
+            }
+            if discrepancy == 0 {
+                shift += 1;
+                continue;
+            }
+
+            let coef = self.div(discrepancy, prev_discrepancy);
+            let saved = locator;
+            for i in 0..(PARITY_SIZE + 1).saturating_sub(shift) {
+                locator[i + shift] ^= self.mul(coef, prev[i]);
+            }
+            if 2 * errors <= n {
+                errors = n + 1 - errors;
+                prev = saved;
+                prev_discrepancy = discrepancy;
+                shift = 1;
+            } else {
+                shift += 1;
+            }
+        }
+        if errors > PARITY_SIZE / 2 {
+            return None;
+        }
+
+        // Error evaluator: syndromes(x) * locator(x) mod x^PARITY_SIZE
+        let mut evaluator = [0; PARITY_SIZE];
+        for i in 0..PARITY_SIZE {
+            for j in 0..=i.min(errors) {
+                evaluator[i] ^= self.mul(syndromes[i - j], locator[j]);
+            }
+        }
+
+        // Chien search and Forney algorithm.
+        let mut positions = Vec::with_capacity(errors);
+        let mut magnitudes = Vec::with_capacity(errors);
+        for position in 0..CODEWORD_SIZE {
+            let power = (CODEWORD_SIZE - 1 - position) % 255;
+            let x_inv = self.exp[(255 - power) % 255];
+            if self.eval(&locator[..=errors], x_inv) != 0 {
+                continue;
+            }
+
+            let mut derivative = 0;
+            let mut x_inv_pow = 1;
+            for i in (1..=errors).step_by(2) {
+                derivative ^= self.mul(locator[i], x_inv_pow);
+                x_inv_pow = self.mul(x_inv_pow, self.mul(x_inv, x_inv));
+            }
+            if derivative == 0 {
+                return None;
+            }
+            let numerator = self.mul(self.exp[power], self.eval(&evaluator, x_inv));
+            positions.push(position);
+            magnitudes.push(self.div(numerator, derivative));
+        }
+        if positions.len() != errors {
+            return None;
+        }
+
+        for (&position, &magnitude) in positions.iter().zip(magnitudes.iter()) {
+            codeword[position] ^= magnitude;
+        }
+        if self.syndromes(codeword).iter().any(|&s| s != 0) {
+            for (&position, &magnitude) in positions.iter().zip(magnitudes.iter()) {
+                codeword[position] ^= magnitude;
+            }
+            return None;
+        }
+        Some(errors)
+    }
+
+    fn syndromes(&self, codeword: &[u8]) -> [u8; PARITY_SIZE] {
+        let mut syndromes = [0; PARITY_SIZE];
+        for (i, s) in syndromes.iter_mut().enumerate() {
+            let root = self.exp[i];
+            *s = codeword.iter().fold(0, |acc, &c| self.mul(acc, root) ^ c);
+        }
+        syndromes
+    }
+
+    /// Evaluates the polynomial whose coefficients are stored in ascending order of degree.
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        debug_assert_ne!(b, 0);
+        if a == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize]
+        }
+    }
+}
+impl ::std::fmt::Debug for ReedSolomon {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "ReedSolomon {{ generator: {:?} }}", self.generator)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn codeword(rs: &ReedSolomon) -> Vec<u8> {
+        let mut codeword = (0..TsPacket::SIZE)
+            .map(|i| (i * 7 + 3) as u8)
+            .collect::<Vec<_>>();
+        codeword[0] = TsPacket::SYNC_BYTE;
+        let parity = rs.encode(&codeword);
+        codeword.extend_from_slice(&parity);
+        codeword
+    }
+
+    #[test]
+    fn no_errors() {
+        let rs = ReedSolomon::new();
+        let original = codeword(&rs);
+        let mut received = original.clone();
+        assert_eq!(rs.decode(&mut received), Some(0));
+        assert_eq!(received, original);
+    }
+
+    #[test]
+    fn correctable_errors() {
+        let rs = ReedSolomon::new();
+        let original = codeword(&rs);
+        for errors in 1..=8 {
+            let mut received = original.clone();
+            for i in 0..errors {
+                received[i * 25 + 1] ^= (i + 1) as u8;
+            }
+            assert_eq!(rs.decode(&mut received), Some(errors));
+            assert_eq!(received, original);
+        }
+
+        let mut received = original.clone();
+        received[CODEWORD_SIZE - 1] ^= 0xFF;
+        received[0] ^= 0xFF;
+        assert_eq!(rs.decode(&mut received), Some(2));
+        assert_eq!(received, original);
+    }
+
+    #[test]
+    fn uncorrectable_errors() {
+        let rs = ReedSolomon::new();
+        let original = codeword(&rs);
+        let mut received = original.clone();
+        for i in 0..12 {
+            received[i * 16 + 2] ^= 0x5A;
+        }
+        let corrupted = received.clone();
+        assert_eq!(rs.decode(&mut received), None);
+        assert_eq!(received, corrupted);
+    }
+}
@@ -0,0 +1,291 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+use ts::descriptor::ServiceDescriptor;
+use ts::psi::{Psi, PsiTable, PsiTableHeader};
+use ts::{Descriptor, VersionNumber};
+use {ErrorKind, Result};
+
+/// Payload for SDT(Service Description Table) packets.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Sdt {
+    /// `true` if the table describes the actual transport stream (`table_id` is `0x42`),
+    /// `false` if it describes another transport stream (`table_id` is `0x46`).
+    pub actual: bool,
+
+    pub transport_stream_id: u16,
+    pub version_number: VersionNumber,
+    pub original_network_id: u16,
+    pub services: Vec<ServiceInfo>,
+}
+impl Sdt {
+    pub(super) const ACTUAL_TABLE_ID: u8 = 0x42;
+    pub(super) const OTHER_TABLE_ID: u8 = 0x46;
+
+    /// Returns the service that has the given identifier (i.e., program number).
+    pub fn service(&self, service_id: u16) -> Option<&ServiceInfo> {
+        self.services.iter().find(|s| s.service_id == service_id)
+    }
+
+    pub(super) fn read_from<R: Read>(reader: R) -> Result<Self> {
+        let psi = track!(Psi::read_from(reader))?;
+        track!(Self::from_tables(psi.tables))
+    }
+
+    /// Makes a `Sdt` instance from all the sections of a table.
+    pub(super) fn from_tables(tables: Vec<PsiTable>) -> Result<Self> {
+        track_assert!(!tables.is_empty(), ErrorKind::InvalidInput);
+        let last_section_number = tables.len() - 1;
+
+        let mut sdt: Option<Sdt> = None;
+        for (section_number, table) in tables.into_iter().enumerate() {
+            let header = table.header;
+            let actual = match header.table_id {
+                Self::ACTUAL_TABLE_ID => true,
+                Self::OTHER_TABLE_ID => false,
+                _ => track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Unexpected table_id: {}",
+                    header.table_id
+                ),
+            };
+
+            let syntax = track_assert_some!(table.syntax.as_ref(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.section_number as usize,
+                section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.last_section_number as usize,
+                last_section_number,
+                ErrorKind::InvalidInput
+            );
+            track_assert!(syntax.current_next_indicator, ErrorKind::InvalidInput);
+
+            let mut reader = &syntax.table_data[..];
+            let original_network_id = track_io!(reader.read_u16::<BigEndian>())?;
+            let _reserved = track_io!(reader.read_u8())?;
+
+            let sdt = sdt.get_or_insert_with(|| Sdt {
+                actual,
+                transport_stream_id: syntax.table_id_extension,
+                version_number: syntax.version_number,
+                original_network_id,
+                services: Vec::new(),
+            });
+            track_assert_eq!(sdt.actual, actual, ErrorKind::InvalidInput);
+            track_assert_eq!(
+                syntax.table_id_extension,
+                sdt.transport_stream_id,
+                ErrorKind::InvalidInput
+            );
+            track_assert_eq!(
+                syntax.version_number,
+                sdt.version_number,
+                ErrorKind::InvalidInput
+            );
+
+            while !reader.is_empty() {
+                sdt.services
+                    .push(track!(ServiceInfo::read_from(&mut reader))?);
+            }
+        }
+        Ok(sdt.expect("Never fails"))
+    }
+
+    pub(super) fn write_to<W: Write>(&self, writer: W) -> Result<()> {
+        track!(self.to_psi().and_then(|psi| psi.write_to(writer)))
+    }
+
+    fn to_psi(&self) -> Result<Psi> {
+        let mut common_data = Vec::with_capacity(3);
+        track_io!(common_data.write_u16::<BigEndian>(self.original_network_id))?;
+        track_io!(common_data.write_u8(0xFF))?; // reserved
+
+        let mut entries = Vec::with_capacity(self.services.len());
+        for s in &self.services {
+            let mut entry = Vec::new();
+            track!(s.write_to(&mut entry))?;
+            entries.push(entry);
+        }
+
+        let header = PsiTableHeader {
+            table_id: if self.actual {
+                Self::ACTUAL_TABLE_ID
+            } else {
+                Self::OTHER_TABLE_ID
+            },
+            private_bit: true, // reserved_future_use
+        };
+        let tables = track!(PsiTable::split(
+            &header,
+            self.transport_stream_id,
+            self.version_number,
+            &common_data,
+            &entries
+        ))?;
+        Ok(Psi { tables })
+    }
+}
+
+/// Service information.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ServiceInfo {
+    /// The identifier of the service (i.e., the program number in the PAT).
+    pub service_id: u16,
+
+    pub eit_schedule_flag: bool,
+    pub eit_present_following_flag: bool,
+    pub running_status: RunningStatus,
+
+    /// If `true`, some components of the service are scrambled.
+    pub free_ca_mode: bool,
+
+    pub descriptors: Vec<Descriptor>,
+}
+impl ServiceInfo {
+    /// Returns the service descriptor of the service.
+    ///
+    /// If the service has no (or malformed) service descriptor, it will return `None`.
+    pub fn service_descriptor(&self) -> Option<ServiceDescriptor> {
+        self.descriptors
+            .iter()
+            .filter(|d| d.tag == ServiceDescriptor::TAG)
+            .filter_map(|d| ServiceDescriptor::from_descriptor(d).ok())
+            .next()
+    }
+
+    fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let service_id = track_io!(reader.read_u16::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        let eit_schedule_flag = (b & 0b0000_0010) != 0;
+        let eit_present_following_flag = (b & 0b0000_0001) != 0;
+
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        let running_status = RunningStatus::from_u8((n >> 13) as u8);
+        let free_ca_mode = (n & 0b0001_0000_0000_0000) != 0;
+        let descriptors_loop_len = n & 0b0000_1111_1111_1111;
+        let mut reader = reader.take(u64::from(descriptors_loop_len));
+        let mut descriptors = Vec::new();
+        while reader.limit() > 0 {
+            let d = track!(Descriptor::read_from(&mut reader))?;
+            descriptors.push(d);
+        }
+
+        Ok(ServiceInfo {
+            service_id,
+            eit_schedule_flag,
+            eit_present_following_flag,
+            running_status,
+            free_ca_mode,
+            descriptors,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u16::<BigEndian>(self.service_id))?;
+        let b = 0b1111_1100
+            | ((self.eit_schedule_flag as u8) << 1)
+            | self.eit_present_following_flag as u8;
+        track_io!(writer.write_u8(b))?;
+
+        let descriptors_loop_len: usize = self.descriptors.iter().map(|d| 2 + d.data.len()).sum();
+        track_assert!(
+            descriptors_loop_len <= 0b0000_1111_1111_1111,
+            ErrorKind::InvalidInput
+        );
+        let n = (u16::from(self.running_status.as_u8()) << 13)
+            | ((self.free_ca_mode as u16) << 12)
+            | descriptors_loop_len as u16;
+        track_io!(writer.write_u16::<BigEndian>(n))?;
+        for d in &self.descriptors {
+            track!(d.write_to(&mut writer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Running status of a service.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RunningStatus {
+    Undefined,
+    NotRunning,
+    StartsInAFewSeconds,
+    Pausing,
+    Running,
+    ServiceOffAir,
+
+    /// Reserved value (`6` or `7`).
+    Reserved(u8),
+}
+impl RunningStatus {
+    /// Makes a `RunningStatus` instance from the lower 3 bits of the given number.
+    pub fn from_u8(n: u8) -> Self {
+        match n & 0b111 {
+            0 => RunningStatus::Undefined,
+            1 => RunningStatus::NotRunning,
+            2 => RunningStatus::StartsInAFewSeconds,
+            3 => RunningStatus::Pausing,
+            4 => RunningStatus::Running,
+            5 => RunningStatus::ServiceOffAir,
+            n => RunningStatus::Reserved(n),
+        }
+    }
+
+    /// Returns the number associated with the running status.
+    pub fn as_u8(&self) -> u8 {
+        match *self {
+            RunningStatus::Undefined => 0,
+            RunningStatus::NotRunning => 1,
+            RunningStatus::StartsInAFewSeconds => 2,
+            RunningStatus::Pausing => 3,
+            RunningStatus::Running => 4,
+            RunningStatus::ServiceOffAir => 5,
+            RunningStatus::Reserved(n) => n & 0b111,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ts::DvbText;
+
+    #[test]
+    fn sdt() {
+        let service = ServiceDescriptor {
+            service_type: 1,
+            service_provider_name: DvbText::encode("Provider"),
+            service_name: DvbText::encode("Télé"),
+        };
+        let sdt = Sdt {
+            actual: false,
+            transport_stream_id: 2,
+            version_number: VersionNumber::from_u8(4).unwrap(),
+            original_network_id: 0x233A,
+            services: vec![ServiceInfo {
+                service_id: 0x1234,
+                eit_schedule_flag: true,
+                eit_present_following_flag: false,
+                running_status: RunningStatus::Pausing,
+                free_ca_mode: true,
+                descriptors: vec![service.to_descriptor().unwrap()],
+            }],
+        };
+        let mut buf = Vec::new();
+        sdt.write_to(&mut buf).unwrap();
+        let decoded = Sdt::read_from(&buf[..]).unwrap();
+        assert_eq!(decoded, sdt);
+
+        let service = decoded
+            .service(0x1234)
+            .unwrap()
+            .service_descriptor()
+            .unwrap();
+        assert_eq!(service.service_name.decode().unwrap(), "Télé");
+    }
+}
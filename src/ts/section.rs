@@ -0,0 +1,349 @@
+use std::collections::HashMap;
+use std::mem;
+
+use ts::psi::PsiTable;
+use ts::VersionNumber;
+use {ErrorKind, Result};
+
+/// Maximum size of a section (including the three header bytes).
+const MAX_SECTION_SIZE: usize = 4096;
+
+/// Reassembler of the sections carried by the TS packets that have the same PID.
+///
+/// It handles the `pointer_field`, the sections spanning multiple packets and
+/// the multiple sections packed into a packet.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SectionAssembler {
+    buf: Vec<u8>,
+    is_assembling: bool,
+}
+impl SectionAssembler {
+    /// Discards the incomplete section.
+    pub fn reset(&mut self) {
+        self.buf.clear();
+        self.is_assembling = false;
+    }
+
+    /// Pushes the payload of a TS packet and returns the completed sections.
+    ///
+    /// Each returned section starts with the `table_id` byte and ends with the last byte
+    /// of the section (i.e., the CRC32 in the case of the long form sections).
+    pub fn push(
+        &mut self,
+        payload_unit_start_indicator: bool,
+        payload: &[u8],
+    ) -> Result<Vec<Vec<u8>>> {
+        let mut sections = Vec::new();
+        if !payload_unit_start_indicator {
+            if self.is_assembling {
+                self.buf.extend_from_slice(payload);
+                track!(self.take_sections(&mut sections))?;
+            }
+            return Ok(sections);
+        }
+
+        track_assert!(!payload.is_empty(), ErrorKind::InvalidInput);
+        let pointer_field = payload[0] as usize;
+        let payload = &payload[1..];
+        if pointer_field > payload.len() {
+            self.reset();
+            track_panic!(
+                ErrorKind::InvalidInput,
+                "Too large pointer field: {}",
+                pointer_field
+            );
+        }
+
+        if self.is_assembling {
+            self.buf.extend_from_slice(&payload[..pointer_field]);
+            track!(self.take_sections(&mut sections))?;
+            if self.is_assembling {
+                log::debug!("Discarded an incomplete section: {} bytes", self.buf.len());
+            }
+        }
+
+        self.buf.clear();
+        self.buf.extend_from_slice(&payload[pointer_field..]);
+        self.is_assembling = true;
+        track!(self.take_sections(&mut sections))?;
+        Ok(sections)
+    }
+
+    fn take_sections(&mut self, sections: &mut Vec<Vec<u8>>) -> Result<()> {
+        loop {
+            if self.buf.first() == Some(&0xFF) {
+                // The remaining bytes of the packet are stuffing bytes.
+                self.reset();
+                return Ok(());
+            }
+            if self.buf.len() < 3 {
+                self.is_assembling = !self.buf.is_empty();
+                return Ok(());
+            }
+
+            let section_len = section_len(&self.buf);
+            if section_len > MAX_SECTION_SIZE {
+                self.reset();
+                track_panic!(
+                    ErrorKind::InvalidInput,
+                    "Too large section: {} bytes",
+                    section_len
+                );
+            }
+            if self.buf.len() < section_len {
+                return Ok(());
+            }
+
+            let rest = self.buf.split_off(section_len);
+            sections.push(mem::replace(&mut self.buf, rest));
+        }
+    }
+}
+
+/// Collector of the sections that make up tables.
+///
+/// The sections are grouped by `table_id`, `table_id_extension` and `current_next_indicator`.
+/// If a section of a different version arrives, the sections collected so far are discarded.
+///
+/// A completed table whose `current_next_indicator` is `0` is kept as the next version,
+/// and it is returned when a section of the version with `current_next_indicator` `1` arrives.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct TableCollector {
+    tables: HashMap<(u8, u16, bool), PartialTable>,
+    next_tables: HashMap<(u8, u16), (VersionNumber, Vec<PsiTable>)>,
+}
+impl TableCollector {
+    /// Pushes a section and returns all the sections of the table if the table is completed.
+    ///
+    /// The returned sections are sorted by `section_number`,
+    /// and their `current_next_indicator` is always `1`.
+    pub fn push(&mut self, table: PsiTable) -> Result<Option<Vec<PsiTable>>> {
+        let (key, version_number, section_number, last_section_number) = match table.syntax {
+            None => return Ok(Some(vec![table])),
+            Some(ref x) => (
+                (
+                    table.header.table_id,
+                    x.table_id_extension,
+                    x.current_next_indicator,
+                ),
+                x.version_number,
+                x.section_number as usize,
+                x.last_section_number as usize,
+            ),
+        };
+        track_assert!(
+            section_number <= last_section_number,
+            ErrorKind::InvalidInput,
+            "section_number={}, last_section_number={}",
+            section_number,
+            last_section_number
+        );
+
+        let (table_id, table_id_extension, current_next_indicator) = key;
+        let next_key = (table_id, table_id_extension);
+        if current_next_indicator
+            && self.next_tables.get(&next_key).map(|t| t.0) == Some(version_number)
+        {
+            // The next version has become current
+            self.tables.remove(&key);
+            let (_, mut sections) = self.next_tables.remove(&next_key).expect("Never fails");
+            for s in sections.iter_mut().filter_map(|s| s.syntax.as_mut()) {
+                s.current_next_indicator = true;
+            }
+            return Ok(Some(sections));
+        }
+
+        let is_completed = {
+            let partial = self
+                .tables
+                .entry(key)
+                .or_insert_with(|| PartialTable::new(version_number, last_section_number));
+            if partial.version_number != version_number
+                || partial.sections.len() != last_section_number + 1
+            {
+                *partial = PartialTable::new(version_number, last_section_number);
+            }
+            partial.sections[section_number] = Some(table);
+            partial.sections.iter().all(Option::is_some)
+        };
+        if !is_completed {
+            return Ok(None);
+        }
+
+        let partial = self.tables.remove(&key).expect("Never fails");
+        let sections = partial
+            .sections
+            .into_iter()
+            .map(|s| s.expect("Never fails"))
+            .collect();
+        if !current_next_indicator {
+            self.next_tables
+                .insert(next_key, (version_number, sections));
+            return Ok(None);
+        }
+        Ok(Some(sections))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PartialTable {
+    version_number: VersionNumber,
+    sections: Vec<Option<PsiTable>>,
+}
+impl PartialTable {
+    fn new(version_number: VersionNumber, last_section_number: usize) -> Self {
+        PartialTable {
+            version_number,
+            sections: (0..=last_section_number).map(|_| None).collect(),
+        }
+    }
+}
+
+/// Returns `true` if the payload consists of a table that is carried by a single section and
+/// optional stuffing bytes.
+///
+/// The section must start at the beginning of the payload (i.e., `pointer_field` is `0`).
+pub(crate) fn is_single_section_table(payload: &[u8]) -> bool {
+    if payload.len() < 4 || payload[0] != 0 || payload[1] == 0xFF {
+        return false;
+    }
+    let section_len = section_len(&payload[1..]);
+    if section_len >= payload.len() || payload[1 + section_len..].iter().any(|&b| b != 0xFF) {
+        return false;
+    }
+
+    let has_syntax_section = (payload[2] & 0b1000_0000) != 0;
+    !has_syntax_section || (section_len >= 8 && payload[7] == 0 && payload[8] == 0)
+}
+
+fn section_len(bytes: &[u8]) -> usize {
+    3 + (((bytes[1] as usize) & 0b0000_1111) << 8 | bytes[2] as usize)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use ts::psi::{PsiTableHeader, PsiTableSyntax};
+
+    fn section(table_id: u8, data_len: usize) -> Vec<u8> {
+        let mut section = vec![table_id, 0xB0 | (data_len >> 8) as u8, data_len as u8];
+        section.extend((0..data_len).map(|_| 0));
+        section
+    }
+
+    fn payload(pusi_pointer: Option<u8>, bytes: &[u8]) -> Vec<u8> {
+        let mut payload = Vec::new();
+        payload.extend(pusi_pointer);
+        payload.extend_from_slice(bytes);
+        payload.resize(184, 0xFF);
+        payload
+    }
+
+    #[test]
+    fn single_packet_section() {
+        let s = section(0, 13);
+        let mut assembler = SectionAssembler::default();
+        let p = payload(Some(0), &s);
+        assert!(is_single_section_table(&p));
+        assert_eq!(assembler.push(true, &p).unwrap(), vec![s]);
+        assert!(!assembler.is_assembling);
+    }
+
+    #[test]
+    fn multi_packet_sections() {
+        let s0 = section(2, 300);
+        let s1 = section(2, 20);
+
+        let mut bytes = s0.clone();
+        bytes.extend_from_slice(&s1);
+        let mut assembler = SectionAssembler::default();
+
+        let p = payload(Some(0), &bytes[..183]);
+        assert!(!is_single_section_table(&p));
+        assert!(assembler.push(true, &p).unwrap().is_empty());
+        assert!(assembler.is_assembling);
+
+        // A packet without PUSI.
+        let p = payload(None, &bytes[183..183 + 100]);
+        assert!(assembler.push(false, &p[..100]).unwrap().is_empty());
+
+        // The second section starts in the middle of the packet.
+        let mut rest = bytes[283..].to_owned();
+        rest.extend_from_slice(&section(2, 5));
+        let pointer = (s0.len() - 283) as u8;
+        let p = payload(Some(pointer), &rest);
+        let sections = assembler.push(true, &p).unwrap();
+        assert_eq!(sections, vec![s0, s1, section(2, 5)]);
+        assert!(!assembler.is_assembling);
+    }
+
+    #[test]
+    fn packets_before_first_pusi_are_ignored() {
+        let mut assembler = SectionAssembler::default();
+        assert!(assembler.push(false, &[1, 2, 3]).unwrap().is_empty());
+        assert!(!assembler.is_assembling);
+
+        let p = payload(Some(3), &[1, 2, 3]);
+        assert!(assembler.push(true, &p).unwrap().is_empty());
+        assert!(assembler.push(true, &[200]).is_err());
+    }
+
+    fn next_table(version: u8, section_number: u8, last_section_number: u8) -> PsiTable {
+        let mut table = table(version, section_number, last_section_number);
+        table.syntax.as_mut().unwrap().current_next_indicator = false;
+        table
+    }
+
+    fn table(version: u8, section_number: u8, last_section_number: u8) -> PsiTable {
+        PsiTable {
+            header: PsiTableHeader {
+                table_id: 0,
+                private_bit: false,
+            },
+            syntax: Some(PsiTableSyntax {
+                table_id_extension: 1,
+                version_number: VersionNumber::from_u8(version).unwrap(),
+                current_next_indicator: true,
+                section_number,
+                last_section_number,
+                table_data: vec![section_number],
+            }),
+        }
+    }
+
+    fn section_numbers(tables: Vec<PsiTable>) -> Vec<u8> {
+        tables
+            .into_iter()
+            .map(|t| t.syntax.unwrap().section_number)
+            .collect()
+    }
+
+    #[test]
+    fn table_collector() {
+        let mut collector = TableCollector::default();
+        let tables = collector.push(table(0, 0, 0)).unwrap().unwrap();
+        assert_eq!(section_numbers(tables), [0]);
+
+        assert!(collector.push(table(0, 2, 2)).unwrap().is_none());
+        assert!(collector.push(table(0, 0, 2)).unwrap().is_none());
+        let tables = collector.push(table(0, 1, 2)).unwrap().unwrap();
+        assert_eq!(section_numbers(tables), [0, 1, 2]);
+
+        // A new version discards the sections of the old version.
+        assert!(collector.push(table(0, 0, 1)).unwrap().is_none());
+        assert!(collector.push(table(1, 1, 1)).unwrap().is_none());
+        assert!(collector.push(table(1, 0, 1)).unwrap().is_some());
+
+        assert!(collector.push(table(1, 2, 1)).is_err());
+
+        // The next version is returned when it becomes current
+        assert!(collector.push(next_table(2, 0, 1)).unwrap().is_none());
+        assert!(collector.push(next_table(2, 1, 1)).unwrap().is_none());
+        assert!(collector.push(table(1, 0, 0)).unwrap().is_some());
+        let tables = collector.push(table(2, 1, 1)).unwrap().unwrap();
+        assert_eq!(tables.len(), 2);
+        assert!(tables
+            .iter()
+            .all(|t| t.syntax.as_ref().unwrap().current_next_indicator));
+    }
+}
@@ -0,0 +1,237 @@
+use std::cmp;
+use std::io::{Read, Seek, SeekFrom};
+
+use pes::PesHeader;
+use ts::sync::PacketSynchronizer;
+use ts::{PacketFormat, Pid, TsPacket, TsPacketRef};
+use Result;
+
+/// Number of bytes scanned at each step of the timestamp search.
+pub(super) const PROBE_SIZE: u64 = TsPacket::SIZE as u64 * 1024;
+
+/// Maximum distance to back up from the found packet to a random access point.
+pub(super) const MAX_BACKOFF: u64 = 64 * 1024 * 1024;
+
+/// Period of the 33-bit PTS (and the base of the PCR).
+pub(super) const TIMESTAMP_CYCLE: u64 = 1 << 33;
+
+/// Returns the number of ticks from `base` to `timestamp` taking the wraparound into account.
+pub(super) fn elapsed(base: u64, timestamp: u64) -> u64 {
+    timestamp.wrapping_sub(base) % TIMESTAMP_CYCLE
+}
+
+/// Clock on which `TsPacketReader::seek_to_timestamp()` searches the stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SeekClock {
+    /// The PTS of the PES packets of the seeked PID.
+    Pts,
+
+    /// The base of the PCR carried by the given PID.
+    ///
+    /// The target PTS is mapped to the PCR by the difference between
+    /// the first PTS of the seeked PID and the PCR preceding it.
+    Pcr(Pid),
+}
+
+/// Timestamps of a single clock carried by a PID.
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Timeline {
+    Pts(Pid),
+    Pcr(Pid),
+}
+impl Timeline {
+    pub fn new(pid: Pid, clock: SeekClock) -> Self {
+        match clock {
+            SeekClock::Pts => Timeline::Pts(pid),
+            SeekClock::Pcr(pcr_pid) => Timeline::Pcr(pcr_pid),
+        }
+    }
+
+    pub fn pid(self) -> Pid {
+        match self {
+            Timeline::Pts(pid) | Timeline::Pcr(pid) => pid,
+        }
+    }
+
+    pub fn timestamp(self, point: &TimingPoint) -> Option<u64> {
+        match self {
+            Timeline::Pts(pid) if point.pid == pid => point.pts,
+            Timeline::Pcr(pid) if point.pid == pid => point.pcr,
+            _ => None,
+        }
+    }
+}
+
+/// Timing information of a packet found by `scan()`.
+#[derive(Debug, Clone, Copy)]
+pub(super) struct TimingPoint {
+    /// The absolute byte offset of the packet.
+    pub offset: u64,
+
+    /// The PID of the packet.
+    pub pid: Pid,
+
+    /// The PTS of the packet if it starts a PES packet.
+    pub pts: Option<u64>,
+
+    /// The base of the PCR of the packet.
+    pub pcr: Option<u64>,
+
+    /// The `random_access_indicator` of the adaptation field.
+    pub is_random_access: bool,
+}
+
+/// Scans the packets of `pids` that start in the byte range `[start, end)`.
+///
+/// It returns the packets that have a timestamp or a random access indicator.
+/// The packets that cannot be decoded are ignored.
+pub(super) fn scan<R: Read + Seek>(
+    stream: &mut R,
+    format: PacketFormat,
+    pids: &[Pid],
+    start: u64,
+    end: u64,
+) -> Result<Vec<TimingPoint>> {
+    let mut buf = Vec::new();
+    let limit = end.saturating_sub(start) + format.packet_size() as u64;
+    track_io!(stream.seek(SeekFrom::Start(start)))?;
+    track_io!(stream.take(limit).read_to_end(&mut buf))?;
+
+    let mut sync = PacketSynchronizer::new(format);
+    sync.reset(start, 0);
+    sync.push(&buf);
+
+    let mut points = Vec::new();
+    let sync_byte_offset = format.sync_byte_offset();
+    while sync.is_packet_ready(true) {
+        let mut bytes = [0; TsPacket::SIZE];
+        bytes.copy_from_slice(&sync.consume_packet()[sync_byte_offset..][..TsPacket::SIZE]);
+        let offset = sync.packet_offset();
+        if offset >= end {
+            break;
+        }
+        let packet = track!(TsPacketRef::new(&bytes))?;
+        if !pids.contains(&packet.pid()) {
+            continue;
+        }
+        if let Some(point) = timing_point(&packet, offset) {
+            points.push(point);
+        }
+    }
+    Ok(points)
+}
+
+/// Returns the offset and the timestamp of the first packet of `timeline`
+/// that starts in the byte range `[start, end)`.
+///
+/// The scanned window is doubled until a timestamp is found or `end` is reached.
+pub(super) fn first_timestamp<R: Read + Seek>(
+    stream: &mut R,
+    format: PacketFormat,
+    timeline: Timeline,
+    start: u64,
+    end: u64,
+) -> Result<Option<(u64, u64)>> {
+    let mut size = PROBE_SIZE;
+    let mut probe_start = start;
+    while probe_start < end {
+        let probe_end = cmp::min(probe_start.saturating_add(size), end);
+        let points = track!(scan(stream, format, &[timeline.pid()], probe_start, probe_end))?;
+        let found = points
+            .iter()
+            .filter_map(|p| timeline.timestamp(p).map(|t| (p.offset, t)))
+            .next();
+        if found.is_some() {
+            return Ok(found);
+        }
+        probe_start = probe_end;
+        size = size.saturating_mul(2);
+    }
+    Ok(None)
+}
+
+/// Returns the offset and the timestamp of the last packet of `timeline`
+/// that starts in the byte range `[start, end)`.
+///
+/// The scanned window is doubled until a timestamp is found or `start` is reached.
+pub(super) fn last_timestamp<R: Read + Seek>(
+    stream: &mut R,
+    format: PacketFormat,
+    timeline: Timeline,
+    start: u64,
+    end: u64,
+) -> Result<Option<(u64, u64)>> {
+    let mut size = PROBE_SIZE;
+    let mut probe_end = end;
+    while probe_end > start {
+        let probe_start = cmp::max(probe_end.saturating_sub(size), start);
+        let points = track!(scan(stream, format, &[timeline.pid()], probe_start, probe_end))?;
+        let found = points
+            .iter()
+            .rev()
+            .filter_map(|p| timeline.timestamp(p).map(|t| (p.offset, t)))
+            .next();
+        if found.is_some() {
+            return Ok(found);
+        }
+        probe_end = probe_start;
+        size = size.saturating_mul(2);
+    }
+    Ok(None)
+}
+
+/// Returns the number of ticks by which the PTSs of `pid` lead the PCRs of `pcr_pid`.
+///
+/// It compares the first PTS of the stream with the PCR preceding it
+/// (or following it if there is none), and returns `0` if either is missing.
+pub(super) fn pts_delay<R: Read + Seek>(
+    stream: &mut R,
+    format: PacketFormat,
+    pid: Pid,
+    pcr_pid: Pid,
+    len: u64,
+) -> Result<u64> {
+    let pts_timeline = Timeline::Pts(pid);
+    let pcr_timeline = Timeline::Pcr(pcr_pid);
+    let (offset, pts) = match track!(first_timestamp(stream, format, pts_timeline, 0, len))? {
+        None => return Ok(0),
+        Some(found) => found,
+    };
+    let mut pcr = track!(last_timestamp(stream, format, pcr_timeline, 0, offset + 1))?;
+    if pcr.is_none() {
+        pcr = track!(first_timestamp(stream, format, pcr_timeline, offset, len))?;
+    }
+    Ok(pcr.map_or(0, |(_, pcr)| elapsed(pcr, pts)))
+}
+
+fn timing_point(packet: &TsPacketRef, offset: u64) -> Option<TimingPoint> {
+    let adaptation_field = packet.adaptation_field().ok()?;
+    let is_random_access = matches!(adaptation_field, Some(ref a) if a.random_access_indicator);
+    let pcr = adaptation_field
+        .as_ref()
+        .and_then(|a| a.pcr)
+        .map(|pcr| pcr.as_u64() / 300);
+
+    let mut pts = None;
+    if packet.payload_unit_start_indicator() {
+        if let Some(payload) = packet.payload().ok()? {
+            if payload.starts_with(&[0, 0, 1]) {
+                pts = PesHeader::read_from(payload)
+                    .ok()
+                    .and_then(|(header, _)| header.pts)
+                    .map(|pts| pts.as_u64());
+            }
+        }
+    }
+
+    if pts.is_none() && pcr.is_none() && !is_random_access {
+        return None;
+    }
+    Some(TimingPoint {
+        offset,
+        pid: packet.pid(),
+        pts,
+        pcr,
+        is_random_access,
+    })
+}
@@ -0,0 +1,1520 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::{Read, Write};
+
+use time::Timestamp;
+use util::{self, WithCrc32};
+use {ErrorKind, Result};
+
+/// Maximum value of `section_length`.
+const MAX_SECTION_LEN: usize = 4093;
+
+/// `splice_command_length` of the legacy streams that do not signal the length.
+const UNKNOWN_COMMAND_LEN: usize = 0xFFF;
+
+const MAX_33BIT: u64 = (1 << 33) - 1;
+
+/// SCTE-35 splice information section.
+///
+/// This is the payload of the PIDs signalled with the stream type `0x86` (`StreamType::SCTE_35`).
+/// Encrypted sections cannot be decoded, and the readers keep them as `TsPayload::Raw` payloads.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpliceInfoSection {
+    /// Stream access point type of the splice point (`3` means "not specified").
+    pub sap_type: u8,
+
+    pub protocol_version: u8,
+
+    /// Offset added to every PTS in the section (modulo 2^33).
+    pub pts_adjustment: Timestamp,
+
+    /// Authorization tier (`0xFFF` means that the tier is not used).
+    pub tier: u16,
+
+    pub splice_command: SpliceCommand,
+    pub descriptors: Vec<SpliceDescriptor>,
+}
+impl SpliceInfoSection {
+    /// Table identifier of the section.
+    pub const TABLE_ID: u8 = 0xFC;
+
+    /// Makes a new `SpliceInfoSection` instance that has the given command and the default
+    /// header values (i.e., no PTS adjustment, no tier and no descriptors).
+    pub fn new(splice_command: SpliceCommand) -> Self {
+        SpliceInfoSection {
+            sap_type: 3,
+            protocol_version: 0,
+            pts_adjustment: Timestamp::new(0).expect("Never fails"),
+            tier: 0xFFF,
+            splice_command,
+            descriptors: Vec::new(),
+        }
+    }
+
+    /// Returns the PTS to which `pts_adjustment` is applied.
+    pub fn adjust_pts(&self, pts: Timestamp) -> Timestamp {
+        let pts = (pts.as_u64() + self.pts_adjustment.as_u64()) & MAX_33BIT;
+        Timestamp::new(pts).expect("Never fails")
+    }
+
+    pub(super) fn read_from<R: Read>(mut reader: R) -> Result<Self> {
+        let pointer_field = track_io!(reader.read_u8())?;
+        track_assert_eq!(pointer_field, 0, ErrorKind::Unsupported);
+
+        let mut header = [0; 3];
+        track_io!(reader.read_exact(&mut header))?;
+        let section_len = (((header[1] & 0b0000_1111) as usize) << 8) | header[2] as usize;
+        let mut section = header.to_vec();
+        track_io!(reader
+            .by_ref()
+            .take(section_len as u64)
+            .read_to_end(&mut section))?;
+        let this = track!(Self::read_section(&section))?;
+        track!(util::consume_stuffing_bytes(reader))?;
+        Ok(this)
+    }
+
+    /// Returns `true` if the `encrypted_packet` flag of the given section is set.
+    ///
+    /// `section` starts with the `table_id` byte.
+    pub(super) fn is_encrypted_section(section: &[u8]) -> bool {
+        matches!(section.get(4), Some(b) if b & 0b1000_0000 != 0)
+    }
+
+    /// Reads a section that starts with the `table_id` byte and ends with the CRC32.
+    pub(super) fn read_section(section: &[u8]) -> Result<Self> {
+        let mut reader = WithCrc32::new(section);
+        let table_id = track_io!(reader.read_u8())?;
+        track_assert_eq!(table_id, Self::TABLE_ID, ErrorKind::InvalidInput);
+
+        let n = track_io!(reader.read_u16::<BigEndian>())?;
+        track_assert_eq!(
+            n & 0b1100_0000_0000_0000,
+            0,
+            ErrorKind::InvalidInput,
+            "Unexpected section_syntax_indicator or private_indicator"
+        );
+        let sap_type = ((n >> 12) & 0b11) as u8;
+        let section_len = (n & 0b0000_1111_1111_1111) as usize;
+        track_assert!(section_len >= 4, ErrorKind::InvalidInput);
+        track_assert_eq!(section.len(), 3 + section_len, ErrorKind::InvalidInput);
+
+        let mut body = vec![0; section_len - 4];
+        track_io!(reader.read_exact(&mut body))?;
+        let crc32 = reader.crc32();
+        let expected_crc32 = track_io!(reader.read_u32::<BigEndian>())?;
+        track_assert_eq!(
+            crc32,
+            expected_crc32,
+            ErrorKind::InvalidInput,
+            "CRC32 mismatch"
+        );
+
+        let mut reader = &body[..];
+        let protocol_version = track_io!(reader.read_u8())?;
+        let n = track_io!(reader.read_uint::<BigEndian>(5))?;
+        let encrypted_packet = (n >> 39) != 0;
+        track_assert!(
+            !encrypted_packet,
+            ErrorKind::Unsupported,
+            "Encrypted splice_info_section"
+        );
+        let pts_adjustment = track!(Timestamp::new(n & MAX_33BIT))?;
+        let _cw_index = track_io!(reader.read_u8())?;
+
+        let n = track_io!(reader.read_u24::<BigEndian>())?;
+        let tier = (n >> 12) as u16;
+        let splice_command_len = (n & 0xFFF) as usize;
+        let splice_command_type = track_io!(reader.read_u8())?;
+        let splice_command = if splice_command_len == UNKNOWN_COMMAND_LEN {
+            track!(SpliceCommand::read_from(
+                splice_command_type,
+                &mut reader,
+                true
+            ))?
+        } else {
+            track_assert!(splice_command_len <= reader.len(), ErrorKind::InvalidInput);
+            let (mut command, rest) = reader.split_at(splice_command_len);
+            reader = rest;
+            let splice_command = track!(SpliceCommand::read_from(
+                splice_command_type,
+                &mut command,
+                false
+            ))?;
+            track_assert!(command.is_empty(), ErrorKind::InvalidInput);
+            splice_command
+        };
+
+        let descriptor_loop_len = track_io!(reader.read_u16::<BigEndian>())? as usize;
+        track_assert!(descriptor_loop_len <= reader.len(), ErrorKind::InvalidInput);
+        let (mut descriptor_loop, _alignment_stuffing) = reader.split_at(descriptor_loop_len);
+        let mut descriptors = Vec::new();
+        while !descriptor_loop.is_empty() {
+            descriptors.push(track!(SpliceDescriptor::read_from(&mut descriptor_loop))?);
+        }
+
+        Ok(SpliceInfoSection {
+            sap_type,
+            protocol_version,
+            pts_adjustment,
+            tier,
+            splice_command,
+            descriptors,
+        })
+    }
+
+    pub(super) fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.sap_type <= 3, ErrorKind::InvalidInput);
+        track_assert!(self.tier <= 0xFFF, ErrorKind::InvalidInput);
+
+        let mut command = Vec::new();
+        track!(self.splice_command.write_to(&mut command))?;
+        track_assert!(command.len() < UNKNOWN_COMMAND_LEN, ErrorKind::InvalidInput);
+
+        let mut descriptor_loop = Vec::new();
+        for d in &self.descriptors {
+            track!(d.write_to(&mut descriptor_loop))?;
+        }
+        track_assert!(descriptor_loop.len() <= 0xFFFF, ErrorKind::InvalidInput);
+
+        let mut body = Vec::new();
+        track_io!(body.write_u8(self.protocol_version))?;
+        track_io!(body.write_uint::<BigEndian>(self.pts_adjustment.as_u64(), 5))?;
+        track_io!(body.write_u8(0xFF))?; // cw_index (not used by unencrypted sections)
+        let n = (u32::from(self.tier) << 12) | command.len() as u32;
+        track_io!(body.write_u24::<BigEndian>(n))?;
+        track_io!(body.write_u8(self.splice_command.command_type()))?;
+        body.extend_from_slice(&command);
+        track_io!(body.write_u16::<BigEndian>(descriptor_loop.len() as u16))?;
+        body.extend_from_slice(&descriptor_loop);
+
+        let section_len = body.len() + 4;
+        track_assert!(
+            section_len <= MAX_SECTION_LEN,
+            ErrorKind::InvalidInput,
+            "Too large section: {} bytes",
+            section_len
+        );
+
+        track_io!(writer.write_u8(0))?; // pointer field
+        let mut writer = WithCrc32::new(writer);
+        track_io!(writer.write_u8(Self::TABLE_ID))?;
+        let n = (u16::from(self.sap_type) << 12) | section_len as u16;
+        track_io!(writer.write_u16::<BigEndian>(n))?;
+        track_io!(writer.write_all(&body))?;
+        let crc32 = writer.crc32();
+        track_io!(writer.write_u32::<BigEndian>(crc32))?;
+        Ok(())
+    }
+}
+
+/// Splice command of a `SpliceInfoSection`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpliceCommand {
+    SpliceNull,
+    SpliceSchedule(SpliceSchedule),
+    SpliceInsert(SpliceInsert),
+    TimeSignal(SpliceTime),
+    BandwidthReservation,
+    PrivateCommand {
+        identifier: u32,
+        private_bytes: Vec<u8>,
+    },
+
+    /// Command that has an unsupported type.
+    Unknown {
+        command_type: u8,
+        data: Vec<u8>,
+    },
+}
+impl SpliceCommand {
+    /// Returns the `splice_command_type` of the command.
+    pub fn command_type(&self) -> u8 {
+        match *self {
+            SpliceCommand::SpliceNull => 0x00,
+            SpliceCommand::SpliceSchedule(_) => 0x04,
+            SpliceCommand::SpliceInsert(_) => 0x05,
+            SpliceCommand::TimeSignal(_) => 0x06,
+            SpliceCommand::BandwidthReservation => 0x07,
+            SpliceCommand::PrivateCommand { .. } => 0xFF,
+            SpliceCommand::Unknown { command_type, .. } => command_type,
+        }
+    }
+
+    /// Reads a command.
+    ///
+    /// If `is_unbounded` is `true`, the reader also contains the bytes following the command.
+    fn read_from(command_type: u8, reader: &mut &[u8], is_unbounded: bool) -> Result<Self> {
+        Ok(match command_type {
+            0x00 => SpliceCommand::SpliceNull,
+            0x04 => SpliceCommand::SpliceSchedule(track!(SpliceSchedule::read_from(reader))?),
+            0x05 => SpliceCommand::SpliceInsert(track!(SpliceInsert::read_from(reader))?),
+            0x06 => SpliceCommand::TimeSignal(track!(SpliceTime::read_from(reader))?),
+            0x07 => SpliceCommand::BandwidthReservation,
+            _ => {
+                track_assert!(
+                    !is_unbounded,
+                    ErrorKind::InvalidInput,
+                    "Unknown length of splice command: type={}",
+                    command_type
+                );
+                if command_type == 0xFF {
+                    let identifier = track_io!(reader.read_u32::<BigEndian>())?;
+                    let private_bytes = reader.to_owned();
+                    *reader = &[];
+                    SpliceCommand::PrivateCommand {
+                        identifier,
+                        private_bytes,
+                    }
+                } else {
+                    let data = reader.to_owned();
+                    *reader = &[];
+                    SpliceCommand::Unknown { command_type, data }
+                }
+            }
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        match *self {
+            SpliceCommand::SpliceNull | SpliceCommand::BandwidthReservation => {}
+            SpliceCommand::SpliceSchedule(ref x) => track!(x.write_to(writer))?,
+            SpliceCommand::SpliceInsert(ref x) => track!(x.write_to(writer))?,
+            SpliceCommand::TimeSignal(ref x) => track!(x.write_to(writer))?,
+            SpliceCommand::PrivateCommand {
+                identifier,
+                ref private_bytes,
+            } => {
+                track_io!(writer.write_u32::<BigEndian>(identifier))?;
+                track_io!(writer.write_all(private_bytes))?;
+            }
+            SpliceCommand::Unknown {
+                command_type,
+                ref data,
+            } => {
+                track_assert!(
+                    !matches!(command_type, 0x00 | 0x04..=0x07 | 0xFF),
+                    ErrorKind::InvalidInput,
+                    "Command type {} must not be used by `SpliceCommand::Unknown`",
+                    command_type
+                );
+                track_io!(writer.write_all(data))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `splice_insert()` command.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpliceInsert {
+    pub splice_event_id: u32,
+
+    /// The details of the event.
+    ///
+    /// This is `None` if the event is cancelled (i.e., `splice_event_cancel_indicator` is set).
+    pub event: Option<SpliceInsertEvent>,
+}
+impl SpliceInsert {
+    /// Makes a program splice that leaves the network (i.e., cue-out) at `splice_time`.
+    ///
+    /// If `splice_time` is `None`, the splice takes place immediately.
+    pub fn cue_out(
+        splice_event_id: u32,
+        splice_time: Option<Timestamp>,
+        break_duration: Option<BreakDuration>,
+    ) -> Self {
+        Self::program_splice(splice_event_id, true, splice_time, break_duration)
+    }
+
+    /// Makes a program splice that returns to the network (i.e., cue-in) at `splice_time`.
+    ///
+    /// If `splice_time` is `None`, the splice takes place immediately.
+    pub fn cue_in(splice_event_id: u32, splice_time: Option<Timestamp>) -> Self {
+        Self::program_splice(splice_event_id, false, splice_time, None)
+    }
+
+    fn program_splice(
+        splice_event_id: u32,
+        out_of_network_indicator: bool,
+        splice_time: Option<Timestamp>,
+        break_duration: Option<BreakDuration>,
+    ) -> Self {
+        SpliceInsert {
+            splice_event_id,
+            event: Some(SpliceInsertEvent {
+                out_of_network_indicator,
+                program_splice_flag: true,
+                splice_immediate_flag: splice_time.is_none(),
+                event_id_compliance_flag: true,
+                splice_time: splice_time.map(|t| SpliceTime { pts_time: Some(t) }),
+                components: Vec::new(),
+                break_duration,
+                unique_program_id: 0,
+                avail_num: 0,
+                avails_expected: 0,
+            }),
+        }
+    }
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let splice_event_id = track_io!(reader.read_u32::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        if (b & 0b1000_0000) != 0 {
+            return Ok(SpliceInsert {
+                splice_event_id,
+                event: None,
+            });
+        }
+
+        let b = track_io!(reader.read_u8())?;
+        let out_of_network_indicator = (b & 0b1000_0000) != 0;
+        let program_splice_flag = (b & 0b0100_0000) != 0;
+        let duration_flag = (b & 0b0010_0000) != 0;
+        let splice_immediate_flag = (b & 0b0001_0000) != 0;
+        let event_id_compliance_flag = (b & 0b0000_1000) != 0;
+
+        let mut splice_time = None;
+        let mut components = Vec::new();
+        if program_splice_flag {
+            if !splice_immediate_flag {
+                splice_time = Some(track!(SpliceTime::read_from(reader))?);
+            }
+        } else {
+            let component_count = track_io!(reader.read_u8())?;
+            for _ in 0..component_count {
+                let component_tag = track_io!(reader.read_u8())?;
+                let splice_time = if splice_immediate_flag {
+                    None
+                } else {
+                    Some(track!(SpliceTime::read_from(reader))?)
+                };
+                components.push(SpliceInsertComponent {
+                    component_tag,
+                    splice_time,
+                });
+            }
+        }
+        let break_duration = if duration_flag {
+            Some(track!(BreakDuration::read_from(reader))?)
+        } else {
+            None
+        };
+        let unique_program_id = track_io!(reader.read_u16::<BigEndian>())?;
+        let avail_num = track_io!(reader.read_u8())?;
+        let avails_expected = track_io!(reader.read_u8())?;
+        Ok(SpliceInsert {
+            splice_event_id,
+            event: Some(SpliceInsertEvent {
+                out_of_network_indicator,
+                program_splice_flag,
+                splice_immediate_flag,
+                event_id_compliance_flag,
+                splice_time,
+                components,
+                break_duration,
+                unique_program_id,
+                avail_num,
+                avails_expected,
+            }),
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u32::<BigEndian>(self.splice_event_id))?;
+        let event = match self.event {
+            None => {
+                track_io!(writer.write_u8(0b1111_1111))?;
+                return Ok(());
+            }
+            Some(ref event) => event,
+        };
+        track_io!(writer.write_u8(0b0111_1111))?;
+
+        let has_time = !event.splice_immediate_flag;
+        if event.program_splice_flag {
+            track_assert!(event.components.is_empty(), ErrorKind::InvalidInput);
+            track_assert_eq!(
+                event.splice_time.is_some(),
+                has_time,
+                ErrorKind::InvalidInput
+            );
+        } else {
+            track_assert!(event.splice_time.is_none(), ErrorKind::InvalidInput);
+            track_assert!(event.components.len() <= 0xFF, ErrorKind::InvalidInput);
+            for c in &event.components {
+                track_assert_eq!(c.splice_time.is_some(), has_time, ErrorKind::InvalidInput);
+            }
+        }
+
+        let b = ((event.out_of_network_indicator as u8) << 7)
+            | ((event.program_splice_flag as u8) << 6)
+            | ((event.break_duration.is_some() as u8) << 5)
+            | ((event.splice_immediate_flag as u8) << 4)
+            | ((event.event_id_compliance_flag as u8) << 3)
+            | 0b0000_0111;
+        track_io!(writer.write_u8(b))?;
+        if let Some(ref t) = event.splice_time {
+            track!(t.write_to(&mut writer))?;
+        }
+        if !event.program_splice_flag {
+            track_io!(writer.write_u8(event.components.len() as u8))?;
+            for c in &event.components {
+                track_io!(writer.write_u8(c.component_tag))?;
+                if let Some(ref t) = c.splice_time {
+                    track!(t.write_to(&mut writer))?;
+                }
+            }
+        }
+        if let Some(ref d) = event.break_duration {
+            track!(d.write_to(&mut writer))?;
+        }
+        track_io!(writer.write_u16::<BigEndian>(event.unique_program_id))?;
+        track_io!(writer.write_u8(event.avail_num))?;
+        track_io!(writer.write_u8(event.avails_expected))?;
+        Ok(())
+    }
+}
+
+/// Event of a `splice_insert()` command that is not cancelled.
+///
+/// If `program_splice_flag` is set, `splice_time` is used and `components` must be empty.
+/// Otherwise, `components` is used and `splice_time` must be `None`.
+/// The splice times are absent if and only if `splice_immediate_flag` is set.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpliceInsertEvent {
+    pub out_of_network_indicator: bool,
+    pub program_splice_flag: bool,
+    pub splice_immediate_flag: bool,
+    pub event_id_compliance_flag: bool,
+    pub splice_time: Option<SpliceTime>,
+    pub components: Vec<SpliceInsertComponent>,
+    pub break_duration: Option<BreakDuration>,
+    pub unique_program_id: u16,
+    pub avail_num: u8,
+    pub avails_expected: u8,
+}
+
+/// Component of a `splice_insert()` command.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpliceInsertComponent {
+    pub component_tag: u8,
+    pub splice_time: Option<SpliceTime>,
+}
+
+/// `splice_schedule()` command.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SpliceSchedule {
+    pub events: Vec<ScheduledSplice>,
+}
+impl SpliceSchedule {
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let splice_count = track_io!(reader.read_u8())?;
+        let mut events = Vec::with_capacity(splice_count as usize);
+        for _ in 0..splice_count {
+            events.push(track!(ScheduledSplice::read_from(reader))?);
+        }
+        Ok(SpliceSchedule { events })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.events.len() <= 0xFF, ErrorKind::InvalidInput);
+        track_io!(writer.write_u8(self.events.len() as u8))?;
+        for e in &self.events {
+            track!(e.write_to(&mut writer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Splice event of a `splice_schedule()` command.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScheduledSplice {
+    pub splice_event_id: u32,
+
+    /// The details of the event.
+    ///
+    /// This is `None` if the event is cancelled (i.e., `splice_event_cancel_indicator` is set).
+    pub event: Option<ScheduledSpliceEvent>,
+}
+impl ScheduledSplice {
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let splice_event_id = track_io!(reader.read_u32::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        if (b & 0b1000_0000) != 0 {
+            return Ok(ScheduledSplice {
+                splice_event_id,
+                event: None,
+            });
+        }
+
+        let b = track_io!(reader.read_u8())?;
+        let out_of_network_indicator = (b & 0b1000_0000) != 0;
+        let program_splice_flag = (b & 0b0100_0000) != 0;
+        let duration_flag = (b & 0b0010_0000) != 0;
+
+        let mut utc_splice_time = None;
+        let mut components = Vec::new();
+        if program_splice_flag {
+            utc_splice_time = Some(track_io!(reader.read_u32::<BigEndian>())?);
+        } else {
+            let component_count = track_io!(reader.read_u8())?;
+            for _ in 0..component_count {
+                let component_tag = track_io!(reader.read_u8())?;
+                let utc_splice_time = track_io!(reader.read_u32::<BigEndian>())?;
+                components.push(ScheduledSpliceComponent {
+                    component_tag,
+                    utc_splice_time,
+                });
+            }
+        }
+        let break_duration = if duration_flag {
+            Some(track!(BreakDuration::read_from(reader))?)
+        } else {
+            None
+        };
+        let unique_program_id = track_io!(reader.read_u16::<BigEndian>())?;
+        let avail_num = track_io!(reader.read_u8())?;
+        let avails_expected = track_io!(reader.read_u8())?;
+        Ok(ScheduledSplice {
+            splice_event_id,
+            event: Some(ScheduledSpliceEvent {
+                out_of_network_indicator,
+                program_splice_flag,
+                utc_splice_time,
+                components,
+                break_duration,
+                unique_program_id,
+                avail_num,
+                avails_expected,
+            }),
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u32::<BigEndian>(self.splice_event_id))?;
+        let event = match self.event {
+            None => {
+                track_io!(writer.write_u8(0b1111_1111))?;
+                return Ok(());
+            }
+            Some(ref event) => event,
+        };
+        track_io!(writer.write_u8(0b0111_1111))?;
+
+        if event.program_splice_flag {
+            track_assert!(event.components.is_empty(), ErrorKind::InvalidInput);
+        } else {
+            track_assert!(event.utc_splice_time.is_none(), ErrorKind::InvalidInput);
+            track_assert!(event.components.len() <= 0xFF, ErrorKind::InvalidInput);
+        }
+
+        let b = ((event.out_of_network_indicator as u8) << 7)
+            | ((event.program_splice_flag as u8) << 6)
+            | ((event.break_duration.is_some() as u8) << 5)
+            | 0b0001_1111;
+        track_io!(writer.write_u8(b))?;
+        if event.program_splice_flag {
+            let utc_splice_time =
+                track_assert_some!(event.utc_splice_time, ErrorKind::InvalidInput);
+            track_io!(writer.write_u32::<BigEndian>(utc_splice_time))?;
+        } else {
+            track_io!(writer.write_u8(event.components.len() as u8))?;
+            for c in &event.components {
+                track_io!(writer.write_u8(c.component_tag))?;
+                track_io!(writer.write_u32::<BigEndian>(c.utc_splice_time))?;
+            }
+        }
+        if let Some(ref d) = event.break_duration {
+            track!(d.write_to(&mut writer))?;
+        }
+        track_io!(writer.write_u16::<BigEndian>(event.unique_program_id))?;
+        track_io!(writer.write_u8(event.avail_num))?;
+        track_io!(writer.write_u8(event.avails_expected))?;
+        Ok(())
+    }
+}
+
+/// Scheduled splice event that is not cancelled.
+///
+/// The splice times are in seconds since the GPS epoch (1980-01-06 00:00:00 UTC).
+/// If `program_splice_flag` is set, `utc_splice_time` must be `Some` and `components`
+/// must be empty. Otherwise, `utc_splice_time` must be `None`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScheduledSpliceEvent {
+    pub out_of_network_indicator: bool,
+    pub program_splice_flag: bool,
+    pub utc_splice_time: Option<u32>,
+    pub components: Vec<ScheduledSpliceComponent>,
+    pub break_duration: Option<BreakDuration>,
+    pub unique_program_id: u16,
+    pub avail_num: u8,
+    pub avails_expected: u8,
+}
+
+/// Component of a scheduled splice event.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ScheduledSpliceComponent {
+    pub component_tag: u8,
+    pub utc_splice_time: u32,
+}
+
+/// `splice_time()` structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpliceTime {
+    /// The splice time before applying `pts_adjustment`.
+    ///
+    /// This is `None` if `time_specified_flag` is not set.
+    pub pts_time: Option<Timestamp>,
+}
+impl SpliceTime {
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let b = track_io!(reader.read_u8())?;
+        if (b & 0b1000_0000) == 0 {
+            return Ok(SpliceTime { pts_time: None });
+        }
+        let n = (u64::from(b & 1) << 32) | u64::from(track_io!(reader.read_u32::<BigEndian>())?);
+        let pts_time = track!(Timestamp::new(n))?;
+        Ok(SpliceTime {
+            pts_time: Some(pts_time),
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        match self.pts_time {
+            None => track_io!(writer.write_u8(0b0111_1111))?,
+            Some(t) => {
+                let n = (0b1111_1110 << 32) | t.as_u64();
+                track_io!(writer.write_uint::<BigEndian>(n, 5))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// `break_duration()` structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BreakDuration {
+    /// If `true`, the splice event returns to the network automatically at the end of the break.
+    pub auto_return: bool,
+
+    /// The duration of the break in 90 kHz units (33 bits).
+    pub duration: u64,
+}
+impl BreakDuration {
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let n = track_io!(reader.read_uint::<BigEndian>(5))?;
+        Ok(BreakDuration {
+            auto_return: (n >> 39) != 0,
+            duration: n & MAX_33BIT,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.duration <= MAX_33BIT, ErrorKind::InvalidInput);
+        let n = ((self.auto_return as u64) << 39) | (0b11_1111 << 33) | self.duration;
+        track_io!(writer.write_uint::<BigEndian>(n, 5))?;
+        Ok(())
+    }
+}
+
+/// Splice descriptor in a `SpliceInfoSection`.
+///
+/// Only the descriptors that have the SCTE identifier (`"CUEI"`) are decoded.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SpliceDescriptor {
+    Avail(AvailDescriptor),
+    Dtmf(DtmfDescriptor),
+    Segmentation(SegmentationDescriptor),
+    Time(TimeDescriptor),
+
+    /// Descriptor that has an unsupported tag or a private identifier.
+    Raw {
+        splice_descriptor_tag: u8,
+        identifier: u32,
+        data: Vec<u8>,
+    },
+}
+impl SpliceDescriptor {
+    /// The identifier of the descriptors defined by SCTE (`"CUEI"`).
+    pub const CUEI: u32 = 0x4355_4549;
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let tag = track_io!(reader.read_u8())?;
+        let len = track_io!(reader.read_u8())? as usize;
+        track_assert!(len >= 4 && len <= reader.len(), ErrorKind::InvalidInput);
+        let (mut data, rest) = reader.split_at(len);
+        *reader = rest;
+
+        let identifier = track_io!(data.read_u32::<BigEndian>())?;
+        let d = match (identifier, tag) {
+            (Self::CUEI, AvailDescriptor::TAG) => {
+                SpliceDescriptor::Avail(track!(AvailDescriptor::read_from(&mut data))?)
+            }
+            (Self::CUEI, DtmfDescriptor::TAG) => {
+                SpliceDescriptor::Dtmf(track!(DtmfDescriptor::read_from(&mut data))?)
+            }
+            (Self::CUEI, SegmentationDescriptor::TAG) => SpliceDescriptor::Segmentation(track!(
+                SegmentationDescriptor::read_from(&mut data)
+            )?),
+            (Self::CUEI, TimeDescriptor::TAG) => {
+                SpliceDescriptor::Time(track!(TimeDescriptor::read_from(&mut data))?)
+            }
+            _ => {
+                let d = SpliceDescriptor::Raw {
+                    splice_descriptor_tag: tag,
+                    identifier,
+                    data: data.to_owned(),
+                };
+                data = &[];
+                d
+            }
+        };
+        track_assert!(
+            data.is_empty(),
+            ErrorKind::InvalidInput,
+            "Unexpected trailing bytes: tag={}, len={}",
+            tag,
+            data.len()
+        );
+        Ok(d)
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        let mut data = Vec::new();
+        let (tag, identifier) = match *self {
+            SpliceDescriptor::Avail(ref x) => {
+                track!(x.write_to(&mut data))?;
+                (AvailDescriptor::TAG, Self::CUEI)
+            }
+            SpliceDescriptor::Dtmf(ref x) => {
+                track!(x.write_to(&mut data))?;
+                (DtmfDescriptor::TAG, Self::CUEI)
+            }
+            SpliceDescriptor::Segmentation(ref x) => {
+                track!(x.write_to(&mut data))?;
+                (SegmentationDescriptor::TAG, Self::CUEI)
+            }
+            SpliceDescriptor::Time(ref x) => {
+                track!(x.write_to(&mut data))?;
+                (TimeDescriptor::TAG, Self::CUEI)
+            }
+            SpliceDescriptor::Raw {
+                splice_descriptor_tag,
+                identifier,
+                data: ref x,
+            } => {
+                data.extend_from_slice(x);
+                (splice_descriptor_tag, identifier)
+            }
+        };
+        track_assert!(
+            data.len() <= 0xFF - 4,
+            ErrorKind::InvalidInput,
+            "Too large splice descriptor: {} bytes",
+            data.len()
+        );
+        track_io!(writer.write_u8(tag))?;
+        track_io!(writer.write_u8((data.len() + 4) as u8))?;
+        track_io!(writer.write_u32::<BigEndian>(identifier))?;
+        track_io!(writer.write_all(&data))?;
+        Ok(())
+    }
+}
+
+/// `avail_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AvailDescriptor {
+    pub provider_avail_id: u32,
+}
+impl AvailDescriptor {
+    /// Splice descriptor tag.
+    pub const TAG: u8 = 0x00;
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let provider_avail_id = track_io!(reader.read_u32::<BigEndian>())?;
+        Ok(AvailDescriptor { provider_avail_id })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u32::<BigEndian>(self.provider_avail_id))?;
+        Ok(())
+    }
+}
+
+/// `DTMF_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DtmfDescriptor {
+    /// The time between the descriptor and the splice in tenths of a second.
+    pub preroll: u8,
+
+    /// The DTMF characters (up to 7 bytes).
+    pub dtmf_chars: Vec<u8>,
+}
+impl DtmfDescriptor {
+    /// Splice descriptor tag.
+    pub const TAG: u8 = 0x01;
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let preroll = track_io!(reader.read_u8())?;
+        let dtmf_count = track_io!(reader.read_u8())? >> 5;
+        let mut dtmf_chars = vec![0; dtmf_count as usize];
+        track_io!(reader.read_exact(&mut dtmf_chars))?;
+        Ok(DtmfDescriptor {
+            preroll,
+            dtmf_chars,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.dtmf_chars.len() <= 7, ErrorKind::InvalidInput);
+        track_io!(writer.write_u8(self.preroll))?;
+        track_io!(writer.write_u8(((self.dtmf_chars.len() as u8) << 5) | 0b1_1111))?;
+        track_io!(writer.write_all(&self.dtmf_chars))?;
+        Ok(())
+    }
+}
+
+/// `time_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeDescriptor {
+    /// The seconds of the TAI time (48 bits).
+    pub tai_seconds: u64,
+
+    pub tai_ns: u32,
+    pub utc_offset: u16,
+}
+impl TimeDescriptor {
+    /// Splice descriptor tag.
+    pub const TAG: u8 = 0x03;
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let tai_seconds = track_io!(reader.read_u48::<BigEndian>())?;
+        let tai_ns = track_io!(reader.read_u32::<BigEndian>())?;
+        let utc_offset = track_io!(reader.read_u16::<BigEndian>())?;
+        Ok(TimeDescriptor {
+            tai_seconds,
+            tai_ns,
+            utc_offset,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_assert!(self.tai_seconds < (1 << 48), ErrorKind::InvalidInput);
+        track_io!(writer.write_u48::<BigEndian>(self.tai_seconds))?;
+        track_io!(writer.write_u32::<BigEndian>(self.tai_ns))?;
+        track_io!(writer.write_u16::<BigEndian>(self.utc_offset))?;
+        Ok(())
+    }
+}
+
+/// `segmentation_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SegmentationDescriptor {
+    pub segmentation_event_id: u32,
+    pub segmentation_event_id_compliance_indicator: bool,
+
+    /// The details of the segmentation event.
+    ///
+    /// This is `None` if the event is cancelled
+    /// (i.e., `segmentation_event_cancel_indicator` is set).
+    pub segmentation: Option<Segmentation>,
+}
+impl SegmentationDescriptor {
+    /// Splice descriptor tag.
+    pub const TAG: u8 = 0x02;
+
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let segmentation_event_id = track_io!(reader.read_u32::<BigEndian>())?;
+        let b = track_io!(reader.read_u8())?;
+        let segmentation_event_id_compliance_indicator = (b & 0b0100_0000) != 0;
+        let segmentation = if (b & 0b1000_0000) != 0 {
+            None
+        } else {
+            Some(track!(Segmentation::read_from(reader))?)
+        };
+        Ok(SegmentationDescriptor {
+            segmentation_event_id,
+            segmentation_event_id_compliance_indicator,
+            segmentation,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        track_io!(writer.write_u32::<BigEndian>(self.segmentation_event_id))?;
+        let b = ((self.segmentation.is_none() as u8) << 7)
+            | ((self.segmentation_event_id_compliance_indicator as u8) << 6)
+            | 0b0011_1111;
+        track_io!(writer.write_u8(b))?;
+        if let Some(ref x) = self.segmentation {
+            track!(x.write_to(writer))?;
+        }
+        Ok(())
+    }
+}
+
+/// Segmentation event of a `segmentation_descriptor()` that is not cancelled.
+///
+/// If `program_segmentation_flag` is set, `components` must be empty.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Segmentation {
+    /// The delivery restrictions of the segment.
+    ///
+    /// This is `None` if `delivery_not_restricted_flag` is set.
+    pub delivery_restrictions: Option<DeliveryRestrictions>,
+
+    pub program_segmentation_flag: bool,
+    pub components: Vec<SegmentationComponent>,
+
+    /// The duration of the segment in 90 kHz units (40 bits).
+    pub segmentation_duration: Option<u64>,
+
+    pub segmentation_upid_type: u8,
+    pub segmentation_upid: Vec<u8>,
+    pub segmentation_type: SegmentationType,
+    pub segment_num: u8,
+    pub segments_expected: u8,
+
+    /// The pair of `sub_segment_num` and `sub_segments_expected`.
+    ///
+    /// These fields are present only for the segmentation types that have sub-segments
+    /// (see `SegmentationType::has_sub_segments()`), and may be omitted by the legacy streams.
+    pub sub_segment: Option<(u8, u8)>,
+}
+impl Segmentation {
+    fn read_from(reader: &mut &[u8]) -> Result<Self> {
+        let b = track_io!(reader.read_u8())?;
+        let program_segmentation_flag = (b & 0b1000_0000) != 0;
+        let segmentation_duration_flag = (b & 0b0100_0000) != 0;
+        let delivery_not_restricted_flag = (b & 0b0010_0000) != 0;
+        let delivery_restrictions = if delivery_not_restricted_flag {
+            None
+        } else {
+            Some(DeliveryRestrictions {
+                web_delivery_allowed_flag: (b & 0b0001_0000) != 0,
+                no_regional_blackout_flag: (b & 0b0000_1000) != 0,
+                archive_allowed_flag: (b & 0b0000_0100) != 0,
+                device_restrictions: b & 0b0000_0011,
+            })
+        };
+
+        let mut components = Vec::new();
+        if !program_segmentation_flag {
+            let component_count = track_io!(reader.read_u8())?;
+            for _ in 0..component_count {
+                let component_tag = track_io!(reader.read_u8())?;
+                let pts_offset = track_io!(reader.read_uint::<BigEndian>(5))? & MAX_33BIT;
+                components.push(SegmentationComponent {
+                    component_tag,
+                    pts_offset,
+                });
+            }
+        }
+        let segmentation_duration = if segmentation_duration_flag {
+            Some(track_io!(reader.read_uint::<BigEndian>(5))?)
+        } else {
+            None
+        };
+
+        let segmentation_upid_type = track_io!(reader.read_u8())?;
+        let segmentation_upid_len = track_io!(reader.read_u8())?;
+        let mut segmentation_upid = vec![0; segmentation_upid_len as usize];
+        track_io!(reader.read_exact(&mut segmentation_upid))?;
+
+        let segmentation_type = SegmentationType::from_u8(track_io!(reader.read_u8())?);
+        let segment_num = track_io!(reader.read_u8())?;
+        let segments_expected = track_io!(reader.read_u8())?;
+        let sub_segment = if segmentation_type.has_sub_segments() && reader.len() >= 2 {
+            let sub_segment_num = track_io!(reader.read_u8())?;
+            let sub_segments_expected = track_io!(reader.read_u8())?;
+            Some((sub_segment_num, sub_segments_expected))
+        } else {
+            None
+        };
+        Ok(Segmentation {
+            delivery_restrictions,
+            program_segmentation_flag,
+            components,
+            segmentation_duration,
+            segmentation_upid_type,
+            segmentation_upid,
+            segmentation_type,
+            segment_num,
+            segments_expected,
+            sub_segment,
+        })
+    }
+
+    fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        if self.program_segmentation_flag {
+            track_assert!(self.components.is_empty(), ErrorKind::InvalidInput);
+        }
+        track_assert!(self.components.len() <= 0xFF, ErrorKind::InvalidInput);
+        track_assert!(
+            self.segmentation_upid.len() <= 0xFF,
+            ErrorKind::InvalidInput
+        );
+        if let Some(d) = self.segmentation_duration {
+            track_assert!(d < (1 << 40), ErrorKind::InvalidInput);
+        }
+        if self.sub_segment.is_some() {
+            track_assert!(
+                self.segmentation_type.has_sub_segments(),
+                ErrorKind::InvalidInput
+            );
+        }
+
+        let restrictions = match self.delivery_restrictions {
+            None => 0b0011_1111,
+            Some(ref r) => {
+                track_assert!(r.device_restrictions <= 3, ErrorKind::InvalidInput);
+                ((r.web_delivery_allowed_flag as u8) << 4)
+                    | ((r.no_regional_blackout_flag as u8) << 3)
+                    | ((r.archive_allowed_flag as u8) << 2)
+                    | r.device_restrictions
+            }
+        };
+        let b = ((self.program_segmentation_flag as u8) << 7)
+            | ((self.segmentation_duration.is_some() as u8) << 6)
+            | restrictions;
+        track_io!(writer.write_u8(b))?;
+
+        if !self.program_segmentation_flag {
+            track_io!(writer.write_u8(self.components.len() as u8))?;
+            for c in &self.components {
+                track_assert!(c.pts_offset <= MAX_33BIT, ErrorKind::InvalidInput);
+                track_io!(writer.write_u8(c.component_tag))?;
+                let n = (0b111_1111 << 33) | c.pts_offset;
+                track_io!(writer.write_uint::<BigEndian>(n, 5))?;
+            }
+        }
+        if let Some(d) = self.segmentation_duration {
+            track_io!(writer.write_uint::<BigEndian>(d, 5))?;
+        }
+
+        track_io!(writer.write_u8(self.segmentation_upid_type))?;
+        track_io!(writer.write_u8(self.segmentation_upid.len() as u8))?;
+        track_io!(writer.write_all(&self.segmentation_upid))?;
+        track_io!(writer.write_u8(self.segmentation_type.as_u8()))?;
+        track_io!(writer.write_u8(self.segment_num))?;
+        track_io!(writer.write_u8(self.segments_expected))?;
+        if let Some((sub_segment_num, sub_segments_expected)) = self.sub_segment {
+            track_io!(writer.write_u8(sub_segment_num))?;
+            track_io!(writer.write_u8(sub_segments_expected))?;
+        }
+        Ok(())
+    }
+}
+
+/// Delivery restrictions of a segment.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DeliveryRestrictions {
+    pub web_delivery_allowed_flag: bool,
+    pub no_regional_blackout_flag: bool,
+    pub archive_allowed_flag: bool,
+
+    /// The device group restrictions (2 bits).
+    pub device_restrictions: u8,
+}
+
+/// Component of a `segmentation_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SegmentationComponent {
+    pub component_tag: u8,
+
+    /// The offset from the splice time in 90 kHz units (33 bits).
+    pub pts_offset: u64,
+}
+
+/// `segmentation_type_id` of a `segmentation_descriptor()`.
+#[allow(missing_docs)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentationType {
+    NotIndicated,
+    ContentIdentification,
+    ProgramStart,
+    ProgramEnd,
+    ProgramEarlyTermination,
+    ProgramBreakaway,
+    ProgramResumption,
+    ProgramRunoverPlanned,
+    ProgramRunoverUnplanned,
+    ProgramOverlapStart,
+    ProgramBlackoutOverride,
+    ProgramJoin,
+    ChapterStart,
+    ChapterEnd,
+    BreakStart,
+    BreakEnd,
+    OpeningCreditStart,
+    OpeningCreditEnd,
+    ClosingCreditStart,
+    ClosingCreditEnd,
+    ProviderAdvertisementStart,
+    ProviderAdvertisementEnd,
+    DistributorAdvertisementStart,
+    DistributorAdvertisementEnd,
+    ProviderPlacementOpportunityStart,
+    ProviderPlacementOpportunityEnd,
+    DistributorPlacementOpportunityStart,
+    DistributorPlacementOpportunityEnd,
+    ProviderOverlayPlacementOpportunityStart,
+    ProviderOverlayPlacementOpportunityEnd,
+    DistributorOverlayPlacementOpportunityStart,
+    DistributorOverlayPlacementOpportunityEnd,
+    ProviderPromoStart,
+    ProviderPromoEnd,
+    DistributorPromoStart,
+    DistributorPromoEnd,
+    UnscheduledEventStart,
+    UnscheduledEventEnd,
+    AlternateContentOpportunityStart,
+    AlternateContentOpportunityEnd,
+    ProviderAdBlockStart,
+    ProviderAdBlockEnd,
+    DistributorAdBlockStart,
+    DistributorAdBlockEnd,
+    NetworkStart,
+    NetworkEnd,
+
+    /// Segmentation type that is not listed above.
+    Unknown(u8),
+}
+impl SegmentationType {
+    /// Makes a `SegmentationType` instance that associated with the given number.
+    pub fn from_u8(n: u8) -> Self {
+        match n {
+            0x00 => SegmentationType::NotIndicated,
+            0x01 => SegmentationType::ContentIdentification,
+            0x10 => SegmentationType::ProgramStart,
+            0x11 => SegmentationType::ProgramEnd,
+            0x12 => SegmentationType::ProgramEarlyTermination,
+            0x13 => SegmentationType::ProgramBreakaway,
+            0x14 => SegmentationType::ProgramResumption,
+            0x15 => SegmentationType::ProgramRunoverPlanned,
+            0x16 => SegmentationType::ProgramRunoverUnplanned,
+            0x17 => SegmentationType::ProgramOverlapStart,
+            0x18 => SegmentationType::ProgramBlackoutOverride,
+            0x19 => SegmentationType::ProgramJoin,
+            0x20 => SegmentationType::ChapterStart,
+            0x21 => SegmentationType::ChapterEnd,
+            0x22 => SegmentationType::BreakStart,
+            0x23 => SegmentationType::BreakEnd,
+            0x24 => SegmentationType::OpeningCreditStart,
+            0x25 => SegmentationType::OpeningCreditEnd,
+            0x26 => SegmentationType::ClosingCreditStart,
+            0x27 => SegmentationType::ClosingCreditEnd,
+            0x30 => SegmentationType::ProviderAdvertisementStart,
+            0x31 => SegmentationType::ProviderAdvertisementEnd,
+            0x32 => SegmentationType::DistributorAdvertisementStart,
+            0x33 => SegmentationType::DistributorAdvertisementEnd,
+            0x34 => SegmentationType::ProviderPlacementOpportunityStart,
+            0x35 => SegmentationType::ProviderPlacementOpportunityEnd,
+            0x36 => SegmentationType::DistributorPlacementOpportunityStart,
+            0x37 => SegmentationType::DistributorPlacementOpportunityEnd,
+            0x38 => SegmentationType::ProviderOverlayPlacementOpportunityStart,
+            0x39 => SegmentationType::ProviderOverlayPlacementOpportunityEnd,
+            0x3A => SegmentationType::DistributorOverlayPlacementOpportunityStart,
+            0x3B => SegmentationType::DistributorOverlayPlacementOpportunityEnd,
+            0x3C => SegmentationType::ProviderPromoStart,
+            0x3D => SegmentationType::ProviderPromoEnd,
+            0x3E => SegmentationType::DistributorPromoStart,
+            0x3F => SegmentationType::DistributorPromoEnd,
+            0x40 => SegmentationType::UnscheduledEventStart,
+            0x41 => SegmentationType::UnscheduledEventEnd,
+            0x42 => SegmentationType::AlternateContentOpportunityStart,
+            0x43 => SegmentationType::AlternateContentOpportunityEnd,
+            0x44 => SegmentationType::ProviderAdBlockStart,
+            0x45 => SegmentationType::ProviderAdBlockEnd,
+            0x46 => SegmentationType::DistributorAdBlockStart,
+            0x47 => SegmentationType::DistributorAdBlockEnd,
+            0x50 => SegmentationType::NetworkStart,
+            0x51 => SegmentationType::NetworkEnd,
+            _ => SegmentationType::Unknown(n),
+        }
+    }
+
+    /// Returns the number associated with the segmentation type.
+    pub fn as_u8(&self) -> u8 {
+        match *self {
+            SegmentationType::NotIndicated => 0x00,
+            SegmentationType::ContentIdentification => 0x01,
+            SegmentationType::ProgramStart => 0x10,
+            SegmentationType::ProgramEnd => 0x11,
+            SegmentationType::ProgramEarlyTermination => 0x12,
+            SegmentationType::ProgramBreakaway => 0x13,
+            SegmentationType::ProgramResumption => 0x14,
+            SegmentationType::ProgramRunoverPlanned => 0x15,
+            SegmentationType::ProgramRunoverUnplanned => 0x16,
+            SegmentationType::ProgramOverlapStart => 0x17,
+            SegmentationType::ProgramBlackoutOverride => 0x18,
+            SegmentationType::ProgramJoin => 0x19,
+            SegmentationType::ChapterStart => 0x20,
+            SegmentationType::ChapterEnd => 0x21,
+            SegmentationType::BreakStart => 0x22,
+            SegmentationType::BreakEnd => 0x23,
+            SegmentationType::OpeningCreditStart => 0x24,
+            SegmentationType::OpeningCreditEnd => 0x25,
+            SegmentationType::ClosingCreditStart => 0x26,
+            SegmentationType::ClosingCreditEnd => 0x27,
+            SegmentationType::ProviderAdvertisementStart => 0x30,
+            SegmentationType::ProviderAdvertisementEnd => 0x31,
+            SegmentationType::DistributorAdvertisementStart => 0x32,
+            SegmentationType::DistributorAdvertisementEnd => 0x33,
+            SegmentationType::ProviderPlacementOpportunityStart => 0x34,
+            SegmentationType::ProviderPlacementOpportunityEnd => 0x35,
+            SegmentationType::DistributorPlacementOpportunityStart => 0x36,
+            SegmentationType::DistributorPlacementOpportunityEnd => 0x37,
+            SegmentationType::ProviderOverlayPlacementOpportunityStart => 0x38,
+            SegmentationType::ProviderOverlayPlacementOpportunityEnd => 0x39,
+            SegmentationType::DistributorOverlayPlacementOpportunityStart => 0x3A,
+            SegmentationType::DistributorOverlayPlacementOpportunityEnd => 0x3B,
+            SegmentationType::ProviderPromoStart => 0x3C,
+            SegmentationType::ProviderPromoEnd => 0x3D,
+            SegmentationType::DistributorPromoStart => 0x3E,
+            SegmentationType::DistributorPromoEnd => 0x3F,
+            SegmentationType::UnscheduledEventStart => 0x40,
+            SegmentationType::UnscheduledEventEnd => 0x41,
+            SegmentationType::AlternateContentOpportunityStart => 0x42,
+            SegmentationType::AlternateContentOpportunityEnd => 0x43,
+            SegmentationType::ProviderAdBlockStart => 0x44,
+            SegmentationType::ProviderAdBlockEnd => 0x45,
+            SegmentationType::DistributorAdBlockStart => 0x46,
+            SegmentationType::DistributorAdBlockEnd => 0x47,
+            SegmentationType::NetworkStart => 0x50,
+            SegmentationType::NetworkEnd => 0x51,
+            SegmentationType::Unknown(n) => n,
+        }
+    }
+
+    /// Returns `true` if the descriptors of the type carry `sub_segment_num` and
+    /// `sub_segments_expected`.
+    pub fn has_sub_segments(&self) -> bool {
+        matches!(
+            *self,
+            SegmentationType::ProviderAdvertisementStart
+                | SegmentationType::DistributorAdvertisementStart
+                | SegmentationType::ProviderPlacementOpportunityStart
+                | SegmentationType::DistributorPlacementOpportunityStart
+                | SegmentationType::ProviderOverlayPlacementOpportunityStart
+                | SegmentationType::DistributorOverlayPlacementOpportunityStart
+                | SegmentationType::ProviderAdBlockStart
+                | SegmentationType::DistributorAdBlockStart
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn round_trip(section: &SpliceInfoSection) -> Vec<u8> {
+        let mut buf = Vec::new();
+        track_try_unwrap!(section.write_to(&mut buf));
+        let decoded = track_try_unwrap!(SpliceInfoSection::read_from(&buf[..]));
+        assert_eq!(decoded, *section);
+        buf
+    }
+
+    #[test]
+    fn splice_insert() {
+        // Example 14.2 of SCTE 35 2022 (splice_insert)
+        let bytes = [
+            0x00, 0xFC, 0x30, 0x2F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xF0, 0x14,
+            0x05, 0x48, 0x00, 0x00, 0x8F, 0x7F, 0xEF, 0xFE, 0x73, 0x69, 0xC0, 0x2E, 0xFE, 0x00,
+            0x52, 0xCC, 0xF5, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0A, 0x00, 0x08, 0x43, 0x55, 0x45,
+            0x49, 0x00, 0x00, 0x01, 0x35, 0x62, 0xDB, 0xA3, 0x0A,
+        ];
+        let section = track_try_unwrap!(SpliceInfoSection::read_from(&bytes[..]));
+        assert_eq!(section.sap_type, 3);
+        assert_eq!(section.tier, 0xFFF);
+        let insert = match section.splice_command {
+            SpliceCommand::SpliceInsert(ref x) => x.clone(),
+            ref other => panic!("{:?}", other),
+        };
+        assert_eq!(insert.splice_event_id, 0x4800_008F);
+        let event = insert.event.unwrap();
+        assert!(event.out_of_network_indicator);
+        assert_eq!(
+            event.splice_time.unwrap().pts_time.map(|t| t.as_u64()),
+            Some(0x0_7369_C02E)
+        );
+        assert_eq!(
+            event.break_duration,
+            Some(BreakDuration {
+                auto_return: true,
+                duration: 0x52_CCF5
+            })
+        );
+        assert_eq!(
+            section.descriptors,
+            [SpliceDescriptor::Avail(AvailDescriptor {
+                provider_avail_id: 0x135
+            })]
+        );
+        assert_eq!(&round_trip(&section)[..], &bytes[..]);
+
+        let mut corrupted = bytes;
+        corrupted[20] ^= 1;
+        assert!(SpliceInfoSection::read_from(&corrupted[..]).is_err());
+    }
+
+    #[test]
+    fn time_signal_with_segmentation() {
+        // Example 14.1 of SCTE 35 2022 (time_signal - Placement Opportunity Start)
+        let bytes = [
+            0x00, 0xFC, 0x30, 0x34, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0xFF, 0xF0, 0x05,
+            0x06, 0xFE, 0x72, 0xBD, 0x00, 0x50, 0x00, 0x1E, 0x02, 0x1C, 0x43, 0x55, 0x45, 0x49,
+            0x48, 0x00, 0x00, 0x8E, 0x7F, 0xCF, 0x00, 0x01, 0xA5, 0x99, 0xB0, 0x08, 0x08, 0x00,
+            0x00, 0x00, 0x00, 0x2C, 0xA0, 0xA1, 0x8A, 0x34, 0x02, 0x00, 0x9A, 0xC9, 0xD1, 0x7E,
+        ];
+        let section = track_try_unwrap!(SpliceInfoSection::read_from(&bytes[..]));
+        let time = match section.splice_command {
+            SpliceCommand::TimeSignal(t) => t,
+            ref other => panic!("{:?}", other),
+        };
+        assert_eq!(time.pts_time.map(|t| t.as_u64()), Some(0x0_72BD_0050));
+
+        let d = match section.descriptors[0] {
+            SpliceDescriptor::Segmentation(ref d) => d.clone(),
+            ref other => panic!("{:?}", other),
+        };
+        assert_eq!(d.segmentation_event_id, 0x4800_008E);
+        let s = d.segmentation.unwrap();
+        assert!(s.program_segmentation_flag);
+        assert_eq!(s.segmentation_duration, Some(0x01A5_99B0));
+        assert_eq!(s.segmentation_upid_type, 0x08);
+        assert_eq!(s.segmentation_upid, [0, 0, 0, 0, 0x2C, 0xA0, 0xA1, 0x8A]);
+        assert_eq!(
+            s.segmentation_type,
+            SegmentationType::ProviderPlacementOpportunityStart
+        );
+        assert_eq!((s.segment_num, s.segments_expected), (2, 0));
+        assert_eq!(s.sub_segment, None);
+        assert_eq!(&round_trip(&section)[..], &bytes[..]);
+    }
+
+    #[test]
+    fn other_commands() {
+        round_trip(&SpliceInfoSection::new(SpliceCommand::SpliceNull));
+        round_trip(&SpliceInfoSection::new(SpliceCommand::BandwidthReservation));
+        round_trip(&SpliceInfoSection::new(SpliceCommand::PrivateCommand {
+            identifier: 0x1234_5678,
+            private_bytes: vec![1, 2, 3],
+        }));
+
+        let schedule = SpliceSchedule {
+            events: vec![
+                ScheduledSplice {
+                    splice_event_id: 1,
+                    event: None,
+                },
+                ScheduledSplice {
+                    splice_event_id: 2,
+                    event: Some(ScheduledSpliceEvent {
+                        out_of_network_indicator: true,
+                        program_splice_flag: false,
+                        utc_splice_time: None,
+                        components: vec![ScheduledSpliceComponent {
+                            component_tag: 3,
+                            utc_splice_time: 0x5000_0000,
+                        }],
+                        break_duration: Some(BreakDuration {
+                            auto_return: false,
+                            duration: 90_000 * 30,
+                        }),
+                        unique_program_id: 4,
+                        avail_num: 1,
+                        avails_expected: 2,
+                    }),
+                },
+            ],
+        };
+        let mut section = SpliceInfoSection::new(SpliceCommand::SpliceSchedule(schedule));
+        section
+            .descriptors
+            .push(SpliceDescriptor::Segmentation(SegmentationDescriptor {
+                segmentation_event_id: 5,
+                segmentation_event_id_compliance_indicator: true,
+                segmentation: Some(Segmentation {
+                    delivery_restrictions: Some(DeliveryRestrictions {
+                        web_delivery_allowed_flag: true,
+                        no_regional_blackout_flag: false,
+                        archive_allowed_flag: true,
+                        device_restrictions: 2,
+                    }),
+                    program_segmentation_flag: false,
+                    components: vec![SegmentationComponent {
+                        component_tag: 3,
+                        pts_offset: 1000,
+                    }],
+                    segmentation_duration: None,
+                    segmentation_upid_type: 0x0F,
+                    segmentation_upid: b"urn:example".to_vec(),
+                    segmentation_type: SegmentationType::DistributorAdBlockStart,
+                    segment_num: 1,
+                    segments_expected: 1,
+                    sub_segment: Some((1, 4)),
+                }),
+            }));
+        section
+            .descriptors
+            .push(SpliceDescriptor::Dtmf(DtmfDescriptor {
+                preroll: 50,
+                dtmf_chars: b"123*".to_vec(),
+            }));
+        section
+            .descriptors
+            .push(SpliceDescriptor::Time(TimeDescriptor {
+                tai_seconds: 0x1234_5678_9ABC,
+                tai_ns: 500,
+                utc_offset: 37,
+            }));
+        section.descriptors.push(SpliceDescriptor::Raw {
+            splice_descriptor_tag: 0x80,
+            identifier: 0x4142_4344,
+            data: vec![9; 3],
+        });
+        round_trip(&section);
+    }
+
+    #[test]
+    fn cue_out() {
+        let pts = Timestamp::new(Timestamp::MAX).unwrap();
+        let duration = BreakDuration {
+            auto_return: true,
+            duration: 90_000 * 60,
+        };
+        let mut section = SpliceInfoSection::new(SpliceCommand::SpliceInsert(
+            SpliceInsert::cue_out(10, Some(pts), Some(duration)),
+        ));
+        section.pts_adjustment = Timestamp::new(2).unwrap();
+        round_trip(&section);
+        assert_eq!(section.adjust_pts(pts).as_u64(), 1);
+
+        round_trip(&SpliceInfoSection::new(SpliceCommand::SpliceInsert(
+            SpliceInsert::cue_in(10, None),
+        )));
+
+        let mut insert = SpliceInsert::cue_out(11, None, None);
+        insert.event.as_mut().unwrap().splice_immediate_flag = false;
+        let section = SpliceInfoSection::new(SpliceCommand::SpliceInsert(insert));
+        assert!(section.write_to(&mut Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,326 @@
+use ts::{PacketFormat, TsPacket};
+
+/// Number of consecutive sync bytes required before the synchronizer locks onto a stream.
+const SYNC_LOCK_THRESHOLD: usize = 3;
+
+/// Byte-level packet synchronizer.
+///
+/// It buffers the bytes pushed into it and locates the packet boundaries by
+/// searching for sync bytes that appear at regular packet intervals.
+///
+/// Once it has locked onto a stream, every packet must start with a sync byte
+/// and be followed by another sync byte (unless the end of the stream is reached).
+/// If the condition is not satisfied, the synchronizer loses the lock and
+/// skips bytes until it finds `SYNC_LOCK_THRESHOLD` consecutive sync bytes again.
+#[derive(Debug)]
+pub(crate) struct PacketSynchronizer {
+    packet_size: usize,
+    sync_byte_offset: usize,
+    buf: Vec<u8>,
+    start: usize,
+    offset: u64,
+    packet_count: u64,
+    locked: bool,
+    skipped_bytes: u64,
+    pending_skipped_bytes: u64,
+}
+impl PacketSynchronizer {
+    pub fn new(format: PacketFormat) -> Self {
+        PacketSynchronizer {
+            packet_size: format.packet_size(),
+            sync_byte_offset: format.sync_byte_offset(),
+            buf: Vec::new(),
+            start: 0,
+            offset: 0,
+            packet_count: 0,
+            locked: false,
+            skipped_bytes: 0,
+            pending_skipped_bytes: 0,
+        }
+    }
+
+    /// Returns the total number of bytes skipped to (re)gain synchronization.
+    pub fn skipped_bytes(&self) -> u64 {
+        self.skipped_bytes
+    }
+
+    /// Returns the absolute byte offset of the last consumed packet.
+    pub fn packet_offset(&self) -> u64 {
+        self.offset - self.packet_size as u64
+    }
+
+    /// Returns the absolute byte offset of the next byte to be examined.
+    pub fn next_offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// Returns the number of the consumed packets (including the ones before `reset()`).
+    #[cfg(feature = "rayon")]
+    pub fn packet_count(&self) -> u64 {
+        self.packet_count
+    }
+
+    /// Returns the zero-based index of the last consumed packet.
+    pub fn packet_index(&self) -> u64 {
+        self.packet_count - 1
+    }
+
+    /// Discards the buffered bytes and restarts the synchronization at the given position.
+    ///
+    /// `offset` and `packet_index` are those of the next byte to be pushed.
+    pub fn reset(&mut self, offset: u64, packet_index: u64) {
+        self.buf.clear();
+        self.start = 0;
+        self.offset = offset;
+        self.packet_count = packet_index;
+        self.locked = false;
+        self.pending_skipped_bytes = 0;
+    }
+
+    /// Appends the given bytes to the internal buffer.
+    pub fn push(&mut self, bytes: &[u8]) {
+        if self.start > 0 {
+            self.buf.drain(..self.start);
+            self.start = 0;
+        }
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Returns `true` if a synchronized packet is available at the head of the buffer.
+    ///
+    /// `eos` should be `true` if no more bytes will be pushed.
+    /// In that case, the trailing bytes that do not form a complete packet are discarded.
+    pub fn is_packet_ready(&mut self, eos: bool) -> bool {
+        loop {
+            if self.locked {
+                let available = self.buf.len() - self.start;
+                if available < self.packet_size {
+                    if eos {
+                        self.skip(available);
+                    }
+                    return false;
+                }
+                let head = self.start + self.sync_byte_offset;
+                let is_next_synced = if available > self.packet_size + self.sync_byte_offset {
+                    self.buf[head + self.packet_size] == TsPacket::SYNC_BYTE
+                } else if eos {
+                    true
+                } else {
+                    return false;
+                };
+                if self.buf[head] == TsPacket::SYNC_BYTE && is_next_synced {
+                    return true;
+                }
+
+                log::debug!("Lost packet synchronization: offset={}", self.offset);
+                self.locked = false;
+                self.skip(1);
+            }
+
+            if !self.lock(eos) {
+                return false;
+            }
+        }
+    }
+
+    /// Consumes the packet at the head of the buffer.
+    ///
+    /// This must be called only after `is_packet_ready()` returned `true`.
+    pub fn consume_packet(&mut self) -> &[u8] {
+        debug_assert!(self.locked);
+        let start = self.start;
+        self.start += self.packet_size;
+        self.offset += self.packet_size as u64;
+        self.packet_count += 1;
+        &self.buf[start..self.start]
+    }
+
+    fn lock(&mut self, eos: bool) -> bool {
+        let available = self.buf.len() - self.start;
+        for i in 0..available.saturating_sub(self.sync_byte_offset) {
+            let head = self.start + i + self.sync_byte_offset;
+            if self.buf[head] != TsPacket::SYNC_BYTE {
+                continue;
+            }
+            if i + self.packet_size > available {
+                // The candidate packet is not complete yet.
+                if eos {
+                    break;
+                }
+                self.skip(i);
+                return false;
+            }
+
+            let mut is_candidate = true;
+            for k in 1..SYNC_LOCK_THRESHOLD {
+                let position = head + k * self.packet_size;
+                if position >= self.buf.len() {
+                    if eos {
+                        break;
+                    }
+                    self.skip(i);
+                    return false;
+                }
+                if self.buf[position] != TsPacket::SYNC_BYTE {
+                    is_candidate = false;
+                    break;
+                }
+            }
+            if is_candidate {
+                self.skip(i);
+                self.locked = true;
+                if self.pending_skipped_bytes > 0 {
+                    log::debug!(
+                        "Regained packet synchronization: offset={}, skipped_bytes={}",
+                        self.offset,
+                        self.pending_skipped_bytes
+                    );
+                    self.pending_skipped_bytes = 0;
+                }
+                return true;
+            }
+        }
+        if eos {
+            self.skip(available);
+        } else {
+            // The last bytes may be the extra header of the next packet.
+            self.skip(available.saturating_sub(self.sync_byte_offset));
+        }
+        false
+    }
+
+    fn skip(&mut self, size: usize) {
+        self.start += size;
+        self.offset += size as u64;
+        self.skipped_bytes += size as u64;
+        self.pending_skipped_bytes += size as u64;
+    }
+}
+
+/// Returns the offset of the first packet boundary at or after `offset` in `bytes`.
+///
+/// The boundary is located in the same way as `PacketSynchronizer` gains the synchronization.
+/// It returns the length of `bytes` if no boundary is found.
+#[cfg(feature = "rayon")]
+pub(crate) fn next_packet_boundary(bytes: &[u8], format: PacketFormat, offset: u64) -> u64 {
+    let len = bytes.len() as u64;
+    if offset >= len {
+        return len;
+    }
+
+    let mut sync = PacketSynchronizer::new(format);
+    sync.reset(offset, 0);
+    let window = format.packet_size() * (SYNC_LOCK_THRESHOLD + 1);
+    for piece in bytes[offset as usize..].chunks(window) {
+        sync.push(piece);
+        if sync.is_packet_ready(false) {
+            return sync.next_offset();
+        }
+    }
+    if sync.is_packet_ready(true) {
+        sync.next_offset()
+    } else {
+        len
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn packet(tag: u8) -> Vec<u8> {
+        let mut packet = vec![tag; TsPacket::SIZE];
+        packet[0] = TsPacket::SYNC_BYTE;
+        packet
+    }
+
+    fn read_all(sync: &mut PacketSynchronizer) -> Vec<Vec<u8>> {
+        let mut packets = Vec::new();
+        while sync.is_packet_ready(true) {
+            packets.push(sync.consume_packet().to_owned());
+        }
+        packets
+    }
+
+    #[test]
+    fn aligned_stream() {
+        let mut sync = PacketSynchronizer::new(PacketFormat::Ts);
+        for i in 0..4 {
+            sync.push(&packet(i));
+        }
+        assert_eq!(read_all(&mut sync), (0..4).map(packet).collect::<Vec<_>>());
+        assert_eq!(sync.skipped_bytes(), 0);
+    }
+
+    #[test]
+    fn leading_garbage() {
+        let mut sync = PacketSynchronizer::new(PacketFormat::Ts);
+        sync.push(&[0x47, 1, 2, 0x47, 3]);
+        for i in 0..4 {
+            sync.push(&packet(i));
+        }
+        assert_eq!(read_all(&mut sync), (0..4).map(packet).collect::<Vec<_>>());
+        assert_eq!(sync.skipped_bytes(), 5);
+    }
+
+    #[test]
+    fn dropped_byte() {
+        let mut sync = PacketSynchronizer::new(PacketFormat::Ts);
+        for i in 0..8 {
+            let mut bytes = packet(i);
+            if i == 3 {
+                bytes.remove(100);
+            }
+            sync.push(&bytes);
+        }
+        let packets = read_all(&mut sync);
+        let expected = (0..8).filter(|&i| i != 3).map(packet).collect::<Vec<_>>();
+        assert_eq!(packets, expected);
+        assert_eq!(sync.skipped_bytes(), TsPacket::SIZE as u64 - 1);
+    }
+
+    #[test]
+    fn incremental_push() {
+        let mut bytes = Vec::new();
+        for i in 0..4 {
+            bytes.extend_from_slice(&packet(i));
+        }
+
+        let mut sync = PacketSynchronizer::new(PacketFormat::Ts);
+        let mut packets = Vec::new();
+        for chunk in bytes.chunks(7) {
+            sync.push(chunk);
+            while sync.is_packet_ready(false) {
+                packets.push(sync.consume_packet().to_owned());
+            }
+        }
+        packets.extend(read_all(&mut sync));
+        assert_eq!(packets, (0..4).map(packet).collect::<Vec<_>>());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn packet_boundary() {
+        let mut bytes = vec![0x47, 1, 2];
+        for i in 0..4 {
+            bytes.extend_from_slice(&packet(i));
+        }
+        let size = TsPacket::SIZE as u64;
+        assert_eq!(next_packet_boundary(&bytes, PacketFormat::Ts, 0), 3);
+        assert_eq!(next_packet_boundary(&bytes, PacketFormat::Ts, 3), 3);
+        assert_eq!(next_packet_boundary(&bytes, PacketFormat::Ts, 4), 3 + size);
+        assert_eq!(next_packet_boundary(&bytes, PacketFormat::Ts, 4 + size * 3), 3 + size * 4);
+        assert_eq!(next_packet_boundary(&bytes, PacketFormat::Ts, 1000), 3 + size * 4);
+    }
+
+    #[test]
+    fn trailing_partial_packet() {
+        let mut sync = PacketSynchronizer::new(PacketFormat::Ts);
+        for i in 0..3 {
+            sync.push(&packet(i));
+        }
+        sync.push(&packet(3)[..50]);
+        assert_eq!(read_all(&mut sync), (0..3).map(packet).collect::<Vec<_>>());
+        assert_eq!(sync.skipped_bytes(), 50);
+    }
+}
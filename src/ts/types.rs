@@ -5,7 +5,8 @@ use std::io::{Read, Write};
 use std::ops::Deref;
 
 use time::Timestamp;
-use ts::TsPacket;
+use ts::reed_solomon;
+use ts::{TpExtraHeader, TsPacket};
 use {ErrorKind, Result};
 
 /// Packet Identifier.
@@ -18,6 +19,17 @@ impl Pid {
     /// PID of the Program Association Table (PAT) packet.
     pub const PAT: u16 = 0;
 
+    /// PID of the Conditional Access Table (CAT) packet.
+    pub const CAT: u16 = 1;
+
+    /// PID of the Network Information Table (NIT) packet of DVB.
+    ///
+    /// Note that the PAT may specify another PID as the network PID (see `Pat::network_pid()`).
+    pub const NIT: u16 = 0x10;
+
+    /// PID of the Service Description Table (SDT) packet of DVB.
+    pub const SDT: u16 = 0x11;
+
     /// PID of the null packet.
     pub const NULL: u16 = 0x1FFF;
 
@@ -194,7 +206,7 @@ impl Bytes {
 
         let len = bytes.len();
         let mut buf = [0; Self::MAX_SIZE];
-        (&mut buf[..len]).copy_from_slice(bytes);
+        buf[..len].copy_from_slice(bytes);
         Ok(Bytes { buf, len })
     }
 
@@ -244,6 +256,42 @@ impl Hash for Bytes {
     }
 }
 
+/// Packet format of a transport stream.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PacketFormat {
+    /// 188-byte TS packets.
+    #[default]
+    Ts,
+
+    /// 192-byte packets used by Blu-ray and AVCHD (BDAV MPEG-2 transport stream, `.m2ts`).
+    ///
+    /// Each TS packet is preceded by a 4-byte `TpExtraHeader`.
+    M2ts,
+
+    /// 204-byte packets used by DVB interfaces such as DVB-ASI.
+    ///
+    /// Each TS packet is followed by 16 bytes of Reed-Solomon RS(204,188) parity.
+    Dvb,
+}
+impl PacketFormat {
+    /// Returns the size of a packet in bytes.
+    pub fn packet_size(&self) -> usize {
+        match *self {
+            PacketFormat::Ts => TsPacket::SIZE,
+            PacketFormat::M2ts => TpExtraHeader::SIZE + TsPacket::SIZE,
+            PacketFormat::Dvb => reed_solomon::CODEWORD_SIZE,
+        }
+    }
+
+    /// Returns the position of the sync byte in a packet.
+    pub fn sync_byte_offset(&self) -> usize {
+        match *self {
+            PacketFormat::Ts | PacketFormat::Dvb => 0,
+            PacketFormat::M2ts => TpExtraHeader::SIZE,
+        }
+    }
+}
+
 /// Transport scrambling control.
 #[allow(missing_docs)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -1,7 +1,19 @@
+#[cfg(feature = "tokio")]
+use std::future::Future;
+#[cfg(feature = "tokio")]
+use std::io;
+use std::collections::HashMap;
 use std::io::Write;
-use ts::TsPacket;
+#[cfg(feature = "tokio")]
+use std::pin::Pin;
+#[cfg(feature = "tokio")]
+use std::task::{Context, Poll};
+#[cfg(feature = "tokio")]
+use tokio::io::AsyncWrite;
+use ts::reed_solomon::ReedSolomon;
+use ts::{ArrivalTimeClock, ContinuityCounter, PacketFormat, Pid, TpExtraHeader, TsPacket};
 
-use Result;
+use {ErrorKind, Result};
 
 /// The `ReadTsPacket` trait allows for writing TS packets to a destination.
 pub trait WriteTsPacket {
@@ -9,15 +21,141 @@ pub trait WriteTsPacket {
     fn write_ts_packet(&mut self, packet: &TsPacket) -> Result<()>;
 }
 
+/// The `AsyncWriteTsPacket` trait allows for writing TS packets to an asynchronous destination.
+#[cfg(feature = "tokio")]
+pub trait AsyncWriteTsPacket {
+    /// Encodes a TS packet into the internal buffer of the writer.
+    ///
+    /// The buffered bytes are written by `poll_write_buffered()` or `poll_flush()`.
+    fn start_write_ts_packet(&mut self, packet: &TsPacket) -> Result<()>;
+
+    /// Attempts to write all the buffered bytes to the destination.
+    fn poll_write_buffered(&mut self, cx: &mut Context) -> Poll<Result<()>>;
+
+    /// Attempts to write all the buffered bytes and flush the destination.
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll<Result<()>>;
+
+    /// Returns a future that writes a TS packet.
+    fn write_ts_packet_async<'a>(
+        &'a mut self,
+        packet: &'a TsPacket,
+    ) -> WriteTsPacketFuture<'a, Self>
+    where
+        Self: Sized,
+    {
+        WriteTsPacketFuture {
+            writer: self,
+            packet: Some(packet),
+        }
+    }
+
+    /// Returns a future that flushes the destination.
+    fn flush_async(&mut self) -> FlushFuture<'_, Self>
+    where
+        Self: Sized,
+    {
+        FlushFuture { writer: self }
+    }
+}
+
+/// Future returned by `AsyncWriteTsPacket::write_ts_packet_async()`.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct WriteTsPacketFuture<'a, W: 'a> {
+    writer: &'a mut W,
+    packet: Option<&'a TsPacket>,
+}
+#[cfg(feature = "tokio")]
+impl<'a, W: AsyncWriteTsPacket> Future for WriteTsPacketFuture<'a, W> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        if let Some(packet) = this.packet.take() {
+            track!(this.writer.start_write_ts_packet(packet))?;
+        }
+        this.writer.poll_write_buffered(cx)
+    }
+}
+
+/// Future returned by `AsyncWriteTsPacket::flush_async()`.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct FlushFuture<'a, W: 'a> {
+    writer: &'a mut W,
+}
+#[cfg(feature = "tokio")]
+impl<'a, W: AsyncWriteTsPacket> Future for FlushFuture<'a, W> {
+    type Output = Result<()>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        self.get_mut().writer.poll_flush(cx)
+    }
+}
+
 /// TS packet writer.
+///
+/// If the `tokio` feature is enabled, it also implements `AsyncWriteTsPacket`
+/// for the streams that implement `tokio::io::AsyncWrite`.
 #[derive(Debug)]
 pub struct TsPacketWriter<W> {
     stream: W,
+    format: PacketFormat,
+    arrival_time_clock: Option<ArrivalTimeClock>,
+    reed_solomon: Option<ReedSolomon>,
+    continuity_counters: HashMap<Pid, ContinuityCounter>,
+    #[cfg(feature = "tokio")]
+    pending: Vec<u8>,
 }
-impl<W: Write> TsPacketWriter<W> {
+impl<W> TsPacketWriter<W> {
     /// Makes a new `TsPacketWriter` instance.
     pub fn new(stream: W) -> Self {
-        TsPacketWriter { stream }
+        Self::with_format(stream, PacketFormat::Ts)
+    }
+
+    /// Makes a new `TsPacketWriter` instance that writes packets of the given format.
+    ///
+    /// In the case of `PacketFormat::Dvb`,
+    /// the writer calculates the Reed-Solomon parity bytes of each packet.
+    pub fn with_format(stream: W, format: PacketFormat) -> Self {
+        let reed_solomon = if format == PacketFormat::Dvb {
+            Some(ReedSolomon::new())
+        } else {
+            None
+        };
+        TsPacketWriter {
+            stream,
+            format,
+            arrival_time_clock: None,
+            reed_solomon,
+            continuity_counters: HashMap::new(),
+            #[cfg(feature = "tokio")]
+            pending: Vec::new(),
+        }
+    }
+
+    /// Returns the packet format of the stream.
+    pub fn format(&self) -> PacketFormat {
+        self.format
+    }
+
+    /// Sets the clock used to generate the extra headers of M2TS packets.
+    ///
+    /// The clock is used only for the packets that do not have their own `tp_extra_header`,
+    /// and for the subsequent packets of a table that is split into several packets.
+    /// Without the clock, the subsequent packets get the arrival time stamp of
+    /// the previous packet plus one.
+    pub fn set_arrival_time_clock(&mut self, clock: ArrivalTimeClock) {
+        self.arrival_time_clock = Some(clock);
+    }
+
+    /// Returns the continuity counter of the last packet written with the given PID.
+    ///
+    /// When a table is split into several packets, the counter is incremented for each of
+    /// the subsequent packets. So the next packet of the PID should have the incremented value
+    /// of this counter instead of the one of the last given packet.
+    pub fn last_continuity_counter(&self, pid: Pid) -> Option<ContinuityCounter> {
+        self.continuity_counters.get(&pid).cloned()
     }
 
     /// Returns a reference to the underlaying byte stream.
@@ -29,9 +167,91 @@ impl<W: Write> TsPacketWriter<W> {
     pub fn into_stream(self) -> W {
         self.stream
     }
+
+    /// Encodes a packet into the bytes to be written to the stream.
+    fn encode_packet(&mut self, packet: &TsPacket) -> Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(TsPacket::SIZE);
+        let last_counter = track!(packet.write_to(&mut buf))?;
+        self.continuity_counters
+            .insert(packet.header.pid, last_counter);
+
+        let mut encoded = Vec::with_capacity(buf.len());
+        let mut last_header: Option<TpExtraHeader> = None;
+        for bytes in buf.chunks(TsPacket::SIZE) {
+            if self.format == PacketFormat::M2ts {
+                let header = match (last_header, packet.tp_extra_header) {
+                    (None, Some(header)) => header,
+                    (Some(last), Some(_)) if self.arrival_time_clock.is_none() => {
+                        // The subsequent packets of a split table must not share
+                        // the arrival time stamp of the first one
+                        let ats = (last.arrival_time_stamp() + 1)
+                            & TpExtraHeader::MAX_ARRIVAL_TIME_STAMP;
+                        track!(TpExtraHeader::new(last.copy_permission_indicator(), ats))?
+                    }
+                    _ => {
+                        let clock = track_assert_some!(
+                            self.arrival_time_clock.as_mut(),
+                            ErrorKind::InvalidInput,
+                            "Neither a TP extra header nor an arrival time clock is available"
+                        );
+                        clock.next_header()
+                    }
+                };
+                track!(header.write_to(&mut encoded))?;
+                last_header = Some(header);
+            }
+            encoded.extend_from_slice(bytes);
+            if let Some(ref rs) = self.reed_solomon {
+                encoded.extend_from_slice(&rs.encode(bytes));
+            }
+        }
+        Ok(encoded)
+    }
 }
 impl<W: Write> WriteTsPacket for TsPacketWriter<W> {
+    /// Writes a TS packet.
+    ///
+    /// If the PSI table of the packet is too large to fit into a single packet,
+    /// the table is split into several packets (and sections if the table allows).
+    /// The continuity counter is incremented for each of the subsequent packets
+    /// (see `TsPacketWriter::last_continuity_counter()`).
     fn write_ts_packet(&mut self, packet: &TsPacket) -> Result<()> {
-        track!(packet.write_to(&mut self.stream))
+        let bytes = track!(self.encode_packet(packet))?;
+        track_io!(self.stream.write_all(&bytes))?;
+        Ok(())
+    }
+}
+#[cfg(feature = "tokio")]
+impl<W: AsyncWrite + Unpin> AsyncWriteTsPacket for TsPacketWriter<W> {
+    fn start_write_ts_packet(&mut self, packet: &TsPacket) -> Result<()> {
+        let bytes = track!(self.encode_packet(packet))?;
+        self.pending.extend_from_slice(&bytes);
+        Ok(())
+    }
+
+    fn poll_write_buffered(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        while !self.pending.is_empty() {
+            let size = match Pin::new(&mut self.stream).poll_write(cx, &self.pending) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(result) => track_io!(result)?,
+            };
+            if size == 0 {
+                let e = io::Error::new(io::ErrorKind::WriteZero, "Failed to write whole buffer");
+                track_io!(Err(e))?;
+            }
+            self.pending.drain(..size);
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_flush(&mut self, cx: &mut Context) -> Poll<Result<()>> {
+        match self.poll_write_buffered(cx) {
+            Poll::Ready(Ok(())) => {}
+            other => return other,
+        }
+        match Pin::new(&mut self.stream).poll_flush(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(result) => Poll::Ready(track_io!(result)),
+        }
     }
 }